@@ -12,11 +12,11 @@ use crate::response::MsgInstantiateContractResponse;
 use astroport::asset::{addr_validate_to_lower, format_lp_token_name, Asset, AssetInfo, PairInfo};
 use astroport::factory::PairType;
 use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
-use astroport::pair::{ConfigResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
 use astroport::pair::{
-    CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse,
-    QueryMsg, ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
+    log2_ratio, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    PoolResponse, QueryMsg, ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
 };
+use astroport::pair::{ConfigResponse, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
 use astroport::querier::{query_factory_config, query_fee_info, query_supply};
 use astroport::{token::InstantiateMsg as TokenInstantiateMsg, U256};
 use cw2::set_contract_version;
@@ -57,6 +57,9 @@ pub fn instantiate(
         block_time_last: 0,
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
+        log_price_cumulative_last: Uint128::zero(),
+        reserve0_cumulative_last: Uint128::zero(),
+        reserve1_cumulative_last: Uint128::zero(),
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -321,11 +324,20 @@ pub fn provide_liquidity(
     )?);
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
-        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
     {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -404,11 +416,20 @@ pub fn withdraw_liquidity(
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
-        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
     {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -555,11 +576,20 @@ pub fn swap(
     }
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
-        accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(env, &config, pools[0].amount, pools[1].amount)?
     {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -588,7 +618,7 @@ pub fn accumulate_prices(
     config: &Config,
     x: Uint128,
     y: Uint128,
-) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+) -> StdResult<Option<(Uint128, Uint128, u64, Uint128)>> {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
         return Ok(None);
@@ -600,6 +630,7 @@ pub fn accumulate_prices(
 
     let mut pcl0 = config.price0_cumulative_last;
     let mut pcl1 = config.price1_cumulative_last;
+    let mut lpcl = config.log_price_cumulative_last;
 
     if !x.is_zero() && !y.is_zero() {
         let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
@@ -613,9 +644,32 @@ pub fn accumulate_prices(
                 .checked_mul(price_precision)?
                 .multiply_ratio(x, y),
         );
+        // signed value stored in two's complement
+        let log_price =
+            ((block_time - config.block_time_last) as i128).wrapping_mul(log2_ratio(y, x));
+        lpcl = lpcl.wrapping_add(Uint128::new(log_price as u128));
     };
 
-    Ok(Some((pcl0, pcl1, block_time)))
+    Ok(Some((pcl0, pcl1, block_time, lpcl)))
+}
+
+/// Returns the reserves cumulative values accumulated with the reserves held since the last accumulation
+pub fn accumulate_reserves(
+    config: &Config,
+    block_time: u64,
+    x: Uint128,
+    y: Uint128,
+) -> (Uint128, Uint128) {
+    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+
+    (
+        config
+            .reserve0_cumulative_last
+            .wrapping_add(time_elapsed.wrapping_mul(x)),
+        config
+            .reserve1_cumulative_last
+            .wrapping_add(time_elapsed.wrapping_mul(y)),
+    )
 }
 
 pub fn calculate_maker_fee(
@@ -765,12 +819,23 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
 
     let mut price0_cumulative_last = config.price0_cumulative_last;
     let mut price1_cumulative_last = config.price1_cumulative_last;
-
-    if let Some((price0_cumulative_new, price1_cumulative_new, _)) =
-        accumulate_prices(env, &config, assets[0].amount, assets[1].amount)?
+    let mut log_price_cumulative_last = config.log_price_cumulative_last;
+    let mut reserve0_cumulative_last = config.reserve0_cumulative_last;
+    let mut reserve1_cumulative_last = config.reserve1_cumulative_last;
+
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(env, &config, assets[0].amount, assets[1].amount)?
     {
         price0_cumulative_last = price0_cumulative_new;
         price1_cumulative_last = price1_cumulative_new;
+        log_price_cumulative_last = log_price_cumulative_new;
+        let reserves = accumulate_reserves(&config, block_time, assets[0].amount, assets[1].amount);
+        reserve0_cumulative_last = reserves.0;
+        reserve1_cumulative_last = reserves.1;
     }
 
     let resp = CumulativePricesResponse {
@@ -778,6 +843,9 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
         total_share,
         price0_cumulative_last,
         price1_cumulative_last,
+        log_price_cumulative_last,
+        reserve0_cumulative_last,
+        reserve1_cumulative_last,
     };
 
     Ok(resp)
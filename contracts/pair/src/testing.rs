@@ -1,7 +1,7 @@
 use crate::contract::reply;
 use crate::contract::{
-    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, query_pair_info,
-    query_pool, query_reverse_simulation, query_share, query_simulation,
+    accumulate_prices, accumulate_reserves, assert_max_spread, compute_swap, execute, instantiate,
+    query_pair_info, query_pool, query_reverse_simulation, query_share, query_simulation,
 };
 use crate::error::ContractError;
 use crate::mock_querier::mock_dependencies;
@@ -12,7 +12,7 @@ use astroport::factory::PairType;
 
 use astroport::pair::{
     Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse, TWAP_PRECISION,
+    SimulationResponse, LOG_PRICE_ONE, TWAP_PRECISION,
 };
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
@@ -1260,6 +1260,7 @@ fn test_accumulate_prices() {
         block_time_last: u64,
         price_x: u128,
         price_y: u128,
+        log_price: u128,
         is_some: bool,
     }
 
@@ -1277,8 +1278,9 @@ fn test_accumulate_prices() {
             },
             Result {
                 block_time_last: 1000,
-                price_x: 2000, // 500/250*1000
-                price_y: 500,  // 250/500*1000
+                price_x: 2000,   // 500/250*1000
+                price_y: 500,    // 250/500*1000
+                log_price: 1000, // log2(500/250)*1000
                 is_some: true,
             },
         ),
@@ -1296,6 +1298,7 @@ fn test_accumulate_prices() {
                 block_time_last: 1000,
                 price_x: 1,
                 price_y: 2,
+                log_price: 0,
                 is_some: false,
             },
         ),
@@ -1310,8 +1313,9 @@ fn test_accumulate_prices() {
             },
             Result {
                 block_time_last: 1500,
-                price_x: 1500, // 500 + (500/250*500)
-                price_y: 2250, // 2000 + (250/500*500)
+                price_x: 1500,  // 500 + (500/250*500)
+                price_y: 2250,  // 2000 + (250/500*500)
+                log_price: 500, // log2(500/250)*500
                 is_some: true,
             },
         ),
@@ -1341,6 +1345,9 @@ fn test_accumulate_prices() {
                 block_time_last: case.block_time_last,
                 price0_cumulative_last: Uint128::new(case.last0),
                 price1_cumulative_last: Uint128::new(case.last1),
+                log_price_cumulative_last: Uint128::zero(),
+                reserve0_cumulative_last: Uint128::zero(),
+                reserve1_cumulative_last: Uint128::zero(),
             },
             Uint128::new(case.x_amount),
             Uint128::new(case.y_amount),
@@ -1351,6 +1358,7 @@ fn test_accumulate_prices() {
 
         if let Some(config) = config {
             assert_eq!(config.2, result.block_time_last);
+            assert_eq!(config.3, Uint128::new(result.log_price * LOG_PRICE_ONE));
             assert_eq!(
                 config.0 / Uint128::from(price_precision),
                 Uint128::new(result.price_x)
@@ -1363,6 +1371,38 @@ fn test_accumulate_prices() {
     }
 }
 
+#[test]
+fn test_accumulate_reserves() {
+    let config = Config {
+        pair_info: PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+            ],
+            contract_addr: Addr::unchecked("pair"),
+            liquidity_token: Addr::unchecked("lp_token"),
+            pair_type: PairType::Xyk {},
+        },
+        factory_addr: Addr::unchecked("factory"),
+        block_time_last: 1000,
+        price0_cumulative_last: Uint128::zero(),
+        price1_cumulative_last: Uint128::zero(),
+        log_price_cumulative_last: Uint128::zero(),
+        reserve0_cumulative_last: Uint128::MAX - Uint128::new(99),
+        reserve1_cumulative_last: Uint128::new(1000),
+    };
+
+    // the reserves held since the last accumulation are added, the accumulators wrap around
+    let (reserve0, reserve1) =
+        accumulate_reserves(&config, 1010, Uint128::new(20), Uint128::new(30));
+    assert_eq!(reserve0, Uint128::new(100));
+    assert_eq!(reserve1, Uint128::new(1300));
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
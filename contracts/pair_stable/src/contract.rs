@@ -17,8 +17,8 @@ use astroport::factory::PairType;
 
 use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
 use astroport::pair::{
-    ConfigResponse, InstantiateMsg, StablePoolParams, StablePoolUpdateParams, DEFAULT_SLIPPAGE,
-    MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
+    log2_ratio, ConfigResponse, InstantiateMsg, StablePoolParams, StablePoolUpdateParams,
+    DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
 };
 
 use astroport::pair::{
@@ -78,6 +78,9 @@ pub fn instantiate(
         block_time_last: 0,
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
+        log_price_cumulative_last: Uint128::zero(),
+        reserve0_cumulative_last: Uint128::zero(),
+        reserve1_cumulative_last: Uint128::zero(),
         init_amp: params.amp * AMP_PRECISION,
         init_amp_time: env.block.time.seconds(),
         next_amp: params.amp * AMP_PRECISION,
@@ -387,7 +390,12 @@ pub fn provide_liquidity(
     )?);
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(
         env,
         &config,
         pools[0].amount,
@@ -395,8 +403,13 @@ pub fn provide_liquidity(
         pools[1].amount,
         token_precision_1,
     )? {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -475,7 +488,12 @@ pub fn withdraw_liquidity(
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(
         env,
         &config,
         pools[0].amount,
@@ -483,8 +501,13 @@ pub fn withdraw_liquidity(
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -638,7 +661,12 @@ pub fn swap(
     }
 
     // Accumulate prices for oracle
-    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) = accumulate_prices(
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(
         env,
         &config,
         pools[0].amount,
@@ -646,8 +674,13 @@ pub fn swap(
         pools[1].amount,
         query_token_precision(&deps.querier, pools[1].info.clone())?,
     )? {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
         config.block_time_last = block_time;
         CONFIG.save(deps.storage, &config)?;
     }
@@ -678,7 +711,7 @@ pub fn accumulate_prices(
     x_precision: u8,
     y: Uint128,
     y_precision: u8,
-) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+) -> StdResult<Option<(Uint128, Uint128, u64, Uint128)>> {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
         return Ok(None);
@@ -694,38 +727,55 @@ pub fn accumulate_prices(
 
     let mut pcl0 = config.price0_cumulative_last;
     let mut pcl1 = config.price1_cumulative_last;
+    let mut lpcl = config.log_price_cumulative_last;
 
     if !x.is_zero() && !y.is_zero() {
-        let current_amp = compute_current_amp(config, &env)?;
+        // the elapsed time is priced with the amp at its start, so the prices accumulated
+        // up to any time in between don't depend on when they are computed during an amp change
+        let current_amp = compute_amp_at(config, config.block_time_last)?;
+        let one = adjust_precision(Uint128::new(1), 0, greater_precision)?;
+        let price0 =
+            Uint128::new(calc_amount(x.u128(), y.u128(), one.u128(), current_amp).unwrap());
         pcl0 = config.price0_cumulative_last.wrapping_add(adjust_precision(
-            time_elapsed.checked_mul(Uint128::new(
-                calc_amount(
-                    x.u128(),
-                    y.u128(),
-                    adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
-                    current_amp,
-                )
-                .unwrap(),
-            ))?,
+            time_elapsed.checked_mul(price0)?,
             greater_precision,
             TWAP_PRECISION,
         )?);
         pcl1 = config.price1_cumulative_last.wrapping_add(adjust_precision(
             time_elapsed.checked_mul(Uint128::new(
-                calc_amount(
-                    y.u128(),
-                    x.u128(),
-                    adjust_precision(Uint128::new(1), 0, greater_precision)?.u128(),
-                    current_amp,
-                )
-                .unwrap(),
+                calc_amount(y.u128(), x.u128(), one.u128(), current_amp).unwrap(),
             ))?,
             greater_precision,
             TWAP_PRECISION,
-        )?)
+        )?);
+        if !price0.is_zero() {
+            // signed value stored in two's complement
+            let log_price = ((block_time - config.block_time_last) as i128)
+                .wrapping_mul(log2_ratio(price0, one));
+            lpcl = lpcl.wrapping_add(Uint128::new(log_price as u128));
+        }
     };
 
-    Ok(Some((pcl0, pcl1, block_time)))
+    Ok(Some((pcl0, pcl1, block_time, lpcl)))
+}
+
+/// Returns the reserves cumulative values accumulated with the reserves held since the last accumulation
+pub fn accumulate_reserves(
+    config: &Config,
+    block_time: u64,
+    x: Uint128,
+    y: Uint128,
+) -> (Uint128, Uint128) {
+    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+
+    (
+        config
+            .reserve0_cumulative_last
+            .wrapping_add(time_elapsed.wrapping_mul(x)),
+        config
+            .reserve1_cumulative_last
+            .wrapping_add(time_elapsed.wrapping_mul(y)),
+    )
 }
 
 pub fn calculate_maker_fee(
@@ -884,8 +934,16 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
 
     let mut price0_cumulative_last = config.price0_cumulative_last;
     let mut price1_cumulative_last = config.price1_cumulative_last;
-
-    if let Some((price0_cumulative_new, price1_cumulative_new, _)) = accumulate_prices(
+    let mut log_price_cumulative_last = config.log_price_cumulative_last;
+    let mut reserve0_cumulative_last = config.reserve0_cumulative_last;
+    let mut reserve1_cumulative_last = config.reserve1_cumulative_last;
+
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(
         env,
         &config,
         assets[0].amount,
@@ -895,6 +953,10 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
     )? {
         price0_cumulative_last = price0_cumulative_new;
         price1_cumulative_last = price1_cumulative_new;
+        log_price_cumulative_last = log_price_cumulative_new;
+        let reserves = accumulate_reserves(&config, block_time, assets[0].amount, assets[1].amount);
+        reserve0_cumulative_last = reserves.0;
+        reserve1_cumulative_last = reserves.1;
     }
 
     let resp = CumulativePricesResponse {
@@ -902,6 +964,9 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
         total_share,
         price0_cumulative_last,
         price1_cumulative_last,
+        log_price_cumulative_last,
+        reserve0_cumulative_last,
+        reserve1_cumulative_last,
     };
 
     Ok(resp)
@@ -1082,13 +1147,38 @@ pub fn update_config(
     info: MessageInfo,
     params: Binary,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    let mut config = CONFIG.load(deps.storage)?;
     let factory_config = query_factory_config(&deps.querier, config.factory_addr.clone())?;
 
     if info.sender != factory_config.owner {
         return Err(ContractError::Unauthorized {});
     }
 
+    // Accumulate prices for oracle with the amp in effect until now
+    let (pools, _) = pool_info(deps.as_ref(), config.clone())?;
+    if let Some((
+        price0_cumulative_new,
+        price1_cumulative_new,
+        block_time,
+        log_price_cumulative_new,
+    )) = accumulate_prices(
+        env.clone(),
+        &config,
+        pools[0].amount,
+        query_token_precision(&deps.querier, pools[0].info.clone())?,
+        pools[1].amount,
+        query_token_precision(&deps.querier, pools[1].info.clone())?,
+    )? {
+        let (reserve0_cumulative_new, reserve1_cumulative_new) =
+            accumulate_reserves(&config, block_time, pools[0].amount, pools[1].amount);
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.log_price_cumulative_last = log_price_cumulative_new;
+        config.reserve0_cumulative_last = reserve0_cumulative_new;
+        config.reserve1_cumulative_last = reserve1_cumulative_new;
+        config.block_time_last = block_time;
+    }
+
     match from_binary::<StablePoolUpdateParams>(&params)? {
         StablePoolUpdateParams::StartChangingAmp {
             next_amp,
@@ -1156,7 +1246,13 @@ fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<(
 
 /// Compute actual amplification coefficient (A)
 fn compute_current_amp(config: &Config, env: &Env) -> StdResult<u64> {
-    let block_time = env.block.time.seconds();
+    compute_amp_at(config, env.block.time.seconds())
+}
+
+/// Compute amplification coefficient (A) at the given time, the initial amp is used for
+/// times before the start of the current amp change
+fn compute_amp_at(config: &Config, time: u64) -> StdResult<u64> {
+    let block_time = time.max(config.init_amp_time);
 
     if block_time < config.next_amp_time {
         let elapsed_time =
@@ -11,6 +11,12 @@ pub struct Config {
     pub block_time_last: u64,
     pub price0_cumulative_last: Uint128,
     pub price1_cumulative_last: Uint128,
+    #[serde(default)]
+    pub log_price_cumulative_last: Uint128,
+    #[serde(default)]
+    pub reserve0_cumulative_last: Uint128,
+    #[serde(default)]
+    pub reserve1_cumulative_last: Uint128,
     pub init_amp: u64,
     pub init_amp_time: u64,
     pub next_amp: u64,
@@ -12,7 +12,7 @@ use astroport::asset::{Asset, AssetInfo, PairInfo};
 
 use astroport::pair::{
     Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse, StablePoolParams, TWAP_PRECISION,
+    SimulationResponse, StablePoolParams, LOG_PRICE_ONE, TWAP_PRECISION,
 };
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
@@ -1286,6 +1286,9 @@ fn test_accumulate_prices() {
                 block_time_last: case.block_time_last,
                 price0_cumulative_last: Uint128::new(case.last0),
                 price1_cumulative_last: Uint128::new(case.last1),
+                log_price_cumulative_last: Uint128::zero(),
+                reserve0_cumulative_last: Uint128::zero(),
+                reserve1_cumulative_last: Uint128::zero(),
                 init_amp: 100 * AMP_PRECISION,
                 init_amp_time: env.block.time.seconds(),
                 next_amp: 100 * AMP_PRECISION,
@@ -1310,10 +1313,85 @@ fn test_accumulate_prices() {
                 config.1 / Uint128::from(price_precision),
                 Uint128::new(result.cumulative_price_y)
             );
+
+            // the log price accumulator averages to the same price
+            let elapsed = (case.block_time - case.block_time_last) as f64;
+            let log_price_average =
+                config.3.u128() as i128 as f64 / (elapsed * LOG_PRICE_ONE as f64);
+            let price_average =
+                (config.0.u128() - case.last0) as f64 / (elapsed * price_precision as f64);
+            assert!((2f64.powf(log_price_average) - price_average).abs() < 1e-5);
         }
     }
 }
 
+#[test]
+fn test_accumulate_prices_amp_change() {
+    // amp goes from 100 to 200 between 0 and 1000, last accumulation at 100
+    let config = Config {
+        pair_info: PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+            ],
+            contract_addr: Addr::unchecked("pair"),
+            liquidity_token: Addr::unchecked("lp_token"),
+            pair_type: PairType::Stable {},
+        },
+        factory_addr: Addr::unchecked("factory"),
+        block_time_last: 100,
+        price0_cumulative_last: Uint128::zero(),
+        price1_cumulative_last: Uint128::zero(),
+        log_price_cumulative_last: Uint128::zero(),
+        reserve0_cumulative_last: Uint128::zero(),
+        reserve1_cumulative_last: Uint128::zero(),
+        init_amp: 100 * AMP_PRECISION,
+        init_amp_time: 0,
+        next_amp: 200 * AMP_PRECISION,
+        next_amp_time: 1000,
+    };
+
+    let accumulate = |block_time: u64| {
+        accumulate_prices(
+            mock_env_with_block_time(block_time),
+            &config,
+            Uint128::new(250_000000),
+            6,
+            Uint128::new(500_000000),
+            6,
+        )
+        .unwrap()
+        .unwrap()
+    };
+
+    // the elapsed time is priced with the amp at its start wherever it ends
+    let (price0_500, price1_500, _, log_price_500) = accumulate(500);
+    let (price0_900, price1_900, _, log_price_900) = accumulate(900);
+    assert_eq!(price0_500 * Uint128::new(2), price0_900);
+    assert_eq!(price1_500 * Uint128::new(2), price1_900);
+    assert_eq!(log_price_500 * Uint128::new(2), log_price_900);
+
+    // the price of the scarce asset is closer to one with a higher amp
+    let (price0_after, _, _, _) = accumulate_prices(
+        mock_env_with_block_time(1500),
+        &Config {
+            block_time_last: 1100,
+            ..config.clone()
+        },
+        Uint128::new(250_000000),
+        6,
+        Uint128::new(500_000000),
+        6,
+    )
+    .unwrap()
+    .unwrap();
+    assert!(price0_after < price0_500);
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
@@ -3,7 +3,10 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use astroport::oracle::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use astroport::oracle::{
+    ConsultLiquidityResponse, ConsultPathResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    QueryMsg,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,4 +18,6 @@ fn main() {
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(ConsultPathResponse), &out_dir);
+    export_schema(&schema_for!(ConsultLiquidityResponse), &out_dir);
 }
@@ -1,103 +1,259 @@
 use crate::error::ContractError;
+use crate::migration;
 use crate::querier::{query_cumulative_prices, query_pair_info, query_prices};
-use crate::state::{Config, PriceCumulativeLast, CONFIG, PRICE_LAST};
-use astroport::asset::{addr_validate_to_lower, Asset, AssetInfo};
-use astroport::oracle::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use astroport::pair::TWAP_PRECISION;
+use crate::state::{
+    pair_key, read_observations, store_observation, Config, Observation, PriceCumulativeLast,
+    CONFIG, PAIRS, PRICE_LAST,
+};
+use astroport::asset::{addr_validate_to_lower, Asset, AssetInfo, PairInfo};
+use astroport::factory::PairType;
+use astroport::oracle::{
+    ConsultLiquidityResponse, ConsultPathResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    QueryMsg, DEFAULT_MAX_OBSERVATIONS, DEFAULT_PERIOD, MAX_OBSERVATIONS_LIMIT,
+};
+use astroport::pair::{LOG_PRICE_ONE, TWAP_PRECISION};
 use astroport::querier::query_token_precision;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Uint128,
+    attr, entry_point, to_binary, Addr, Attribute, Binary, Deps, DepsMut, Env, MessageInfo,
+    Response, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "astroport-oracle";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-const PERIOD: u64 = 86400;
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    msg.asset_infos[0].check(deps.api)?;
-    msg.asset_infos[1].check(deps.api)?;
-
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let factory_contract = addr_validate_to_lower(deps.api, msg.factory_contract.as_ref())?;
-    let pair_info = query_pair_info(
-        &deps.querier,
-        factory_contract.clone(),
-        msg.asset_infos.clone(),
+    let config = build_config(
+        info.sender,
+        addr_validate_to_lower(deps.api, msg.factory_contract.as_ref())?,
+        msg.period,
+        msg.max_observations,
+        msg.max_age,
     )?;
-
-    let config = Config {
-        owner: info.sender,
-        factory: factory_contract,
-        asset_infos: msg.asset_infos,
-        pair: pair_info.clone(),
-    };
     CONFIG.save(deps.storage, &config)?;
-    let prices = query_cumulative_prices(&deps.querier, pair_info.contract_addr)?;
 
-    let price = PriceCumulativeLast {
-        price0_cumulative_last: prices.price0_cumulative_last,
-        price1_cumulative_last: prices.price1_cumulative_last,
-        price_0_average: Decimal256::zero(),
-        price_1_average: Decimal256::zero(),
-        block_timestamp_last: env.block.time.seconds(),
-    };
-    PRICE_LAST.save(deps.storage, &price)?;
+    for asset_infos in msg.pairs {
+        register_pair(deps.branch(), &env, &config, asset_infos)?;
+    }
+
     Ok(Response::default())
 }
 
+/// Fills the config with the default limits and validates it
+pub(crate) fn build_config(
+    owner: Addr,
+    factory: Addr,
+    period: Option<u64>,
+    max_observations: Option<u32>,
+    max_age: Option<u64>,
+) -> Result<Config, ContractError> {
+    let period = period.unwrap_or(DEFAULT_PERIOD);
+    if period == 0 {
+        return Err(ContractError::InvalidPeriod {});
+    }
+
+    let max_observations = max_observations.unwrap_or(DEFAULT_MAX_OBSERVATIONS);
+    if max_observations == 0 || max_observations > MAX_OBSERVATIONS_LIMIT {
+        return Err(ContractError::InvalidMaxObservations {
+            limit: MAX_OBSERVATIONS_LIMIT,
+        });
+    }
+
+    let max_age = max_age.unwrap_or(period * 2);
+    if max_age < period {
+        return Err(ContractError::InvalidMaxAge {});
+    }
+
+    Ok(Config {
+        owner,
+        factory,
+        period,
+        max_observations,
+        max_age,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Update {} => update(deps, env),
+        ExecuteMsg::RegisterPair { asset_infos } => {
+            execute_register_pair(deps, env, info, asset_infos)
+        }
+        ExecuteMsg::Update { asset_infos } => update(deps, env, asset_infos),
+        ExecuteMsg::UpdateMany { pairs } => update_many(deps, env, pairs),
     }
 }
 
-pub fn update(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+pub fn execute_register_pair(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset_infos: [AssetInfo; 2],
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let price_last = PRICE_LAST.load(deps.storage)?;
 
-    let prices = query_cumulative_prices(&deps.querier, config.pair.contract_addr)?;
-    let time_elapsed = env.block.time.seconds() - price_last.block_timestamp_last;
+    // permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_info = register_pair(deps, &env, &config, asset_infos)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_pair"),
+        attr("pair", pair_info.contract_addr),
+    ]))
+}
+
+/// Stores the factory pair of the given assets along with its first observation
+pub(crate) fn register_pair(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    asset_infos: [AssetInfo; 2],
+) -> Result<PairInfo, ContractError> {
+    asset_infos[0].check(deps.api)?;
+    asset_infos[1].check(deps.api)?;
+
+    let key = pair_key(&asset_infos);
+    if PAIRS.has(deps.storage, &key) {
+        return Err(ContractError::PairAlreadyRegistered {});
+    }
+
+    let pair_info = query_pair_info(&deps.querier, config.factory.clone(), asset_infos)?;
+    match pair_info.pair_type {
+        PairType::Xyk {} | PairType::Stable {} => {}
+        PairType::Custom(_) => {
+            return Err(ContractError::UnsupportedPairType {
+                pair_type: pair_info.pair_type.to_string(),
+            })
+        }
+    }
+    PAIRS.save(deps.storage, &key, &pair_info)?;
+
+    let prices = query_cumulative_prices(&deps.querier, pair_info.contract_addr.clone())?;
+
+    let price = PriceCumulativeLast {
+        price0_cumulative_last: prices.price0_cumulative_last,
+        price1_cumulative_last: prices.price1_cumulative_last,
+        price_0_average: Decimal256::zero(),
+        price_1_average: Decimal256::zero(),
+        block_timestamp_last: env.block.time.seconds(),
+        log_price_cumulative_last: prices.log_price_cumulative_last,
+        price_0_geometric_average: Decimal256::zero(),
+        price_1_geometric_average: Decimal256::zero(),
+        reserve0_cumulative_last: prices.reserve0_cumulative_last,
+        reserve1_cumulative_last: prices.reserve1_cumulative_last,
+        reserve_0_average: Uint128::zero(),
+        reserve_1_average: Uint128::zero(),
+    };
+    PRICE_LAST.save(deps.storage, &key, &price)?;
+    store_observation(
+        deps.storage,
+        &key,
+        config.max_observations,
+        &Observation {
+            timestamp: env.block.time.seconds(),
+            price0_cumulative: prices.price0_cumulative_last,
+            price1_cumulative: prices.price1_cumulative_last,
+            log_price_cumulative: prices.log_price_cumulative_last,
+            reserve0_cumulative: prices.reserve0_cumulative_last,
+            reserve1_cumulative: prices.reserve1_cumulative_last,
+        },
+    )?;
+
+    Ok(pair_info)
+}
+
+pub fn update(
+    deps: DepsMut,
+    env: Env,
+    asset_infos: [AssetInfo; 2],
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
     // ensure that at least one full period has passed since the last update
-    if time_elapsed < PERIOD {
+    if !update_pair(deps, &env, &config, &asset_infos)? {
         return Err(ContractError::WrongPeriod {});
     }
 
-    let price_0_average = Decimal256::from_ratio(
-        Uint256::from(
-            prices
-                .price0_cumulative_last
-                .wrapping_sub(price_last.price0_cumulative_last),
-        ),
-        time_elapsed,
-    );
-
-    let price_1_average = Decimal256::from_ratio(
-        Uint256::from(
-            prices
-                .price1_cumulative_last
-                .wrapping_sub(price_last.price1_cumulative_last),
-        ),
-        time_elapsed,
-    );
+    Ok(Response::default())
+}
+
+pub fn update_many(
+    mut deps: DepsMut,
+    env: Env,
+    pairs: Vec<[AssetInfo; 2]>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut attributes: Vec<Attribute> = vec![attr("action", "update_many")];
+    for asset_infos in pairs {
+        if update_pair(deps.branch(), &env, &config, &asset_infos)? {
+            let pair_info = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+            attributes.push(attr("updated_pair", pair_info.contract_addr));
+        }
+    }
+
+    Ok(Response::new().add_attributes(attributes))
+}
+
+/// Updates the average prices of the pair, returns false if the period has not elapsed yet
+fn update_pair(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    asset_infos: &[AssetInfo; 2],
+) -> Result<bool, ContractError> {
+    let key = pair_key(asset_infos);
+    let pair_info = PAIRS
+        .may_load(deps.storage, &key)?
+        .ok_or(ContractError::PairNotRegistered {})?;
+    let price_last = PRICE_LAST.load(deps.storage, &key)?;
+
+    let time_elapsed = env.block.time.seconds() - price_last.block_timestamp_last;
+    if time_elapsed < config.period {
+        return Ok(false);
+    }
+
+    let prices = query_cumulative_prices(&deps.querier, pair_info.contract_addr)?;
+
+    let price0_delta = prices
+        .price0_cumulative_last
+        .wrapping_sub(price_last.price0_cumulative_last);
+    let price1_delta = prices
+        .price1_cumulative_last
+        .wrapping_sub(price_last.price1_cumulative_last);
+
+    let price_0_average = Decimal256::from_ratio(Uint256::from(price0_delta), time_elapsed);
+    let price_1_average = Decimal256::from_ratio(Uint256::from(price1_delta), time_elapsed);
+
+    // nothing is accumulated while the pair has no liquidity, the arithmetic average of a very
+    // low price may round to zero though
+    let price_geometric_average = if !has_liquidity(price0_delta, price1_delta) {
+        [Decimal256::zero(), Decimal256::zero()]
+    } else {
+        let log_price_average = log_price_delta(
+            prices.log_price_cumulative_last,
+            price_last.log_price_cumulative_last,
+        ) / time_elapsed as i128;
+        [exp2(log_price_average), exp2(-log_price_average)]
+    };
 
     let prices = PriceCumulativeLast {
         price0_cumulative_last: prices.price0_cumulative_last,
@@ -105,54 +261,439 @@ pub fn update(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
         price_0_average,
         price_1_average,
         block_timestamp_last: env.block.time.seconds(),
+        log_price_cumulative_last: prices.log_price_cumulative_last,
+        price_0_geometric_average: price_geometric_average[0],
+        price_1_geometric_average: price_geometric_average[1],
+        reserve0_cumulative_last: prices.reserve0_cumulative_last,
+        reserve1_cumulative_last: prices.reserve1_cumulative_last,
+        reserve_0_average: prices
+            .reserve0_cumulative_last
+            .wrapping_sub(price_last.reserve0_cumulative_last)
+            / Uint128::from(time_elapsed),
+        reserve_1_average: prices
+            .reserve1_cumulative_last
+            .wrapping_sub(price_last.reserve1_cumulative_last)
+            / Uint128::from(time_elapsed),
     };
-    PRICE_LAST.save(deps.storage, &prices)?;
-    Ok(Response::default())
+    PRICE_LAST.save(deps.storage, &key, &prices)?;
+    store_observation(
+        deps.storage,
+        &key,
+        config.max_observations,
+        &Observation {
+            timestamp: env.block.time.seconds(),
+            price0_cumulative: prices.price0_cumulative_last,
+            price1_cumulative: prices.price1_cumulative_last,
+            log_price_cumulative: prices.log_price_cumulative_last,
+            reserve0_cumulative: prices.reserve0_cumulative_last,
+            reserve1_cumulative: prices.reserve1_cumulative_last,
+        },
+    )?;
+
+    Ok(true)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
-        QueryMsg::Consult { token, amount } => to_binary(&consult(deps, token, amount)?),
+        QueryMsg::Consult {
+            asset_infos,
+            token,
+            amount,
+            window,
+            allow_spot,
+            geometric,
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            Ok(to_binary(&consult(
+                deps,
+                &env,
+                asset_infos,
+                token,
+                Uint256::from(amount),
+                window,
+                config.max_age,
+                allow_spot.unwrap_or(false),
+                geometric.unwrap_or(false),
+            )?)?)
+        }
+        QueryMsg::ConsultPath {
+            path,
+            amount,
+            window,
+            max_age,
+            geometric,
+        } => Ok(to_binary(&consult_path(
+            deps,
+            env,
+            path,
+            amount,
+            window,
+            max_age,
+            geometric.unwrap_or(false),
+        )?)?),
+        QueryMsg::ConsultLiquidity {
+            asset_infos,
+            window,
+        } => Ok(to_binary(&consult_liquidity(
+            deps,
+            env,
+            asset_infos,
+            window,
+        )?)?),
+    }
+}
+
+/// Prices `amount` of the token in the other pair asset.
+/// The spot price is used if no TWAP is available yet and `allow_spot` is set.
+/// The geometric mean price is used instead of the arithmetic one if `geometric` is set.
+#[allow(clippy::too_many_arguments)]
+fn consult(
+    deps: Deps,
+    env: &Env,
+    asset_infos: [AssetInfo; 2],
+    token: AssetInfo,
+    amount: Uint256,
+    window: Option<u64>,
+    max_age: u64,
+    allow_spot: bool,
+    geometric: bool,
+) -> Result<Uint256, ContractError> {
+    let key = pair_key(&asset_infos);
+    let pair_info = PAIRS
+        .may_load(deps.storage, &key)?
+        .ok_or(ContractError::PairNotRegistered {})?;
+
+    let (token_index, partner) = if pair_info.asset_infos[0].equal(&token) {
+        (0, &pair_info.asset_infos[1])
+    } else if pair_info.asset_infos[1].equal(&token) {
+        (1, &pair_info.asset_infos[0])
+    } else {
+        return Err(ContractError::InvalidToken {});
+    };
+
+    let price_last = PRICE_LAST.load(deps.storage, &key)?;
+    if env.block.time.seconds() - price_last.block_timestamp_last > max_age {
+        return Err(ContractError::StalePrice {
+            asset: token.to_string(),
+            partner: partner.to_string(),
+        });
+    }
+
+    // arithmetic averages are accumulated with TWAP_PRECISION, geometric ones are plain prices
+    let price_precision = if geometric {
+        Decimal256::one()
+    } else {
+        Decimal256::from_uint256(10_u128.pow(TWAP_PRECISION.into()))
+    };
+
+    if let Some(window) = window {
+        let config = CONFIG.load(deps.storage)?;
+        let price_average =
+            window_price_average(deps, env, &config, &key, &pair_info, window, geometric)?
+                [token_index];
+        return Ok(amount * price_average / price_precision);
+    }
+
+    let price_average = match (token_index, geometric) {
+        (0, false) => price_last.price_0_average,
+        (_, false) => price_last.price_1_average,
+        (0, true) => price_last.price_0_geometric_average,
+        (_, true) => price_last.price_1_geometric_average,
+    };
+
+    if !price_average.is_zero() {
+        return Ok(amount * price_average / price_precision);
+    }
+
+    // no full period has elapsed since the pair registration
+    if !allow_spot {
+        return Err(ContractError::NoTwapAvailable {});
+    }
+
+    // get precision
+    let p = query_token_precision(&deps.querier, token.clone())?;
+    let one = Uint128::new(10_u128.pow(p.into()));
+
+    let price = query_prices(
+        &deps.querier,
+        pair_info.contract_addr,
+        Asset {
+            info: token,
+            amount: one,
+        },
+    )?
+    .return_amount;
+
+    Ok(Uint256::from(price).multiply_ratio(amount, Uint256::from(one)))
+}
+
+/// Chains the consult of every leg of the path, the output of a leg is priced by the next one
+fn consult_path(
+    deps: Deps,
+    env: Env,
+    path: Vec<AssetInfo>,
+    amount: Uint128,
+    window: Option<u64>,
+    max_age: Option<u64>,
+    geometric: bool,
+) -> Result<ConsultPathResponse, ContractError> {
+    if path.len() < 2 {
+        return Err(ContractError::InvalidPath {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    // The caller can only tighten the staleness limit of the config
+    let max_age = max_age.unwrap_or(config.max_age).min(config.max_age);
+    let now = env.block.time.seconds();
+
+    let mut amount = Uint256::from(amount);
+    let mut min_observation_age = u64::MAX;
+    let mut max_observation_age = 0;
+    for leg in path.windows(2) {
+        let asset_infos = [leg[0].clone(), leg[1].clone()];
+        amount = consult(
+            deps,
+            &env,
+            asset_infos.clone(),
+            leg[0].clone(),
+            amount,
+            window,
+            max_age,
+            false,
+            geometric,
+        )?;
+
+        let price_last = PRICE_LAST.load(deps.storage, &pair_key(&asset_infos))?;
+        let observation_age = now - price_last.block_timestamp_last;
+        min_observation_age = min_observation_age.min(observation_age);
+        max_observation_age = max_observation_age.max(observation_age);
     }
+
+    // the conversion panics on overflow
+    if amount > Uint256::from(u128::MAX) {
+        return Err(ContractError::PathAmountOverflow {});
+    }
+
+    Ok(ConsultPathResponse {
+        amount: amount.into(),
+        min_observation_age,
+        max_observation_age,
+    })
 }
 
-fn consult(deps: Deps, token: AssetInfo, amount: Uint128) -> Result<Uint256, StdError> {
+/// Time weighted average reserves of the pair over the last update period or the last `window` seconds
+fn consult_liquidity(
+    deps: Deps,
+    env: Env,
+    asset_infos: [AssetInfo; 2],
+    window: Option<u64>,
+) -> Result<ConsultLiquidityResponse, ContractError> {
+    let key = pair_key(&asset_infos);
+    let pair_info = PAIRS
+        .may_load(deps.storage, &key)?
+        .ok_or(ContractError::PairNotRegistered {})?;
+
     let config = CONFIG.load(deps.storage)?;
-    let price_last = PRICE_LAST.load(deps.storage)?;
+    let price_last = PRICE_LAST.load(deps.storage, &key)?;
+    if env.block.time.seconds() - price_last.block_timestamp_last > config.max_age {
+        return Err(ContractError::StaleLiquidity {
+            pair: pair_info.contract_addr.to_string(),
+        });
+    }
 
-    let price_average = if config.asset_infos[0].equal(&token) {
-        price_last.price_0_average
-    } else if config.asset_infos[1].equal(&token) {
-        price_last.price_1_average
+    let reserves_average = if let Some(window) = window {
+        let (start, current) = window_observations(deps, &env, &config, &key, &pair_info, window)?;
+        [
+            current
+                .reserve0_cumulative
+                .wrapping_sub(start.reserve0_cumulative)
+                / Uint128::from(window),
+            current
+                .reserve1_cumulative
+                .wrapping_sub(start.reserve1_cumulative)
+                / Uint128::from(window),
+        ]
     } else {
-        return Err(StdError::generic_err("Invalid Token"));
+        [price_last.reserve_0_average, price_last.reserve_1_average]
     };
 
-    Ok(if price_average.is_zero() {
-        // get precision
-        let p = query_token_precision(&deps.querier, token.clone())?;
-        let one = Uint128::new(10_u128.pow(p.into()));
+    // no full period has elapsed since the pair registration
+    if reserves_average[0].is_zero() && reserves_average[1].is_zero() {
+        return Err(ContractError::NoTwapAvailable {});
+    }
+
+    let prices = query_cumulative_prices(&deps.querier, pair_info.contract_addr)?;
 
-        let price = query_prices(
-            &deps.querier,
-            config.pair.contract_addr,
+    Ok(ConsultLiquidityResponse {
+        assets: [
             Asset {
-                info: token,
-                amount: one,
+                info: pair_info.asset_infos[0].clone(),
+                amount: reserves_average[0],
             },
-        )
-        .unwrap()
-        .return_amount;
+            Asset {
+                info: pair_info.asset_infos[1].clone(),
+                amount: reserves_average[1],
+            },
+        ],
+        total_share: prices.total_share,
+    })
+}
+
+/// Average prices of both assets over the last `window` seconds, geometric means if `geometric` is set
+fn window_price_average(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pair_key: &[u8],
+    pair_info: &PairInfo,
+    window: u64,
+    geometric: bool,
+) -> Result<[Decimal256; 2], ContractError> {
+    let (start, current) = window_observations(deps, env, config, pair_key, pair_info, window)?;
+
+    let price0_delta = current
+        .price0_cumulative
+        .wrapping_sub(start.price0_cumulative);
+    let price1_delta = current
+        .price1_cumulative
+        .wrapping_sub(start.price1_cumulative);
+    if !has_liquidity(price0_delta, price1_delta) {
+        return Err(ContractError::NoTwapAvailable {});
+    }
+
+    if geometric {
+        let log_price_average =
+            log_price_delta(current.log_price_cumulative, start.log_price_cumulative)
+                / window as i128;
+        return Ok([exp2(log_price_average), exp2(-log_price_average)]);
+    }
+
+    Ok([
+        Decimal256::from_ratio(Uint256::from(price0_delta), window),
+        Decimal256::from_ratio(Uint256::from(price1_delta), window),
+    ])
+}
+
+/// Pairs accumulate prices only while they have liquidity. At least one of the prices is
+/// accumulated then, as the price of one asset is the inverse of the other one.
+fn has_liquidity(price0_delta: Uint128, price1_delta: Uint128) -> bool {
+    !price0_delta.is_zero() || !price1_delta.is_zero()
+}
+
+/// Observations at the start and at the end of the last `window` seconds.
+/// The start is interpolated between the stored observations, the end is the current pair state.
+fn window_observations(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    pair_key: &[u8],
+    pair_info: &PairInfo,
+    window: u64,
+) -> Result<(Observation, Observation), ContractError> {
+    let now = env.block.time.seconds();
+    if window == 0 || window > now {
+        return Err(ContractError::InvalidWindow {});
+    }
+    let target = now - window;
+
+    let prices = query_cumulative_prices(&deps.querier, pair_info.contract_addr.clone())?;
+    let current = Observation {
+        timestamp: now,
+        price0_cumulative: prices.price0_cumulative_last,
+        price1_cumulative: prices.price1_cumulative_last,
+        log_price_cumulative: prices.log_price_cumulative_last,
+        reserve0_cumulative: prices.reserve0_cumulative_last,
+        reserve1_cumulative: prices.reserve1_cumulative_last,
+    };
+
+    let mut newer = current.clone();
+    let mut start = None;
+    for older in read_observations(deps.storage, pair_key, config.max_observations)? {
+        if older.timestamp <= target {
+            start = Some(interpolate_observation(&older, &newer, target));
+            break;
+        }
+        newer = older;
+    }
+    let start = start.ok_or(ContractError::NotEnoughObservations {})?;
+
+    Ok((start, current))
+}
 
-        Uint256::from(price).multiply_ratio(Uint256::from(amount), Uint256::from(one))
+/// Cumulative values at the given timestamp between two observations, assuming a constant price
+fn interpolate_observation(
+    older: &Observation,
+    newer: &Observation,
+    timestamp: u64,
+) -> Observation {
+    if older.timestamp == timestamp || newer.timestamp == older.timestamp {
+        return older.clone();
+    }
+
+    let elapsed = timestamp - older.timestamp;
+    let duration = newer.timestamp - older.timestamp;
+    let interpolate = |older: Uint128, newer: Uint128| {
+        older.wrapping_add(newer.wrapping_sub(older).multiply_ratio(elapsed, duration))
+    };
+
+    Observation {
+        timestamp,
+        price0_cumulative: interpolate(older.price0_cumulative, newer.price0_cumulative),
+        price1_cumulative: interpolate(older.price1_cumulative, newer.price1_cumulative),
+        reserve0_cumulative: interpolate(older.reserve0_cumulative, newer.reserve0_cumulative),
+        reserve1_cumulative: interpolate(older.reserve1_cumulative, newer.reserve1_cumulative),
+        log_price_cumulative: older.log_price_cumulative.wrapping_add(Uint128::new(
+            (log_price_delta(newer.log_price_cumulative, older.log_price_cumulative)
+                * elapsed as i128
+                / duration as i128) as u128,
+        )),
+    }
+}
+
+/// Signed difference of two log price accumulator values, they are stored in two's complement
+fn log_price_delta(newer: Uint128, older: Uint128) -> i128 {
+    newer.wrapping_sub(older).u128() as i128
+}
+
+/// Returns 2^x where x is multiplied by LOG_PRICE_ONE
+fn exp2(x: i128) -> Decimal256 {
+    // ln(2) multiplied by LOG_PRICE_ONE
+    const LN_2: u128 = 693_147_180_559_945_309;
+
+    let one = LOG_PRICE_ONE as i128;
+    let int = x.div_euclid(one);
+    let frac = x.rem_euclid(one) as u128;
+
+    // 2^frac = e^(frac * ln(2)) with the Taylor series, the exponent is below one
+    let exponent = frac * LN_2 / LOG_PRICE_ONE;
+    let mut result = LOG_PRICE_ONE;
+    let mut term = LOG_PRICE_ONE;
+    let mut n = 1;
+    while term > 0 {
+        term = term * exponent / LOG_PRICE_ONE / n;
+        result += term;
+        n += 1;
+    }
+
+    let power = (0..int.abs()).fold(Uint256::one(), |power, _| power * Uint256::from(2u128));
+    if int >= 0 {
+        Decimal256::from_ratio(Uint256::from(result) * power, LOG_PRICE_ONE)
     } else {
-        let price_precision = Uint256::from(10_u128.pow(TWAP_PRECISION.into()));
-        Uint256::from(amount) * price_average / Decimal256::from_uint256(price_precision)
-    })
+        Decimal256::from_ratio(result, Uint256::from(LOG_PRICE_ONE) * power)
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+
+    match contract_version.version.as_ref() {
+        "1.0.0" => migration::migrate_to_many_pairs(deps.branch(), &env, msg)?,
+        _ => return Err(ContractError::MigrationError {}),
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attribute("action", "migrate"))
 }
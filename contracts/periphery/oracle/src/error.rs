@@ -11,4 +11,49 @@ pub enum ContractError {
 
     #[error("Period not elapsed")]
     WrongPeriod {},
+
+    #[error("Pair is not registered")]
+    PairNotRegistered {},
+
+    #[error("Pair is already registered")]
+    PairAlreadyRegistered {},
+
+    #[error("Pair type {pair_type} is not supported")]
+    UnsupportedPairType { pair_type: String },
+
+    #[error("Invalid Token")]
+    InvalidToken {},
+
+    #[error("Path must contain at least two assets")]
+    InvalidPath {},
+
+    #[error("Path amount overflows Uint128")]
+    PathAmountOverflow {},
+
+    #[error("Invalid window")]
+    InvalidWindow {},
+
+    #[error("Not enough price observations for the window")]
+    NotEnoughObservations {},
+
+    #[error("No TWAP available yet")]
+    NoTwapAvailable {},
+
+    #[error("Price of {asset} in {partner} is stale")]
+    StalePrice { asset: String, partner: String },
+
+    #[error("Liquidity of {pair} is stale")]
+    StaleLiquidity { pair: String },
+
+    #[error("Max age must not be less than the period")]
+    InvalidMaxAge {},
+
+    #[error("Period must be positive")]
+    InvalidPeriod {},
+
+    #[error("Max observations must be between 1 and {limit}")]
+    InvalidMaxObservations { limit: u32 },
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
 }
@@ -1,5 +1,6 @@
 pub mod contract;
 pub mod error;
+mod migration;
 mod querier;
 pub mod state;
 
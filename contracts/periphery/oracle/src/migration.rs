@@ -0,0 +1,58 @@
+use astroport::asset::{AssetInfo, PairInfo};
+use astroport::oracle::MigrateMsg;
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{Addr, DepsMut, Env, Uint128};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::{build_config, register_pair};
+use crate::error::ContractError;
+use crate::state::CONFIG;
+
+/// Config of the single pair oracle (v1.0.0)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV100 {
+    pub owner: Addr,
+    pub factory: Addr,
+    pub asset_infos: [AssetInfo; 2],
+    pub pair: PairInfo,
+}
+
+/// Prices of the single pair oracle (v1.0.0)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceCumulativeLastV100 {
+    pub price0_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint128,
+    pub price_0_average: Decimal256,
+    pub price_1_average: Decimal256,
+    pub block_timestamp_last: u64,
+}
+
+pub const CONFIG_V100: Item<ConfigV100> = Item::new("config");
+pub const PRICE_LAST_V100: Item<PriceCumulativeLastV100> = Item::new("price_last");
+
+/// Registers the pair of v1.0.0 as the only pair of the oracle. The v1.0.0 prices lack the log
+/// price and reserve accumulators, so the pair starts from a fresh observation and has no TWAP
+/// until its first update.
+pub fn migrate_to_many_pairs(
+    deps: DepsMut,
+    env: &Env,
+    msg: MigrateMsg,
+) -> Result<(), ContractError> {
+    let cfg = CONFIG_V100.load(deps.storage)?;
+    PRICE_LAST_V100.remove(deps.storage);
+
+    let config = build_config(
+        cfg.owner,
+        cfg.factory,
+        msg.period,
+        msg.max_observations,
+        msg.max_age,
+    )?;
+    CONFIG.save(deps.storage, &config)?;
+
+    register_pair(deps, env, &config, cfg.asset_infos)?;
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
-use astroport::asset::{Asset, PairInfo};
+use crate::state::pair_key;
+use astroport::asset::{Asset, AssetInfo, PairInfo};
 use astroport::factory::PairType;
 use astroport::factory::QueryMsg::Pair;
-use astroport::pair::CumulativePricesResponse;
-use astroport::pair::QueryMsg::CumulativePrices;
+use astroport::pair::QueryMsg::{CumulativePrices, Simulation};
+use astroport::pair::{CumulativePricesResponse, SimulationResponse};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_binary, from_slice, to_binary, Addr, Coin, OwnedDeps, Querier, QuerierResult,
@@ -27,6 +28,10 @@ pub fn mock_dependencies(
 pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
     token_querier: TokenQuerier,
+    // pair addresses returned by the factory, "pair" is returned for other assets
+    pair_addresses: HashMap<Vec<u8>, Addr>,
+    // pair types returned by the factory, xyk is returned for other assets
+    pair_types: HashMap<Vec<u8>, PairType>,
 }
 
 #[derive(Clone, Default)]
@@ -44,7 +49,6 @@ impl TokenQuerier {
         price0: Uint128,
         price1: Uint128,
     ) {
-        self.pairs = HashMap::new();
         self.pairs.insert(
             pair.to_string(),
             CumulativePricesResponse {
@@ -52,6 +56,9 @@ impl TokenQuerier {
                 total_share: total,
                 price0_cumulative_last: price0,
                 price1_cumulative_last: price1,
+                log_price_cumulative_last: Uint128::zero(),
+                reserve0_cumulative_last: Uint128::zero(),
+                reserve1_cumulative_last: Uint128::zero(),
             },
         );
     }
@@ -81,10 +88,18 @@ impl WasmMockQuerier {
                     match from_binary(&msg).unwrap() {
                         Pair { asset_infos } => SystemResult::Ok(
                             to_binary(&PairInfo {
+                                contract_addr: self
+                                    .pair_addresses
+                                    .get(&pair_key(&asset_infos))
+                                    .cloned()
+                                    .unwrap_or_else(|| Addr::unchecked("pair")),
+                                pair_type: self
+                                    .pair_types
+                                    .get(&pair_key(&asset_infos))
+                                    .cloned()
+                                    .unwrap_or(PairType::Xyk {}),
                                 asset_infos,
-                                contract_addr: Addr::unchecked("pair"),
                                 liquidity_token: Addr::unchecked("lp_token"),
-                                pair_type: PairType::Xyk {},
                             })
                             .into(),
                         ),
@@ -101,6 +116,15 @@ impl WasmMockQuerier {
                             };
                             SystemResult::Ok(to_binary(&balance).into())
                         }
+                        // spot price is always one
+                        Simulation { offer_asset } => SystemResult::Ok(
+                            to_binary(&SimulationResponse {
+                                return_amount: offer_asset.amount,
+                                spread_amount: Uint128::zero(),
+                                commission_amount: Uint128::zero(),
+                            })
+                            .into(),
+                        ),
                         _ => panic!("DO NOT ENTER HERE"),
                     }
                 }
@@ -114,6 +138,8 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
+            pair_addresses: HashMap::new(),
+            pair_types: HashMap::new(),
         }
     }
 
@@ -127,4 +153,25 @@ impl WasmMockQuerier {
     ) {
         self.token_querier.set(pair, assert, total, price1, price2)
     }
+
+    pub fn set_log_price_cumulative(&mut self, pair: Addr, log_price: Uint128) {
+        if let Some(prices) = self.token_querier.pairs.get_mut(pair.as_str()) {
+            prices.log_price_cumulative_last = log_price;
+        }
+    }
+
+    pub fn set_reserves_cumulative(&mut self, pair: Addr, reserve0: Uint128, reserve1: Uint128) {
+        if let Some(prices) = self.token_querier.pairs.get_mut(pair.as_str()) {
+            prices.reserve0_cumulative_last = reserve0;
+            prices.reserve1_cumulative_last = reserve1;
+        }
+    }
+
+    pub fn set_pair_address(&mut self, asset_infos: [AssetInfo; 2], pair: Addr) {
+        self.pair_addresses.insert(pair_key(&asset_infos), pair);
+    }
+
+    pub fn set_pair_type(&mut self, asset_infos: [AssetInfo; 2], pair_type: PairType) {
+        self.pair_types.insert(pair_key(&asset_infos), pair_type);
+    }
 }
@@ -3,12 +3,17 @@ use serde::{Deserialize, Serialize};
 
 use astroport::asset::{AssetInfo, PairInfo};
 use cosmwasm_bignumber::Decimal256;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map, U64Key};
 
 /// Stores config at the given key
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const PRICE_LAST: Item<PriceCumulativeLast> = Item::new("price_last");
+/// Registered pairs, keyed by pair_key
+pub const PAIRS: Map<&[u8], PairInfo> = Map::new("pairs");
+pub const PRICE_LAST: Map<&[u8], PriceCumulativeLast> = Map::new("price_last");
+/// Ring buffer of cumulative price observations, keyed by pair_key and slot
+pub const OBSERVATIONS: Map<(&[u8], U64Key), Observation> = Map::new("observations");
+pub const OBSERVATIONS_STATE: Map<&[u8], ObservationsState> = Map::new("observations_state");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceCumulativeLast {
@@ -17,6 +22,22 @@ pub struct PriceCumulativeLast {
     pub price_0_average: Decimal256,
     pub price_1_average: Decimal256,
     pub block_timestamp_last: u64,
+    #[serde(default)]
+    pub log_price_cumulative_last: Uint128,
+    /// Geometric mean prices of the last update period
+    #[serde(default)]
+    pub price_0_geometric_average: Decimal256,
+    #[serde(default)]
+    pub price_1_geometric_average: Decimal256,
+    #[serde(default)]
+    pub reserve0_cumulative_last: Uint128,
+    #[serde(default)]
+    pub reserve1_cumulative_last: Uint128,
+    /// Average reserves of the last update period
+    #[serde(default)]
+    pub reserve_0_average: Uint128,
+    #[serde(default)]
+    pub reserve_1_average: Uint128,
 }
 
 /// Contract global configuration
@@ -24,6 +45,95 @@ pub struct PriceCumulativeLast {
 pub struct Config {
     pub owner: Addr,
     pub factory: Addr,
-    pub asset_infos: [AssetInfo; 2],
-    pub pair: PairInfo,
+    /// Minimal time between price updates in seconds
+    pub period: u64,
+    /// Capacity of the observations ring buffer of every pair
+    pub max_observations: u32,
+    /// Maximum time in seconds since the last update of a pair for its price to be consulted
+    pub max_age: u64,
+}
+
+/// Cumulative prices and reserves snapshot taken on registration and every update
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price0_cumulative: Uint128,
+    pub price1_cumulative: Uint128,
+    #[serde(default)]
+    pub log_price_cumulative: Uint128,
+    #[serde(default)]
+    pub reserve0_cumulative: Uint128,
+    #[serde(default)]
+    pub reserve1_cumulative: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ObservationsState {
+    /// Slot of the latest observation
+    pub last_slot: u64,
+    /// Number of stored observations
+    pub count: u64,
+}
+
+/// Storage key of the pair, the same for both orders of the assets. Every asset is prefixed with
+/// its length, so different pairs can't have the same key.
+pub fn pair_key(asset_infos: &[AssetInfo; 2]) -> Vec<u8> {
+    let mut asset_infos = asset_infos.to_vec();
+    asset_infos.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+    let mut key = vec![];
+    for asset_info in asset_infos.iter() {
+        let bytes = asset_info.as_bytes();
+        key.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+        key.extend_from_slice(bytes);
+    }
+
+    key
+}
+
+/// Stores the observation in the next slot of the pair, overwriting the oldest one if the buffer is full
+pub fn store_observation(
+    storage: &mut dyn Storage,
+    pair_key: &[u8],
+    max_observations: u32,
+    observation: &Observation,
+) -> StdResult<()> {
+    let capacity = max_observations as u64;
+    let state = match OBSERVATIONS_STATE.may_load(storage, pair_key)? {
+        Some(state) => ObservationsState {
+            last_slot: (state.last_slot + 1) % capacity,
+            count: (state.count + 1).min(capacity),
+        },
+        None => ObservationsState {
+            last_slot: 0,
+            count: 1,
+        },
+    };
+
+    OBSERVATIONS.save(
+        storage,
+        (pair_key, U64Key::new(state.last_slot)),
+        observation,
+    )?;
+    OBSERVATIONS_STATE.save(storage, pair_key, &state)
+}
+
+/// Returns stored observations of the pair from the newest to the oldest
+pub fn read_observations(
+    storage: &dyn Storage,
+    pair_key: &[u8],
+    max_observations: u32,
+) -> StdResult<Vec<Observation>> {
+    let capacity = max_observations as u64;
+    let state = match OBSERVATIONS_STATE.may_load(storage, pair_key)? {
+        Some(state) => state,
+        None => return Ok(vec![]),
+    };
+
+    (0..state.count)
+        .map(|i| {
+            let slot = (state.last_slot + capacity - i) % capacity;
+            OBSERVATIONS.load(storage, (pair_key, U64Key::new(slot)))
+        })
+        .collect()
 }
@@ -1,10 +1,17 @@
-use crate::contract::{execute, instantiate};
-use crate::mock_querier::mock_dependencies;
+use crate::contract::{execute, instantiate, migrate, query};
+use crate::migration::{ConfigV100, PriceCumulativeLastV100, CONFIG_V100, PRICE_LAST_V100};
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+use crate::state::{pair_key, CONFIG, PAIRS, PRICE_LAST};
 use astroport::asset::{Asset, AssetInfo};
-use astroport::oracle::{ExecuteMsg, InstantiateMsg};
+use astroport::factory::PairType;
+use astroport::oracle::{
+    ConsultLiquidityResponse, ConsultPathResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+};
+use astroport::pair::LOG_PRICE_ONE;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{attr, from_binary, Addr, Deps, Env, Uint128};
+use cw2::{get_contract_version, set_contract_version};
 use std::ops::Mul;
 
 #[test]
@@ -24,6 +31,22 @@ fn decimal_overflow() {
     );
 }
 
+#[test]
+fn pair_keys_are_unique() {
+    let native = |denom: &str| AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    };
+
+    assert_eq!(
+        pair_key(&[native("uusd"), native("uluna")]),
+        pair_key(&[native("uluna"), native("uusd")])
+    );
+    assert_ne!(
+        pair_key(&[native("ab"), native("c")]),
+        pair_key(&[native("a"), native("bc")])
+    );
+}
+
 #[test]
 fn oracle_overflow() {
     let mut deps = mock_dependencies(&[]);
@@ -53,7 +76,10 @@ fn oracle_overflow() {
 
     let instantiate_msg = InstantiateMsg {
         factory_contract: factory.to_string(),
-        asset_infos: [astro_asset_info, usdc_asset_info],
+        pairs: vec![[astro_asset_info.clone(), usdc_asset_info.clone()]],
+        period: None,
+        max_observations: None,
+        max_age: None,
     };
 
     //set cumulative price 192738282u128
@@ -75,5 +101,1143 @@ fn oracle_overflow() {
         Uint128::from(100u128),
     );
     env.block.time = env.block.time.plus_seconds(86400);
-    execute(deps.as_mut(), env, info, ExecuteMsg::Update {}).unwrap();
+    execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::Update {
+            asset_infos: [astro_asset_info, usdc_asset_info],
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn consult_window() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+    let start = env.block.time.seconds();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let asset_infos = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let asset = [
+        Asset {
+            info: astro_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: usdc_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+    ];
+
+    // constant prices: 2 USDC per ASTRO and 0.5 ASTRO per USDC
+    let set_cumulative_price = |querier: &mut WasmMockQuerier, time: u64| {
+        querier.set_cumulative_price(
+            Addr::unchecked("pair"),
+            asset.clone(),
+            Uint128::zero(),
+            Uint128::from((time - start) as u128 * 2_000000),
+            Uint128::from((time - start) as u128 * 500000),
+        );
+    };
+
+    let instantiate_msg = InstantiateMsg {
+        factory_contract: String::from("factory"),
+        pairs: vec![asset_infos.clone()],
+        period: Some(0),
+        max_observations: Some(3),
+        max_age: None,
+    };
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Period must be positive");
+
+    let instantiate_msg = InstantiateMsg {
+        period: Some(300),
+        max_observations: Some(1001),
+        ..instantiate_msg
+    };
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Max observations must be between 1 and 1000"
+    );
+
+    let instantiate_msg = InstantiateMsg {
+        max_observations: Some(3),
+        ..instantiate_msg
+    };
+    set_cumulative_price(&mut deps.querier, start);
+    instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+
+    // the configured period is used between updates
+    env.block.time = env.block.time.plus_seconds(299);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Period not elapsed");
+
+    // observations at +300, +600, +900 and +1200, the one from instantiate is overwritten
+    env.block.time = env.block.time.minus_seconds(299);
+    for _ in 0..4 {
+        env.block.time = env.block.time.plus_seconds(300);
+        set_cumulative_price(&mut deps.querier, env.block.time.seconds());
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+    }
+
+    // halfway between the observations at +600 and +900, 100 seconds after the last update
+    env.block.time = env.block.time.plus_seconds(100);
+    set_cumulative_price(&mut deps.querier, env.block.time.seconds());
+    let res: Uint256 = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Consult {
+                asset_infos: asset_infos.clone(),
+                token: astro_asset_info.clone(),
+                amount: Uint128::from(1000u128),
+                window: Some(550),
+                allow_spot: None,
+                geometric: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, Uint256::from(2000u128));
+
+    // the window starts before the oldest observation
+    let err = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Consult {
+            asset_infos,
+            token: astro_asset_info,
+            amount: Uint128::from(1000u128),
+            window: Some(800),
+            allow_spot: None,
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Not enough price observations for the window"
+    );
+}
+
+#[test]
+fn multiple_pairs() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = mock_info("owner", &[]);
+
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let uusd_asset_info = AssetInfo::NativeToken {
+        denom: String::from("uusd"),
+    };
+    let astro_usdc = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let astro_uusd = [astro_asset_info.clone(), uusd_asset_info.clone()];
+    let usdc_uusd = [usdc_asset_info, uusd_asset_info];
+
+    for (asset_infos, pair, price0, price1) in [
+        (&astro_usdc, "pair", 2_000000u128, 500000u128),
+        (&astro_uusd, "pair-astro-uusd", 4_000000u128, 250000u128),
+    ] {
+        deps.querier
+            .set_pair_address(asset_infos.clone(), Addr::unchecked(pair));
+        deps.querier.set_cumulative_price(
+            Addr::unchecked(pair),
+            [
+                Asset {
+                    info: asset_infos[0].clone(),
+                    amount: Uint128::zero(),
+                },
+                Asset {
+                    info: asset_infos[1].clone(),
+                    amount: Uint128::zero(),
+                },
+            ],
+            Uint128::zero(),
+            Uint128::from(price0),
+            Uint128::from(price1),
+        );
+    }
+
+    deps.querier
+        .set_pair_type(astro_uusd.clone(), PairType::Stable {});
+
+    let instantiate_msg = InstantiateMsg {
+        factory_contract: String::from("factory"),
+        pairs: vec![astro_usdc.clone()],
+        period: Some(100),
+        max_observations: None,
+        max_age: None,
+    };
+    instantiate(deps.as_mut(), env.clone(), owner.clone(), instantiate_msg).unwrap();
+
+    // only owner can register pairs
+    let msg = ExecuteMsg::RegisterPair {
+        asset_infos: astro_uusd.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let res = execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_pair"),
+            attr("pair", "pair-astro-uusd")
+        ]
+    );
+
+    // the asset order doesn't matter
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        ExecuteMsg::RegisterPair {
+            asset_infos: [astro_uusd[1].clone(), astro_uusd[0].clone()],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Pair is already registered");
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        ExecuteMsg::Update {
+            asset_infos: usdc_uusd.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Pair is not registered");
+
+    // only xyk and stable pairs are supported
+    deps.querier.set_pair_type(
+        usdc_uusd.clone(),
+        PairType::Custom(String::from("concentrated")),
+    );
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        ExecuteMsg::RegisterPair {
+            asset_infos: usdc_uusd.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Pair type custom-concentrated is not supported"
+    );
+
+    env.block.time = env.block.time.plus_seconds(100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        ExecuteMsg::Update {
+            asset_infos: astro_uusd.clone(),
+        },
+    )
+    .unwrap();
+
+    // astro-usdc is updated after one and a half periods, astro-uusd was updated 50 seconds ago and is skipped
+    env.block.time = env.block.time.plus_seconds(50);
+    for (asset_infos, pair, price0, price1) in [
+        (&astro_usdc, "pair", 302_000000u128, 75_500000u128),
+        (
+            &astro_uusd,
+            "pair-astro-uusd",
+            204_000000u128,
+            12_750000u128,
+        ),
+    ] {
+        deps.querier.set_cumulative_price(
+            Addr::unchecked(pair),
+            [
+                Asset {
+                    info: asset_infos[0].clone(),
+                    amount: Uint128::zero(),
+                },
+                Asset {
+                    info: asset_infos[1].clone(),
+                    amount: Uint128::zero(),
+                },
+            ],
+            Uint128::zero(),
+            Uint128::from(price0),
+            Uint128::from(price1),
+        );
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateMany {
+            pairs: vec![astro_usdc.clone(), astro_uusd.clone()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "update_many"), attr("updated_pair", "pair")]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner,
+        ExecuteMsg::UpdateMany {
+            pairs: vec![usdc_uusd.clone()],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Pair is not registered");
+
+    let res: Uint256 = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Consult {
+                asset_infos: astro_usdc,
+                token: astro_asset_info,
+                amount: Uint128::from(1000u128),
+                window: None,
+                allow_spot: None,
+                geometric: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, Uint256::from(2000u128));
+
+    let err = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Consult {
+            asset_infos: usdc_uusd,
+            token: AssetInfo::NativeToken {
+                denom: String::from("uusd"),
+            },
+            amount: Uint128::from(1000u128),
+            window: None,
+            allow_spot: None,
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Pair is not registered");
+}
+
+#[test]
+fn consult_path() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let uusd_asset_info = AssetInfo::NativeToken {
+        denom: String::from("uusd"),
+    };
+    let astro_usdc = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let usdc_uusd = [usdc_asset_info.clone(), uusd_asset_info.clone()];
+    let path = vec![astro_asset_info.clone(), usdc_asset_info, uusd_asset_info];
+
+    let set_cumulative_price =
+        |querier: &mut WasmMockQuerier, asset_infos: &[AssetInfo; 2], pair: &str, price0: u128| {
+            querier.set_cumulative_price(
+                Addr::unchecked(pair),
+                [
+                    Asset {
+                        info: asset_infos[0].clone(),
+                        amount: Uint128::zero(),
+                    },
+                    Asset {
+                        info: asset_infos[1].clone(),
+                        amount: Uint128::zero(),
+                    },
+                ],
+                Uint128::zero(),
+                Uint128::from(price0),
+                Uint128::zero(),
+            );
+        };
+
+    deps.querier
+        .set_pair_address(usdc_uusd.clone(), Addr::unchecked("pair-usdc-uusd"));
+    set_cumulative_price(&mut deps.querier, &astro_usdc, "pair", 0);
+    set_cumulative_price(&mut deps.querier, &usdc_uusd, "pair-usdc-uusd", 0);
+
+    let instantiate_msg = InstantiateMsg {
+        factory_contract: String::from("factory"),
+        pairs: vec![astro_usdc.clone(), usdc_uusd.clone()],
+        period: Some(100),
+        max_observations: None,
+        max_age: None,
+    };
+    instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+
+    // 3 UUSD per USDC, updated at +100
+    env.block.time = env.block.time.plus_seconds(100);
+    set_cumulative_price(&mut deps.querier, &usdc_uusd, "pair-usdc-uusd", 300_000000);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Update {
+            asset_infos: usdc_uusd,
+        },
+    )
+    .unwrap();
+
+    // 2 USDC per ASTRO, updated at +150
+    env.block.time = env.block.time.plus_seconds(50);
+    set_cumulative_price(&mut deps.querier, &astro_usdc, "pair", 300_000000);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Update {
+            asset_infos: astro_usdc,
+        },
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(10);
+    let res: ConsultPathResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::ConsultPath {
+                path: path.clone(),
+                amount: Uint128::from(1000u128),
+                window: None,
+                max_age: None,
+                geometric: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ConsultPathResponse {
+            amount: Uint128::from(6000u128),
+            min_observation_age: 10,
+            max_observation_age: 60,
+        }
+    );
+
+    // the path output doesn't fit into Uint128
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ConsultPath {
+            path: path.clone(),
+            amount: Uint128::from(u128::MAX),
+            window: None,
+            max_age: None,
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Path amount overflows Uint128");
+
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ConsultPath {
+            path: path.clone(),
+            amount: Uint128::from(1000u128),
+            window: None,
+            max_age: Some(59),
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Price of usdc-token in uusd is stale");
+
+    // the default max age is two periods
+    env.block.time = env.block.time.plus_seconds(141);
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ConsultPath {
+            path: path.clone(),
+            amount: Uint128::from(1000u128),
+            window: None,
+            max_age: None,
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Price of usdc-token in uusd is stale");
+
+    // max age can't exceed the config max age
+    let err = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ConsultPath {
+            path,
+            amount: Uint128::from(1000u128),
+            window: None,
+            max_age: Some(1000),
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Price of usdc-token in uusd is stale");
+
+    let err = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::ConsultPath {
+            path: vec![astro_asset_info],
+            amount: Uint128::from(1000u128),
+            window: None,
+            max_age: None,
+            geometric: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Path must contain at least two assets");
+}
+
+#[test]
+fn consult_without_twap() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+
+    let uusd_asset_info = AssetInfo::NativeToken {
+        denom: String::from("uusd"),
+    };
+    let asset_infos = [
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("astro-token"),
+        },
+        uusd_asset_info.clone(),
+    ];
+    deps.querier.set_cumulative_price(
+        Addr::unchecked("pair"),
+        [
+            Asset {
+                info: asset_infos[0].clone(),
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: asset_infos[1].clone(),
+                amount: Uint128::zero(),
+            },
+        ],
+        Uint128::zero(),
+        Uint128::zero(),
+        Uint128::zero(),
+    );
+
+    let instantiate_msg = InstantiateMsg {
+        factory_contract: String::from("factory"),
+        pairs: vec![asset_infos.clone()],
+        period: Some(100),
+        max_observations: None,
+        max_age: Some(99),
+    };
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Max age must not be less than the period");
+
+    let instantiate_msg = InstantiateMsg {
+        max_age: Some(150),
+        ..instantiate_msg
+    };
+    instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+    let consult_msg = |allow_spot| QueryMsg::Consult {
+        asset_infos: asset_infos.clone(),
+        token: uusd_asset_info.clone(),
+        amount: Uint128::from(1000u128),
+        window: None,
+        allow_spot,
+        geometric: None,
+    };
+
+    // no full period has elapsed, the spot price is used only if explicitly allowed
+    let err = query(deps.as_ref(), env.clone(), consult_msg(None)).unwrap_err();
+    assert_eq!(err.to_string(), "No TWAP available yet");
+
+    let res: Uint256 =
+        from_binary(&query(deps.as_ref(), env.clone(), consult_msg(Some(true))).unwrap()).unwrap();
+    assert_eq!(res, Uint256::from(1000u128));
+
+    env.block.time = env.block.time.plus_seconds(151);
+    let err = query(deps.as_ref(), env, consult_msg(Some(true))).unwrap_err();
+    assert_eq!(err.to_string(), "Price of uusd in astro-token is stale");
+}
+
+#[test]
+fn consult_geometric() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let asset_infos = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let asset = [
+        Asset {
+            info: astro_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: usdc_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+    ];
+
+    let set_cumulative_price =
+        |querier: &mut WasmMockQuerier, price0: u128, price1: u128, log_price: u128| {
+            querier.set_cumulative_price(
+                Addr::unchecked("pair"),
+                asset.clone(),
+                Uint128::zero(),
+                Uint128::from(price0),
+                Uint128::from(price1),
+            );
+            querier.set_log_price_cumulative(Addr::unchecked("pair"), Uint128::from(log_price));
+        };
+
+    set_cumulative_price(&mut deps.querier, 0, 0, 0);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        InstantiateMsg {
+            factory_contract: String::from("factory"),
+            pairs: vec![asset_infos.clone()],
+            period: Some(600),
+            max_observations: None,
+            max_age: None,
+        },
+    )
+    .unwrap();
+
+    // 4 USDC per ASTRO during 300 seconds then 1 USDC per ASTRO during 300 seconds
+    env.block.time = env.block.time.plus_seconds(600);
+    set_cumulative_price(
+        &mut deps.querier,
+        300 * 4_000000 + 300 * 1_000000,
+        300 * 250000 + 300 * 1_000000,
+        300 * 2_000000000000000000,
+    );
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+
+    let consult =
+        |deps: Deps, env: Env, token: &AssetInfo, window: Option<u64>, geometric: Option<bool>| {
+            let res: Uint256 = from_binary(
+                &query(
+                    deps,
+                    env,
+                    QueryMsg::Consult {
+                        asset_infos: asset_infos.clone(),
+                        token: token.clone(),
+                        amount: Uint128::from(1000u128),
+                        window,
+                        allow_spot: None,
+                        geometric,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res
+        };
+
+    // the arithmetic means of both directions are not reciprocal, the geometric ones are
+    assert_eq!(
+        consult(deps.as_ref(), env.clone(), &astro_asset_info, None, None),
+        Uint256::from(2500u128)
+    );
+    assert_eq!(
+        consult(deps.as_ref(), env.clone(), &usdc_asset_info, None, None),
+        Uint256::from(625u128)
+    );
+    assert_eq!(
+        consult(
+            deps.as_ref(),
+            env.clone(),
+            &astro_asset_info,
+            None,
+            Some(true)
+        ),
+        Uint256::from(2000u128)
+    );
+    assert_eq!(
+        consult(
+            deps.as_ref(),
+            env.clone(),
+            &usdc_asset_info,
+            None,
+            Some(true)
+        ),
+        Uint256::from(500u128)
+    );
+
+    // 1 USDC per ASTRO during 300 more seconds, the log price at the window start
+    // is interpolated between the observations: 2^((600 - 300) / 600)
+    env.block.time = env.block.time.plus_seconds(300);
+    set_cumulative_price(
+        &mut deps.querier,
+        300 * 4_000000 + 600 * 1_000000,
+        300 * 250000 + 600 * 1_000000,
+        300 * 2_000000000000000000,
+    );
+    assert_eq!(
+        consult(
+            deps.as_ref(),
+            env.clone(),
+            &astro_asset_info,
+            Some(600),
+            Some(true)
+        ),
+        Uint256::from(1414u128)
+    );
+    assert_eq!(
+        consult(deps.as_ref(), env, &usdc_asset_info, Some(600), Some(true)),
+        Uint256::from(707u128)
+    );
+}
+
+#[test]
+fn consult_geometric_low_price() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let asset_infos = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let asset = [
+        Asset {
+            info: astro_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: usdc_asset_info,
+            amount: Uint128::zero(),
+        },
+    ];
+
+    let set_cumulative_price =
+        |querier: &mut WasmMockQuerier, price0: u128, price1: u128, log_price: u128| {
+            querier.set_cumulative_price(
+                Addr::unchecked("pair"),
+                asset.clone(),
+                Uint128::zero(),
+                Uint128::from(price0),
+                Uint128::from(price1),
+            );
+            querier.set_log_price_cumulative(Addr::unchecked("pair"), Uint128::from(log_price));
+        };
+
+    set_cumulative_price(&mut deps.querier, 0, 0, 0);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        InstantiateMsg {
+            factory_contract: String::from("factory"),
+            pairs: vec![asset_infos.clone()],
+            period: Some(600),
+            max_observations: None,
+            max_age: None,
+        },
+    )
+    .unwrap();
+
+    let consult_msg = |window: Option<u64>, geometric: Option<bool>| QueryMsg::Consult {
+        asset_infos: asset_infos.clone(),
+        token: astro_asset_info.clone(),
+        amount: Uint128::from(1_000000_000000u128),
+        window,
+        allow_spot: None,
+        geometric,
+    };
+
+    // nothing is accumulated without liquidity, a zero log price doesn't mean the price of one
+    env.block.time = env.block.time.plus_seconds(600);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(300);
+    for consult_msg in [
+        consult_msg(None, Some(true)),
+        consult_msg(Some(600), None),
+        consult_msg(Some(600), Some(true)),
+    ] {
+        let err = query(deps.as_ref(), env.clone(), consult_msg).unwrap_err();
+        assert_eq!(err.to_string(), "No TWAP available yet");
+    }
+
+    // 2^-30 USDC per ASTRO during 600 seconds, the arithmetic price rounds to zero
+    env.block.time = env.block.time.plus_seconds(300);
+    let log_price = (-600 * 30 * LOG_PRICE_ONE as i128) as u128;
+    set_cumulative_price(&mut deps.querier, 0, 600 * (1 << 30) * 1_000000, log_price);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+
+    let res: Uint256 =
+        from_binary(&query(deps.as_ref(), env, consult_msg(None, Some(true))).unwrap()).unwrap();
+    assert_eq!(res, Uint256::from(931u128));
+}
+
+#[test]
+fn consult_liquidity() {
+    let mut deps = mock_dependencies(&[]);
+    let info = mock_info("addr0000", &[]);
+
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let asset_infos = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let asset = [
+        Asset {
+            info: astro_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: usdc_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+    ];
+
+    let set_reserves_cumulative =
+        |querier: &mut WasmMockQuerier, reserve0: u128, reserve1: u128| {
+            querier.set_cumulative_price(
+                Addr::unchecked("pair"),
+                asset.clone(),
+                Uint128::from(2000u128),
+                Uint128::zero(),
+                Uint128::zero(),
+            );
+            querier.set_reserves_cumulative(
+                Addr::unchecked("pair"),
+                Uint128::from(reserve0),
+                Uint128::from(reserve1),
+            );
+        };
+
+    set_reserves_cumulative(&mut deps.querier, 0, 0);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        InstantiateMsg {
+            factory_contract: String::from("factory"),
+            pairs: vec![asset_infos.clone()],
+            period: Some(300),
+            max_observations: None,
+            max_age: None,
+        },
+    )
+    .unwrap();
+
+    let consult_msg = |window| QueryMsg::ConsultLiquidity {
+        asset_infos: asset_infos.clone(),
+        window,
+    };
+
+    // no full period has elapsed
+    let err = query(deps.as_ref(), env.clone(), consult_msg(None)).unwrap_err();
+    assert_eq!(err.to_string(), "No TWAP available yet");
+
+    // 1000 ASTRO and 4000 USDC during the first period
+    env.block.time = env.block.time.plus_seconds(300);
+    set_reserves_cumulative(&mut deps.querier, 300 * 1000, 300 * 4000);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+
+    let res: ConsultLiquidityResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), consult_msg(None)).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        ConsultLiquidityResponse {
+            assets: [
+                Asset {
+                    info: astro_asset_info.clone(),
+                    amount: Uint128::from(1000u128),
+                },
+                Asset {
+                    info: usdc_asset_info.clone(),
+                    amount: Uint128::from(4000u128),
+                },
+            ],
+            total_share: Uint128::from(2000u128),
+        }
+    );
+
+    // 2000 ASTRO and 8000 USDC during 100 more seconds, the window starts 100 seconds
+    // before the last update
+    env.block.time = env.block.time.plus_seconds(100);
+    set_reserves_cumulative(
+        &mut deps.querier,
+        300 * 1000 + 100 * 2000,
+        300 * 4000 + 100 * 8000,
+    );
+    let res: ConsultLiquidityResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), consult_msg(Some(200))).unwrap()).unwrap();
+    assert_eq!(res.assets[0].amount, Uint128::from(1500u128));
+    assert_eq!(res.assets[1].amount, Uint128::from(6000u128));
+
+    // the default max age is two periods
+    env.block.time = env.block.time.plus_seconds(501);
+    let err = query(deps.as_ref(), env, consult_msg(None)).unwrap_err();
+    assert_eq!(err.to_string(), "Liquidity of pair is stale");
+}
+
+#[test]
+fn migrate_single_pair_oracle() {
+    let mut deps = mock_dependencies(&[]);
+    let mut env = mock_env();
+
+    let astro_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("astro-token"),
+    };
+    let usdc_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdc-token"),
+    };
+    let asset_infos = [astro_asset_info.clone(), usdc_asset_info.clone()];
+    let asset = [
+        Asset {
+            info: astro_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: usdc_asset_info.clone(),
+            amount: Uint128::zero(),
+        },
+    ];
+
+    set_contract_version(deps.as_mut().storage, "astroport-oracle", "1.0.0").unwrap();
+    CONFIG_V100
+        .save(
+            deps.as_mut().storage,
+            &ConfigV100 {
+                owner: Addr::unchecked("owner"),
+                factory: Addr::unchecked("factory"),
+                asset_infos: asset_infos.clone(),
+                pair: astroport::asset::PairInfo {
+                    asset_infos: asset_infos.clone(),
+                    contract_addr: Addr::unchecked("pair"),
+                    liquidity_token: Addr::unchecked("lp_token"),
+                    pair_type: PairType::Xyk {},
+                },
+            },
+        )
+        .unwrap();
+    PRICE_LAST_V100
+        .save(
+            deps.as_mut().storage,
+            &PriceCumulativeLastV100 {
+                price0_cumulative_last: Uint128::from(100u128),
+                price1_cumulative_last: Uint128::from(100u128),
+                price_0_average: Decimal256::one(),
+                price_1_average: Decimal256::one(),
+                block_timestamp_last: env.block.time.seconds() - 86400,
+            },
+        )
+        .unwrap();
+
+    deps.querier.set_cumulative_price(
+        Addr::unchecked("pair"),
+        asset.clone(),
+        Uint128::zero(),
+        Uint128::from(1_000000u128),
+        Uint128::from(1_000000u128),
+    );
+
+    let msg = MigrateMsg {
+        period: Some(0),
+        max_observations: None,
+        max_age: None,
+    };
+    let err = migrate(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Period must be positive");
+
+    let msg = MigrateMsg {
+        period: None,
+        max_observations: None,
+        max_age: None,
+    };
+    migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.owner, Addr::unchecked("owner"));
+    assert_eq!(config.factory, Addr::unchecked("factory"));
+    assert_eq!(config.period, 86400);
+
+    let key = pair_key(&asset_infos);
+    assert_eq!(
+        PAIRS
+            .load(deps.as_ref().storage, &key)
+            .unwrap()
+            .contract_addr,
+        Addr::unchecked("pair")
+    );
+    let price_last = PRICE_LAST.load(deps.as_ref().storage, &key).unwrap();
+    assert_eq!(
+        price_last.price0_cumulative_last,
+        Uint128::from(1_000000u128)
+    );
+    assert_eq!(price_last.block_timestamp_last, env.block.time.seconds());
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let err = migrate(deps.as_mut(), env.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Contract can't be migrated!");
+
+    // the pair gets a TWAP after its first update
+    deps.querier.set_cumulative_price(
+        Addr::unchecked("pair"),
+        asset,
+        Uint128::zero(),
+        Uint128::from(1_000000u128 + 86400 * 2_000000),
+        Uint128::from(1_000000u128 + 86400 * 500000),
+    );
+    env.block.time = env.block.time.plus_seconds(86400);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Update {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap();
+
+    let res: Uint256 = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Consult {
+                asset_infos,
+                token: astro_asset_info,
+                amount: Uint128::from(1_000000u128),
+                window: None,
+                allow_spot: None,
+                geometric: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, Uint256::from(2_000000u128));
 }
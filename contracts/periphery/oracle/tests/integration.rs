@@ -12,7 +12,7 @@ use astroport::factory::{PairConfig, PairType};
 
 use astroport::oracle::QueryMsg::Consult;
 use astroport::oracle::{ExecuteMsg, InstantiateMsg};
-use astroport::pair::StablePoolParams;
+use astroport::pair::{StablePoolParams, StablePoolUpdateParams};
 
 fn mock_app() -> App {
     let env = mock_env();
@@ -500,6 +500,11 @@ pub fn next_day(block: &mut BlockInfo) {
     block.height += 17280;
 }
 
+pub fn next_hour(block: &mut BlockInfo) {
+    block.time = block.time.plus_seconds(3600);
+    block.height += 720;
+}
+
 #[test]
 fn consult() {
     let mut router = mock_app();
@@ -565,7 +570,10 @@ fn consult() {
 
     let msg = InstantiateMsg {
         factory_contract: factory_instance.to_string(),
-        asset_infos: asset_infos.clone(),
+        pairs: vec![asset_infos.clone()],
+        period: None,
+        max_observations: None,
+        max_age: None,
     };
     let oracle_instance = router
         .instantiate_contract(
@@ -582,7 +590,9 @@ fn consult() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap_err();
@@ -605,7 +615,9 @@ fn consult() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap();
@@ -615,10 +627,14 @@ fn consult() {
         (usdc_token_instance.clone(), Uint128::from(100u128)),
     ] {
         let msg = Consult {
+            asset_infos: asset_infos.clone(),
             token: AssetInfo::Token {
                 contract_addr: addr,
             },
             amount,
+            window: None,
+            allow_spot: None,
+            geometric: None,
         };
         let res: Uint128 = router
             .wrap()
@@ -696,7 +712,10 @@ fn consult_pair_stable() {
 
     let msg = InstantiateMsg {
         factory_contract: factory_instance.to_string(),
-        asset_infos: asset_infos.clone(),
+        pairs: vec![asset_infos.clone()],
+        period: None,
+        max_observations: None,
+        max_age: None,
     };
     let oracle_instance = router
         .instantiate_contract(
@@ -713,7 +732,9 @@ fn consult_pair_stable() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap_err();
@@ -736,7 +757,9 @@ fn consult_pair_stable() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap();
@@ -746,10 +769,14 @@ fn consult_pair_stable() {
         (usdc_token_instance.clone(), Uint128::from(100u128)),
     ] {
         let msg = Consult {
+            asset_infos: asset_infos.clone(),
             token: AssetInfo::Token {
                 contract_addr: addr,
             },
             amount,
+            window: None,
+            allow_spot: None,
+            geometric: None,
         };
         let res: Uint128 = router
             .wrap()
@@ -827,7 +854,10 @@ fn consult2() {
 
     let msg = InstantiateMsg {
         factory_contract: factory_instance.to_string(),
-        asset_infos: asset_infos.clone(),
+        pairs: vec![asset_infos.clone()],
+        period: None,
+        max_observations: None,
+        max_age: None,
     };
     let oracle_instance = router
         .instantiate_contract(
@@ -844,7 +874,9 @@ fn consult2() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap_err();
@@ -854,7 +886,9 @@ fn consult2() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap();
@@ -879,7 +913,9 @@ fn consult2() {
             .execute_contract(
                 owner.clone(),
                 oracle_instance.clone(),
-                &ExecuteMsg::Update {},
+                &ExecuteMsg::Update {
+                    asset_infos: asset_infos.clone(),
+                },
                 &[],
             )
             .unwrap();
@@ -897,10 +933,14 @@ fn consult2() {
         ),
     ] {
         let msg = Consult {
+            asset_infos: asset_infos.clone(),
             token: AssetInfo::Token {
                 contract_addr: addr,
             },
             amount,
+            window: None,
+            allow_spot: None,
+            geometric: None,
         };
         let res: Uint128 = router
             .wrap()
@@ -932,7 +972,9 @@ fn consult2() {
             .execute_contract(
                 owner.clone(),
                 oracle_instance.clone(),
-                &ExecuteMsg::Update {},
+                &ExecuteMsg::Update {
+                    asset_infos: asset_infos.clone(),
+                },
                 &[],
             )
             .unwrap();
@@ -950,10 +992,14 @@ fn consult2() {
         ),
     ] {
         let msg = Consult {
+            asset_infos: asset_infos.clone(),
             token: AssetInfo::Token {
                 contract_addr: addr,
             },
             amount,
+            window: None,
+            allow_spot: None,
+            geometric: None,
         };
         let res: Uint128 = router
             .wrap()
@@ -1009,7 +1055,10 @@ fn consult_zero_price() {
     router.update_block(next_day);
     let msg = InstantiateMsg {
         factory_contract: factory_instance.to_string(),
-        asset_infos: asset_infos.clone(),
+        pairs: vec![asset_infos.clone()],
+        period: None,
+        max_observations: None,
+        max_age: None,
     };
     let oracle_instance = router
         .instantiate_contract(
@@ -1026,7 +1075,9 @@ fn consult_zero_price() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap_err();
@@ -1036,7 +1087,9 @@ fn consult_zero_price() {
         .execute_contract(
             owner.clone(),
             oracle_instance.clone(),
-            &ExecuteMsg::Update {},
+            &ExecuteMsg::Update {
+                asset_infos: asset_infos.clone(),
+            },
             &[],
         )
         .unwrap();
@@ -1054,10 +1107,14 @@ fn consult_zero_price() {
         ),
     ] {
         let msg = Consult {
+            asset_infos: asset_infos.clone(),
             token: AssetInfo::Token {
                 contract_addr: addr,
             },
             amount: amount_in,
+            window: None,
+            allow_spot: None,
+            geometric: None,
         };
         let res: Uint128 = router
             .wrap()
@@ -1069,3 +1126,174 @@ fn consult_zero_price() {
         assert_eq!(res, amount_out);
     }
 }
+
+#[test]
+fn consult_pair_stable_amp_ramp() {
+    let mut router = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user0000");
+    let (astro_token_instance, factory_instance, oracle_code_id) =
+        instantiate_contracts(&mut router, owner.clone());
+
+    let usdc_token_instance = instantiate_token(
+        &mut router,
+        owner.clone(),
+        "Usdc token".to_string(),
+        "USDC".to_string(),
+    );
+
+    let asset_infos = [
+        AssetInfo::Token {
+            contract_addr: usdc_token_instance.clone(),
+        },
+        AssetInfo::Token {
+            contract_addr: astro_token_instance.clone(),
+        },
+    ];
+    // imbalanced pool, so the price depends on the amp
+    let pair_info = create_pair_stable(
+        &mut router,
+        owner.clone(),
+        user.clone(),
+        &factory_instance,
+        [
+            Asset {
+                info: asset_infos[0].clone(),
+                amount: Uint128::from(100_000_000000u128),
+            },
+            Asset {
+                info: asset_infos[1].clone(),
+                amount: Uint128::from(300_000_000000u128),
+            },
+        ],
+    );
+    // the amp can't be changed during the first day
+    router.update_block(next_day);
+
+    let msg = InstantiateMsg {
+        factory_contract: factory_instance.to_string(),
+        pairs: vec![asset_infos.clone()],
+        period: Some(3600),
+        max_observations: Some(100),
+        max_age: None,
+    };
+    let oracle_instance = router
+        .instantiate_contract(
+            oracle_code_id,
+            owner.clone(),
+            &msg,
+            &[],
+            String::from("ORACLE"),
+            None,
+        )
+        .unwrap();
+
+    // pair accumulators are updated on every pair interaction, provide liquidity at the pool
+    // ratio every hour so the prices move only with the amp
+    let next_hour_update = |router: &mut App| -> (Uint128, Uint128) {
+        router.update_block(next_hour);
+        change_provide_liquidity(
+            router,
+            owner.clone(),
+            user.clone(),
+            pair_info.contract_addr.clone(),
+            astro_token_instance.clone(),
+            usdc_token_instance.clone(),
+            Uint128::from(3_000000u128),
+            Uint128::from(1_000000u128),
+        );
+        router
+            .execute_contract(
+                owner.clone(),
+                oracle_instance.clone(),
+                &ExecuteMsg::Update {
+                    asset_infos: asset_infos.clone(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let consult = |token: &Addr| -> Uint128 {
+            router
+                .wrap()
+                .query_wasm_smart(
+                    oracle_instance.clone(),
+                    &Consult {
+                        asset_infos: asset_infos.clone(),
+                        token: AssetInfo::Token {
+                            contract_addr: token.clone(),
+                        },
+                        amount: Uint128::from(1_000000u128),
+                        window: None,
+                        allow_spot: None,
+                        geometric: None,
+                    },
+                )
+                .unwrap()
+        };
+        (
+            consult(&astro_token_instance),
+            consult(&usdc_token_instance),
+        )
+    };
+
+    let (astro_price_before, usdc_price_before) = next_hour_update(&mut router);
+    // astro is abundant in the pool
+    assert!(astro_price_before < Uint128::from(1_000000u128));
+    assert!(usdc_price_before > Uint128::from(1_000000u128));
+
+    // ramp the amp from 100 to 200 over a day
+    let ramp_start = router.block_info().time.seconds();
+    router
+        .execute_contract(
+            owner.clone(),
+            pair_info.contract_addr.clone(),
+            &astroport::pair::ExecuteMsg::UpdateConfig {
+                params: to_binary(&StablePoolUpdateParams::StartChangingAmp {
+                    next_amp: 200,
+                    next_amp_time: ramp_start + 86400,
+                })
+                .unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // the hourly TWAPs move towards parity as the amp grows
+    let (mut astro_price, mut usdc_price) = (astro_price_before, usdc_price_before);
+    for _ in 0..24 {
+        let (astro_price_new, usdc_price_new) = next_hour_update(&mut router);
+        assert!(astro_price_new >= astro_price);
+        assert!(usdc_price_new <= usdc_price);
+        astro_price = astro_price_new;
+        usdc_price = usdc_price_new;
+    }
+    assert!(astro_price > astro_price_before);
+    assert!(usdc_price < usdc_price_before);
+    assert!(astro_price < Uint128::from(1_000000u128));
+
+    // the TWAP over the whole ramp is between the prices at its ends
+    let astro_price_ramp: Uint128 = router
+        .wrap()
+        .query_wasm_smart(
+            oracle_instance.clone(),
+            &Consult {
+                asset_infos: asset_infos.clone(),
+                token: asset_infos[1].clone(),
+                amount: Uint128::from(1_000000u128),
+                window: Some(86400),
+                allow_spot: None,
+                geometric: None,
+            },
+        )
+        .unwrap();
+    assert!(astro_price_ramp > astro_price_before);
+    assert!(astro_price_ramp < astro_price);
+
+    // the amp doesn't change after the ramp, up to rounding
+    let (astro_price_after, usdc_price_after) = next_hour_update(&mut router);
+    let (astro_price_final, usdc_price_final) = next_hour_update(&mut router);
+    let diff = |a: Uint128, b: Uint128| if a > b { a - b } else { b - a };
+    assert!(diff(astro_price_after, astro_price_final) <= Uint128::from(1u128));
+    assert!(diff(usdc_price_after, usdc_price_final) <= Uint128::from(1u128));
+}
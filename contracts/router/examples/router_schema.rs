@@ -2,7 +2,9 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use astroport::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, ExternalVenuesResponse, FindBestRouteResponse,
+    InstantiateMsg, MigrateMsg, QueryMsg, SimulateReverseSwapOperationsResponse,
+    SimulateSplitSwapResponse, SimulateSwapOperationsDetailedResponse,
     SimulateSwapOperationsResponse,
 };
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
@@ -20,4 +22,15 @@ fn main() {
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(SimulateSwapOperationsResponse), &out_dir);
+    export_schema(
+        &schema_for!(SimulateSwapOperationsDetailedResponse),
+        &out_dir,
+    );
+    export_schema(&schema_for!(SimulateSplitSwapResponse), &out_dir);
+    export_schema(&schema_for!(FindBestRouteResponse), &out_dir);
+    export_schema(
+        &schema_for!(SimulateReverseSwapOperationsResponse),
+        &out_dir,
+    );
+    export_schema(&schema_for!(ExternalVenuesResponse), &out_dir);
 }
@@ -1,22 +1,31 @@
 use cosmwasm_std::{
-    entry_point, from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, QueryRequest, Response, StdError, StdResult, Uint128, WasmMsg, WasmQuery,
+    entry_point, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, ContractResult,
+    CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, QueryRequest, Reply, Response, StdError,
+    StdResult, SubMsg, Uint128, WasmMsg, WasmQuery,
 };
 
 use crate::error::ContractError;
-use crate::operations::execute_swap_operation;
-use crate::querier::compute_tax;
-use crate::state::{Config, CONFIG};
+use crate::migration;
+use crate::operations::{execute_swap_operation, load_simulation_adapter, swap_operation_msg};
+use crate::querier::{compute_reverse_tax, compute_tax};
+use crate::routes::{find_candidate_routes, query_pairs_graph};
+use crate::state::{read_external_venues, Config, CONFIG, EXTERNAL_VENUES, OWNERSHIP_PROPOSAL};
 
 use astroport::asset::{addr_validate_to_lower, Asset, AssetInfo, PairInfo};
+use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::pair::{QueryMsg as PairQueryMsg, SimulationResponse};
-use astroport::querier::query_pair_info;
+use astroport::querier::{query_pair_info, reverse_simulate};
 use astroport::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation, MAX_SWAP_OPERATIONS,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, ExternalVenue, ExternalVenuesResponse,
+    FindBestRouteResponse, InstantiateMsg, MigrateMsg, QueryMsg,
+    SimulateReverseSwapOperationsResponse, SimulateSplitSwapResponse,
+    SimulateSwapOperationsDetailedResponse, SimulateSwapOperationsResponse, SwapOperation,
+    SwapOperationSimulation, SwapRoute, DEFAULT_MAX_PAIRS_PAGES, DEFAULT_MAX_ROUTE_CANDIDATES,
+    DEFAULT_MAX_ROUTE_HOPS, MAX_PAIRS_PAGES, MAX_ROUTE_CANDIDATES, MAX_ROUTE_HOPS,
+    MAX_SWAP_OPERATIONS,
 };
-use cw2::set_contract_version;
-use cw20::Cw20ReceiveMsg;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use std::collections::HashMap;
 use terra_cosmwasm::{SwapResponse, TerraMsgWrapper, TerraQuerier};
 
@@ -33,12 +42,18 @@ pub fn instantiate(
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    CONFIG.save(
-        deps.storage,
-        &Config {
-            astroport_factory: addr_validate_to_lower(deps.api, &msg.astroport_factory)?,
-        },
-    )?;
+    let config = Config {
+        owner: addr_validate_to_lower(deps.api, &msg.owner)?,
+        astroport_factory: addr_validate_to_lower(deps.api, &msg.astroport_factory)?,
+        max_route_hops: msg.max_route_hops.unwrap_or(DEFAULT_MAX_ROUTE_HOPS),
+        max_route_candidates: msg
+            .max_route_candidates
+            .unwrap_or(DEFAULT_MAX_ROUTE_CANDIDATES),
+        max_pairs_pages: msg.max_pairs_pages.unwrap_or(DEFAULT_MAX_PAIRS_PAGES),
+    };
+    assert_route_limits(&config)?;
+
+    CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::default())
 }
@@ -56,6 +71,8 @@ pub fn execute(
             operations,
             minimum_receive,
             to,
+            max_spread,
+            callback,
         } => execute_swap_operations(
             deps,
             env,
@@ -64,7 +81,114 @@ pub fn execute(
             operations,
             minimum_receive,
             to,
+            max_spread,
+            callback,
+        ),
+        ExecuteMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+        } => {
+            if info.funds.len() != 1 {
+                return Err(ContractError::InvalidOfferAsset {});
+            }
+
+            let offer_asset = Asset {
+                info: AssetInfo::NativeToken {
+                    denom: info.funds[0].denom.clone(),
+                },
+                amount: info.funds[0].amount,
+            };
+
+            execute_split_swap(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                routes,
+                minimum_receive,
+                to,
+            )
+        }
+        ExecuteMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            max_offer,
+            to,
+            max_spread,
+        } => {
+            let offer_asset = Asset {
+                info: operations
+                    .first()
+                    .ok_or(ContractError::MustProvideOperations {})?
+                    .get_offer_asset_info(),
+                amount: max_offer,
+            };
+            // Only the offer coin may be sent, other coins wouldn't be refunded
+            if !offer_asset.is_native_token() || info.funds.len() != 1 {
+                return Err(ContractError::InvalidOfferAsset {});
+            }
+            offer_asset.assert_sent_native_token_balance(&info)?;
+
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                to,
+                max_spread,
+            )
+        }
+        ExecuteMsg::UpdateConfig {
+            max_route_hops,
+            max_route_candidates,
+            max_pairs_pages,
+        } => update_config(
+            deps,
+            info,
+            max_route_hops,
+            max_route_candidates,
+            max_pairs_pages,
         ),
+        ExecuteMsg::UpdateExternalVenues { add, remove } => {
+            update_external_venues(deps, info, add, remove)
+        }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            propose_new_owner(
+                deps,
+                info,
+                env,
+                owner,
+                expires_in,
+                config.owner,
+                OWNERSHIP_PROPOSAL,
+            )
+            .map(|res| Response::new().add_attributes(res.attributes))
+            .map_err(|e| e.into())
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            drop_ownership_proposal(deps, info, config.owner, OWNERSHIP_PROPOSAL)
+                .map(|res| Response::new().add_attributes(res.attributes))
+                .map_err(|e| e.into())
+        }
+        ExecuteMsg::ClaimOwnership {} => {
+            claim_ownership(deps, info, env, OWNERSHIP_PROPOSAL, |deps, new_owner| {
+                CONFIG.update::<_, StdError>(deps.storage, |mut v| {
+                    v.owner = new_owner;
+                    Ok(v)
+                })?;
+
+                Ok(())
+            })
+            .map(|res| Response::new().add_attributes(res.attributes))
+            .map_err(|e| e.into())
+        }
         ExecuteMsg::ExecuteSwapOperation { operation, to } => {
             execute_swap_operation(deps, env, info, operation, to)
         }
@@ -80,6 +204,22 @@ pub fn execute(
             minimum_receive,
             addr_validate_to_lower(deps.api, &receiver)?,
         ),
+        ExecuteMsg::SendWithCallback {
+            asset_info,
+            prev_balance,
+            minimum_receive,
+            receiver,
+            callback,
+        } => send_with_callback(
+            deps.as_ref(),
+            env,
+            info,
+            asset_info,
+            prev_balance,
+            minimum_receive,
+            addr_validate_to_lower(deps.api, &receiver)?,
+            callback,
+        ),
     }
 }
 
@@ -95,6 +235,8 @@ pub fn receive_cw20(
             operations,
             minimum_receive,
             to,
+            max_spread,
+            callback,
         } => {
             let to_addr = if let Some(to_addr) = to {
                 Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
@@ -110,11 +252,157 @@ pub fn receive_cw20(
                 operations,
                 minimum_receive,
                 to_addr,
+                max_spread,
+                callback,
+            )
+        }
+        Cw20HookMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+        } => {
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender,
+                },
+                amount: cw20_msg.amount,
+            };
+
+            execute_split_swap(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                routes,
+                minimum_receive,
+                to_addr,
+            )
+        }
+        Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            to,
+            max_spread,
+        } => {
+            let to_addr = if let Some(to_addr) = to {
+                Some(addr_validate_to_lower(deps.api, to_addr.as_str())?)
+            } else {
+                None
+            };
+
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender,
+                },
+                amount: cw20_msg.amount,
+            };
+
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                to_addr,
+                max_spread,
             )
         }
     }
 }
 
+/// Update the FindBestRoute limits
+/// Only owner can execute it
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_route_hops: Option<u32>,
+    max_route_candidates: Option<u32>,
+    max_pairs_pages: Option<u32>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(max_route_hops) = max_route_hops {
+        config.max_route_hops = max_route_hops;
+    }
+
+    if let Some(max_route_candidates) = max_route_candidates {
+        config.max_route_candidates = max_route_candidates;
+    }
+
+    if let Some(max_pairs_pages) = max_pairs_pages {
+        config.max_pairs_pages = max_pairs_pages;
+    }
+
+    assert_route_limits(&config)?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+fn assert_route_limits(config: &Config) -> Result<(), ContractError> {
+    if config.max_route_hops == 0
+        || config.max_route_hops > MAX_ROUTE_HOPS
+        || config.max_route_candidates == 0
+        || config.max_route_candidates > MAX_ROUTE_CANDIDATES
+        || config.max_pairs_pages == 0
+        || config.max_pairs_pages > MAX_PAIRS_PAGES
+    {
+        return Err(ContractError::InvalidRouteLimits {});
+    }
+
+    Ok(())
+}
+
+/// Whitelist external venues or remove them from the whitelist
+/// Only owner can execute it
+pub fn update_external_venues(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<ExternalVenue>,
+    remove: Vec<String>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for venue in remove {
+        let venue = addr_validate_to_lower(deps.api, &venue)?;
+        EXTERNAL_VENUES.remove(deps.storage, &venue);
+    }
+
+    for ExternalVenue {
+        venue,
+        simulation_adapter,
+    } in add
+    {
+        let venue = addr_validate_to_lower(deps.api, &venue)?;
+        let simulation_adapter = match simulation_adapter {
+            Some(simulation_adapter) => addr_validate_to_lower(deps.api, &simulation_adapter)?,
+            None => venue.clone(),
+        };
+        EXTERNAL_VENUES.save(deps.storage, &venue, &simulation_adapter)?;
+    }
+
+    Ok(Response::new().add_attribute("action", "update_external_venues"))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operations(
     deps: DepsMut,
     env: Env,
@@ -123,6 +411,8 @@ pub fn execute_swap_operations(
     operations: Vec<SwapOperation>,
     minimum_receive: Option<Uint128>,
     to: Option<Addr>,
+    max_spread: Option<Decimal>,
+    callback: Option<Binary>,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let operations_len = operations.len();
     if operations_len == 0 {
@@ -144,42 +434,274 @@ pub fn execute_swap_operations(
 
     let target_asset_info = operations.last().unwrap().get_target_asset_info();
 
-    let mut operation_index = 0;
-    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = operations
+    let operations = operations
         .into_iter()
-        .map(|op| {
-            operation_index += 1;
-            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: env.contract.address.to_string(),
-                funds: vec![],
-                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
-                    operation: op,
-                    to: if operation_index == operations_len {
-                        Some(to.to_string())
-                    } else {
-                        None
-                    },
-                })?,
-            }))
-        })
-        .collect::<StdResult<Vec<CosmosMsg<TerraMsgWrapper>>>>()?;
+        .map(|operation| operation.with_default_max_spread(max_spread))
+        .collect();
 
-    // Execute minimum amount assertion
-    if let Some(minimum_receive) = minimum_receive {
-        let receiver_balance = target_asset_info.query_pool(&deps.querier, to.clone())?;
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+    // With a callback, the output stays in the router until it is sent along with the callback
+    if let Some(callback) = callback {
+        let prev_balance =
+            target_asset_info.query_pool(&deps.querier, env.contract.address.clone())?;
+
+        let mut messages = operations_into_msgs(deps.as_ref(), &env, operations, None, None, 1)?;
+        messages.push(SubMsg::new(WasmMsg::Execute {
             contract_addr: env.contract.address.to_string(),
             funds: vec![],
-            msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            msg: to_binary(&ExecuteMsg::SendWithCallback {
                 asset_info: target_asset_info,
-                prev_balance: receiver_balance,
+                prev_balance,
                 minimum_receive,
                 receiver: to.to_string(),
+                callback,
             })?,
         }));
+
+        return Ok(Response::new().add_submessages(messages));
+    }
+
+    let mut messages = operations_into_msgs(deps.as_ref(), &env, operations, None, Some(&to), 1)?;
+
+    // Execute minimum amount assertion
+    if let Some(minimum_receive) = minimum_receive {
+        messages.push(SubMsg::new(minimum_receive_msg(
+            deps.as_ref(),
+            &env,
+            target_asset_info,
+            minimum_receive,
+            &to,
+        )?));
+    }
+
+    Ok(Response::new().add_submessages(messages))
+}
+
+/// Split the offer asset across the given routes and execute each of them one after another.
+/// The first operation of every route swaps exactly the route share of the offer asset,
+/// the rest of the route is executed via the regular ExecuteSwapOperation messages.
+pub fn execute_split_swap(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    routes: Vec<SwapRoute>,
+    minimum_receive: Option<Uint128>,
+    to: Option<Addr>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let (offer_asset_info, target_asset_info) = assert_routes(deps.api, &routes)?;
+    if offer_asset.info != offer_asset_info || offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidOfferAsset {});
+    }
+
+    let to = if let Some(to) = to {
+        addr_validate_to_lower(deps.api, to.as_str())?
+    } else {
+        sender
+    };
+
+    let route_amounts = split_offer_amount(offer_asset.amount, &routes)?;
+
+    // Operations are numbered across all routes in the order they were given
+    let mut operation_number = 1;
+    let mut messages: Vec<SubMsg<TerraMsgWrapper>> = vec![];
+    for (route, amount) in routes.into_iter().zip(route_amounts) {
+        let operations_len = route.operations.len() as u64;
+        if !amount.is_zero() {
+            let max_spread = route.max_spread;
+            let operations = route
+                .operations
+                .into_iter()
+                .map(|operation| operation.with_default_max_spread(max_spread))
+                .collect();
+            messages.extend(operations_into_msgs(
+                deps.as_ref(),
+                &env,
+                operations,
+                Some(amount),
+                Some(&to),
+                operation_number,
+            )?);
+        }
+
+        operation_number += operations_len;
+    }
+
+    // Execute minimum amount assertion once all routes are done
+    if let Some(minimum_receive) = minimum_receive {
+        messages.push(SubMsg::new(minimum_receive_msg(
+            deps.as_ref(),
+            &env,
+            target_asset_info,
+            minimum_receive,
+            &to,
+        )?));
+    }
+
+    Ok(Response::new().add_submessages(messages))
+}
+
+/// Swap only the part of the offer asset required to receive ask_amount and send the surplus
+/// back to the sender. The output is checked with the AssertMinimumReceive message.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap_operations_exact_out(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    ask_amount: Uint128,
+    to: Option<Addr>,
+    max_spread: Option<Decimal>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let operations_len = operations.len();
+    if operations_len == 0 {
+        return Err(ContractError::MustProvideOperations {});
+    }
+
+    if operations_len > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::SwapLimitExceeded {});
+    }
+
+    assert_operations(deps.api, &operations)?;
+
+    if offer_asset.info != operations.first().unwrap().get_offer_asset_info() {
+        return Err(ContractError::InvalidOfferAsset {});
+    }
+
+    let offer_amount = reverse_simulate_route(deps.as_ref(), ask_amount, operations.clone())?;
+    if offer_amount > offer_asset.amount {
+        return Err(ContractError::MaxOfferExceeded {
+            max_offer: offer_asset.amount,
+            offer_amount,
+        });
+    }
+
+    let to = if let Some(to) = to {
+        addr_validate_to_lower(deps.api, to.as_str())?
+    } else {
+        sender.clone()
+    };
+
+    let target_asset_info = operations.last().unwrap().get_target_asset_info();
+
+    let operations = operations
+        .into_iter()
+        .map(|operation| operation.with_default_max_spread(max_spread))
+        .collect();
+    let mut messages = operations_into_msgs(
+        deps.as_ref(),
+        &env,
+        operations,
+        Some(offer_amount),
+        Some(&to),
+        1,
+    )?;
+
+    messages.push(SubMsg::new(minimum_receive_msg(
+        deps.as_ref(),
+        &env,
+        target_asset_info,
+        ask_amount,
+        &to,
+    )?));
+
+    // Refund after the assertion, so the refund is never counted as the swap output
+    let surplus = offer_asset.amount.checked_sub(offer_amount)?;
+    if !surplus.is_zero() {
+        messages.push(SubMsg::new(asset_into_msg(
+            deps.as_ref(),
+            Asset {
+                info: offer_asset.info,
+                amount: surplus,
+            },
+            &sender,
+        )?));
     }
 
-    Ok(Response::new().add_messages(messages))
+    Ok(Response::new().add_submessages(messages))
+}
+
+/// Build the messages executing the operations one by one, the last one sends the result to the receiver
+/// or keeps it in the router if there is no receiver.
+/// If the offer amount is given, the first operation swaps exactly that amount,
+/// otherwise every operation swaps the whole router balance of its offer asset.
+/// Every message replies on error, so the failed operation can be reported by its number.
+fn operations_into_msgs(
+    deps: Deps,
+    env: &Env,
+    operations: Vec<SwapOperation>,
+    offer_amount: Option<Uint128>,
+    to: Option<&Addr>,
+    first_operation_number: u64,
+) -> Result<Vec<SubMsg<TerraMsgWrapper>>, ContractError> {
+    let operations_len = operations.len();
+    let mut messages = vec![];
+    for (operation_index, operation) in operations.into_iter().enumerate() {
+        let to = if operation_index + 1 == operations_len {
+            to.map(|to| to.to_string())
+        } else {
+            None
+        };
+
+        let msg = match offer_amount {
+            Some(offer_amount) if operation_index == 0 => {
+                swap_operation_msg(deps, operation, offer_amount, to)?
+            }
+            _ => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation { operation, to })?,
+            }),
+        };
+
+        messages.push(SubMsg::reply_on_error(
+            msg,
+            first_operation_number + operation_index as u64,
+        ));
+    }
+
+    Ok(messages)
+}
+
+fn asset_into_msg(
+    deps: Deps,
+    asset: Asset,
+    recipient: &Addr,
+) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
+    match &asset.info {
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: asset.amount,
+            })?,
+        })),
+        AssetInfo::NativeToken { .. } => Ok(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![asset.deduct_tax(&deps.querier)?],
+        })),
+    }
+}
+
+fn minimum_receive_msg(
+    deps: Deps,
+    env: &Env,
+    asset_info: AssetInfo,
+    minimum_receive: Uint128,
+    receiver: &Addr,
+) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
+    let receiver_balance = asset_info.query_pool(&deps.querier, receiver.clone())?;
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info,
+            prev_balance: receiver_balance,
+            minimum_receive,
+            receiver: receiver.to_string(),
+        })?,
+    }))
 }
 
 fn assert_minimum_receive(
@@ -203,6 +725,85 @@ fn assert_minimum_receive(
     Ok(Response::default())
 }
 
+/// Send the router balance increase since prev_balance to the receiver contract
+/// along with the callback message, the amount left after tax must exceed minimum_receive
+#[allow(clippy::too_many_arguments)]
+fn send_with_callback(
+    deps: Deps,
+    env: Env,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    prev_balance: Uint128,
+    minimum_receive: Option<Uint128>,
+    receiver: Addr,
+    callback: Binary,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    if env.contract.address != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    asset_info.check(deps.api)?;
+    let balance = asset_info.query_pool(&deps.querier, env.contract.address)?;
+    let asset = Asset {
+        info: asset_info,
+        amount: balance.checked_sub(prev_balance)?,
+    };
+
+    let (message, receive_amount) = match &asset.info {
+        AssetInfo::Token { contract_addr } => (
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: receiver.to_string(),
+                    amount: asset.amount,
+                    msg: callback,
+                })?,
+            }),
+            asset.amount,
+        ),
+        AssetInfo::NativeToken { .. } => {
+            let coin = asset.deduct_tax(&deps.querier)?;
+            let receive_amount = coin.amount;
+            (
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: receiver.to_string(),
+                    funds: vec![coin],
+                    msg: callback,
+                }),
+                receive_amount,
+            )
+        }
+    };
+
+    if let Some(minimum_receive) = minimum_receive {
+        if receive_amount < minimum_receive {
+            return Err(ContractError::AssertionMinimumReceive {
+                receive: minimum_receive,
+                amount: receive_amount,
+            });
+        }
+    }
+
+    Ok(Response::new().add_message(message))
+}
+
+/// Swap operations reply on error only, report which of them failed
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    _deps: DepsMut,
+    _env: Env,
+    msg: Reply,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    match msg.result {
+        ContractResult::Err(error) => Err(ContractError::SwapOperationFailed {
+            operation: msg.id,
+            error,
+        }),
+        ContractResult::Ok(_) => Ok(Response::default()),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
@@ -215,21 +816,85 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
             offer_amount,
             operations,
         )?)?),
+        QueryMsg::SimulateSwapOperationsDetailed {
+            offer_amount,
+            operations,
+        } => Ok(to_binary(&simulate_swap_operations_detailed(
+            deps,
+            offer_amount,
+            operations,
+        )?)?),
+        QueryMsg::SimulateSplitSwap {
+            offer_amount,
+            routes,
+        } => Ok(to_binary(&simulate_split_swap(
+            deps,
+            offer_amount,
+            routes,
+        )?)?),
+        QueryMsg::SimulateReverseSwapOperations {
+            ask_amount,
+            operations,
+        } => Ok(to_binary(&simulate_reverse_swap_operations(
+            deps, ask_amount, operations,
+        )?)?),
+        QueryMsg::FindBestRoute {
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        } => Ok(to_binary(&find_best_route(
+            deps,
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        )?)?),
+        QueryMsg::ExternalVenues { start_after, limit } => Ok(to_binary(&query_external_venues(
+            deps,
+            start_after,
+            limit,
+        )?)?),
     }
 }
 
 pub fn query_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
     let state = CONFIG.load(deps.storage)?;
     let resp = ConfigResponse {
+        owner: state.owner.into_string(),
         astroport_factory: state.astroport_factory.into_string(),
+        max_route_hops: state.max_route_hops,
+        max_route_candidates: state.max_route_candidates,
+        max_pairs_pages: state.max_pairs_pages,
     };
 
     Ok(resp)
 }
 
+pub fn query_external_venues(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<ExternalVenuesResponse, ContractError> {
+    let start_after = start_after
+        .map(|venue| addr_validate_to_lower(deps.api, &venue))
+        .transpose()?;
+
+    Ok(ExternalVenuesResponse {
+        venues: read_external_venues(deps, start_after, limit)?,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+
+    match contract_version.version.as_ref() {
+        "1.0.0" => migration::migrate_config(deps.branch(), msg)?,
+        _ => return Err(ContractError::MigrationError {}),
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attribute("action", "migrate"))
 }
 
 fn simulate_swap_operations(
@@ -237,10 +902,51 @@ fn simulate_swap_operations(
     offer_amount: Uint128,
     operations: Vec<SwapOperation>,
 ) -> Result<SimulateSwapOperationsResponse, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    let astroport_factory = config.astroport_factory;
-    let terra_querier = TerraQuerier::new(&deps.querier);
+    let operations_len = operations.len();
+    if operations_len == 0 {
+        return Err(ContractError::MustProvideOperations {});
+    }
+
+    if operations_len > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::SwapLimitExceeded {});
+    }
+
+    assert_operations(deps.api, &operations)?;
+
+    Ok(SimulateSwapOperationsResponse {
+        amount: simulate_route(deps, offer_amount, operations)?,
+    })
+}
+
+fn simulate_swap_operations_detailed(
+    deps: Deps,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> Result<SimulateSwapOperationsDetailedResponse, ContractError> {
+    let operations_len = operations.len();
+    if operations_len == 0 {
+        return Err(ContractError::MustProvideOperations {});
+    }
+
+    if operations_len > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::SwapLimitExceeded {});
+    }
+
+    assert_operations(deps.api, &operations)?;
+
+    let simulations = simulate_route_detailed(deps, offer_amount, operations)?;
 
+    Ok(SimulateSwapOperationsDetailedResponse {
+        amount: simulations.last().unwrap().return_amount,
+        operations: simulations,
+    })
+}
+
+fn simulate_reverse_swap_operations(
+    deps: Deps,
+    ask_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> Result<SimulateReverseSwapOperationsResponse, ContractError> {
     let operations_len = operations.len();
     if operations_len == 0 {
         return Err(ContractError::MustProvideOperations {});
@@ -252,25 +958,126 @@ fn simulate_swap_operations(
 
     assert_operations(deps.api, &operations)?;
 
+    Ok(SimulateReverseSwapOperationsResponse {
+        offer_amount: reverse_simulate_route(deps, ask_amount, operations)?,
+    })
+}
+
+fn simulate_split_swap(
+    deps: Deps,
+    offer_amount: Uint128,
+    routes: Vec<SwapRoute>,
+) -> Result<SimulateSplitSwapResponse, ContractError> {
+    assert_routes(deps.api, &routes)?;
+    let offer_amounts = split_offer_amount(offer_amount, &routes)?;
+
+    let mut route_amounts = vec![];
+    let mut amount = Uint128::zero();
+    for (route, offer_amount) in routes.into_iter().zip(offer_amounts) {
+        let route_amount = if offer_amount.is_zero() {
+            Uint128::zero()
+        } else {
+            simulate_route(deps, offer_amount, route.operations)?
+        };
+
+        amount = amount.checked_add(route_amount)?;
+        route_amounts.push(route_amount);
+    }
+
+    Ok(SimulateSplitSwapResponse {
+        route_amounts,
+        amount,
+    })
+}
+
+/// Simulate every candidate route between the two assets and return the one with the biggest output.
+/// Candidates that fail to simulate (e.g. because of an empty pool) are skipped.
+fn find_best_route(
+    deps: Deps,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    max_hops: Option<u32>,
+) -> Result<FindBestRouteResponse, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    offer_asset.info.check(deps.api)?;
+    ask_asset_info.check(deps.api)?;
+
+    let max_hops = max_hops
+        .unwrap_or(config.max_route_hops)
+        .min(config.max_route_hops);
+
+    let graph = query_pairs_graph(deps, config.astroport_factory, config.max_pairs_pages)?;
+    let candidates = find_candidate_routes(
+        &graph,
+        &offer_asset.info,
+        &ask_asset_info,
+        max_hops as usize,
+        config.max_route_candidates as usize,
+    );
+
+    let mut best_route: Option<FindBestRouteResponse> = None;
+    for operations in candidates {
+        let amount = match simulate_route(deps, offer_asset.amount, operations.clone()) {
+            Ok(amount) => amount,
+            Err(_) => continue,
+        };
+
+        let is_better = match &best_route {
+            Some(best_route) => amount > best_route.amount,
+            None => true,
+        };
+        if is_better {
+            best_route = Some(FindBestRouteResponse { operations, amount });
+        }
+    }
+
+    best_route.ok_or(ContractError::NoRouteFound {})
+}
+
+/// Simulate already validated operations and return the amount the receiver would get
+fn simulate_route(
+    deps: Deps,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> Result<Uint128, ContractError> {
+    let simulations = simulate_route_detailed(deps, offer_amount, operations)?;
+
+    Ok(simulations
+        .last()
+        .map_or(offer_amount, |simulation| simulation.return_amount))
+}
+
+/// Simulate already validated operations one by one, taxes are deducted wherever the execution deducts them
+fn simulate_route_detailed(
+    deps: Deps,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> Result<Vec<SwapOperationSimulation>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let astroport_factory = config.astroport_factory;
+    let terra_querier = TerraQuerier::new(&deps.querier);
+
+    let operations_len = operations.len();
+
     let mut operation_index = 0;
     let mut offer_amount = offer_amount;
+    let mut simulations = vec![];
     for operation in operations.into_iter() {
         operation_index += 1;
 
-        match operation {
+        let simulation = match operation {
             SwapOperation::NativeSwap {
                 offer_denom,
                 ask_denom,
             } => {
                 // Deduct tax before query simulation
                 // because last swap is swap_send
-                if operation_index == operations_len {
-                    offer_amount = offer_amount.checked_sub(compute_tax(
-                        deps,
-                        offer_amount,
-                        offer_denom.clone(),
-                    )?)?;
-                }
+                let offer_tax_amount = if operation_index == operations_len {
+                    compute_tax(deps, offer_amount, offer_denom.clone())?
+                } else {
+                    Uint128::zero()
+                };
+                let offer_amount = offer_amount.checked_sub(offer_tax_amount)?;
 
                 let res: SwapResponse = terra_querier.query_swap(
                     Coin {
@@ -280,11 +1087,20 @@ fn simulate_swap_operations(
                     ask_denom,
                 )?;
 
-                offer_amount = res.receive.amount;
+                // The market module spread is already included in the swap result
+                SwapOperationSimulation {
+                    offer_amount,
+                    offer_tax_amount,
+                    return_amount: res.receive.amount,
+                    return_tax_amount: Uint128::zero(),
+                    commission_amount: Uint128::zero(),
+                    spread_amount: Uint128::zero(),
+                }
             }
             SwapOperation::AstroSwap {
                 offer_asset_info,
                 ask_asset_info,
+                ..
             } => {
                 let pair_info: PairInfo = query_pair_info(
                     &deps.querier,
@@ -292,40 +1108,166 @@ fn simulate_swap_operations(
                     &[offer_asset_info.clone(), ask_asset_info.clone()],
                 )?;
 
-                // Deduct tax before querying simulation
-                if let AssetInfo::NativeToken { denom } = offer_asset_info.clone() {
-                    offer_amount =
-                        offer_amount.checked_sub(compute_tax(deps, offer_amount, denom)?)?;
-                }
+                simulate_pair_swap(
+                    deps,
+                    pair_info.contract_addr,
+                    offer_asset_info,
+                    ask_asset_info,
+                    offer_amount,
+                )?
+            }
+            SwapOperation::ExternalSwap {
+                venue,
+                offer_asset_info,
+                ask_asset_info,
+                ..
+            } => simulate_pair_swap(
+                deps,
+                load_simulation_adapter(deps, &venue)?,
+                offer_asset_info,
+                ask_asset_info,
+                offer_amount,
+            )?,
+        };
 
-                let mut res: SimulationResponse =
-                    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-                        contract_addr: pair_info.contract_addr.to_string(),
-                        msg: to_binary(&PairQueryMsg::Simulation {
-                            offer_asset: Asset {
-                                info: offer_asset_info,
-                                amount: offer_amount,
-                            },
-                        })?,
-                    }))?;
-
-                // Deduct tax after querying simulation
-                if let AssetInfo::NativeToken { denom } = ask_asset_info.clone() {
-                    res.return_amount = res.return_amount.checked_sub(compute_tax(
-                        deps,
-                        res.return_amount,
-                        denom,
-                    )?)?;
-                }
+        offer_amount = simulation.return_amount;
+        simulations.push(simulation);
+    }
+
+    Ok(simulations)
+}
+
+/// Simulate a swap through a contract answering the `astroport::pair` simulation queries,
+/// taxes are deducted the same way the swap execution does
+fn simulate_pair_swap(
+    deps: Deps,
+    contract_addr: Addr,
+    offer_asset_info: AssetInfo,
+    ask_asset_info: AssetInfo,
+    offer_amount: Uint128,
+) -> Result<SwapOperationSimulation, ContractError> {
+    // Deduct tax before querying simulation, the same as asset_into_swap_msg
+    let offer_tax_amount = match offer_asset_info.clone() {
+        AssetInfo::NativeToken { denom } => compute_tax(deps, offer_amount, denom)?,
+        AssetInfo::Token { .. } => Uint128::zero(),
+    };
+    let offer_amount = offer_amount.checked_sub(offer_tax_amount)?;
+
+    let res: SimulationResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&PairQueryMsg::Simulation {
+            offer_asset: Asset {
+                info: offer_asset_info,
+                amount: offer_amount,
+            },
+        })?,
+    }))?;
+
+    // Deduct tax after querying simulation, the pair deducts it when sending the ask asset
+    let return_tax_amount = match ask_asset_info {
+        AssetInfo::NativeToken { denom } => compute_tax(deps, res.return_amount, denom)?,
+        AssetInfo::Token { .. } => Uint128::zero(),
+    };
+
+    Ok(SwapOperationSimulation {
+        offer_amount,
+        offer_tax_amount,
+        return_amount: res.return_amount.checked_sub(return_tax_amount)?,
+        return_tax_amount,
+        commission_amount: res.commission_amount,
+        spread_amount: res.spread_amount,
+    })
+}
+
+/// Walk already validated operations backwards and return the offer amount needed
+/// for the receiver to get ask_amount. Taxes are added wherever the forward execution deducts them.
+fn reverse_simulate_route(
+    deps: Deps,
+    ask_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> Result<Uint128, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let astroport_factory = config.astroport_factory;
+
+    let mut ask_amount = ask_amount;
+    for operation in operations.into_iter().rev() {
+        match operation {
+            SwapOperation::NativeSwap { .. } => {
+                return Err(ContractError::NativeSwapNotSupported {});
+            }
+            SwapOperation::AstroSwap {
+                offer_asset_info,
+                ask_asset_info,
+                ..
+            } => {
+                let pair_info: PairInfo = query_pair_info(
+                    &deps.querier,
+                    astroport_factory.clone(),
+                    &[offer_asset_info.clone(), ask_asset_info.clone()],
+                )?;
 
-                offer_amount = res.return_amount;
+                ask_amount = reverse_simulate_pair_swap(
+                    deps,
+                    pair_info.contract_addr,
+                    offer_asset_info,
+                    ask_asset_info,
+                    ask_amount,
+                )?;
+            }
+            SwapOperation::ExternalSwap {
+                venue,
+                offer_asset_info,
+                ask_asset_info,
+                ..
+            } => {
+                ask_amount = reverse_simulate_pair_swap(
+                    deps,
+                    load_simulation_adapter(deps, &venue)?,
+                    offer_asset_info,
+                    ask_asset_info,
+                    ask_amount,
+                )?;
             }
         }
     }
 
-    Ok(SimulateSwapOperationsResponse {
-        amount: offer_amount,
-    })
+    Ok(ask_amount)
+}
+
+/// Reverse simulate a swap through a contract answering the `astroport::pair` simulation queries,
+/// taxes are added wherever the swap execution deducts them
+fn reverse_simulate_pair_swap(
+    deps: Deps,
+    contract_addr: Addr,
+    offer_asset_info: AssetInfo,
+    ask_asset_info: AssetInfo,
+    ask_amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let mut ask_amount = ask_amount;
+
+    // Add tax deducted by the pair when sending the ask asset
+    if let AssetInfo::NativeToken { denom } = ask_asset_info.clone() {
+        ask_amount = ask_amount.checked_add(compute_reverse_tax(deps, ask_amount, denom)?)?;
+    }
+
+    let res = reverse_simulate(
+        &deps.querier,
+        &contract_addr,
+        &Asset {
+            info: ask_asset_info,
+            amount: ask_amount,
+        },
+    )?;
+
+    // The pair rounds the offer amount down, round it up instead
+    let mut offer_amount = res.offer_amount.checked_add(Uint128::new(1))?;
+
+    // Add tax deducted when sending the offer asset to the pair
+    if let AssetInfo::NativeToken { denom } = offer_asset_info {
+        offer_amount = offer_amount.checked_add(compute_reverse_tax(deps, offer_amount, denom)?)?;
+    }
+
+    Ok(offer_amount)
 }
 
 fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(), ContractError> {
@@ -346,6 +1288,12 @@ fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(),
             SwapOperation::AstroSwap {
                 offer_asset_info,
                 ask_asset_info,
+                ..
+            }
+            | SwapOperation::ExternalSwap {
+                offer_asset_info,
+                ask_asset_info,
+                ..
             } => (offer_asset_info.clone(), ask_asset_info.clone()),
         };
         offer_asset.check(api)?;
@@ -362,6 +1310,92 @@ fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(),
     Ok(())
 }
 
+/// Validate split swap routes and return their common offer and target assets
+fn assert_routes(
+    api: &dyn Api,
+    routes: &[SwapRoute],
+) -> Result<(AssetInfo, AssetInfo), ContractError> {
+    if routes.is_empty() {
+        return Err(ContractError::MustProvideRoutes {});
+    }
+
+    let operations_len: usize = routes.iter().map(|route| route.operations.len()).sum();
+    if operations_len > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::SwapLimitExceeded {});
+    }
+
+    let mut total_share = Decimal::zero();
+    let mut assets: Option<(AssetInfo, AssetInfo)> = None;
+    for route in routes {
+        if route.operations.is_empty() {
+            return Err(ContractError::MustProvideOperations {});
+        }
+
+        if route.offer_share.is_zero() {
+            return Err(ContractError::InvalidOfferShares {});
+        }
+        total_share = total_share + route.offer_share;
+
+        assert_operations(api, &route.operations)?;
+
+        let route_assets = (
+            route.operations.first().unwrap().get_offer_asset_info(),
+            route.operations.last().unwrap().get_target_asset_info(),
+        );
+        // Intermediate operations swap the whole router balance, so they must never
+        // touch the offer asset still reserved for the following routes
+        if route
+            .operations
+            .iter()
+            .skip(1)
+            .any(|operation| operation.get_offer_asset_info() == route_assets.0)
+        {
+            return Err(StdError::generic_err(
+                "invalid routes; offer asset can't be swapped in the middle of a route",
+            )
+            .into());
+        }
+
+        match &assets {
+            Some(assets) if *assets != route_assets => {
+                return Err(StdError::generic_err(
+                    "invalid routes; offer and target assets must be the same for all routes",
+                )
+                .into());
+            }
+            _ => assets = Some(route_assets),
+        }
+    }
+
+    if total_share != Decimal::one() {
+        return Err(ContractError::InvalidOfferShares {});
+    }
+
+    Ok(assets.unwrap())
+}
+
+/// Split the offer amount according to the route shares.
+/// The last route gets the remainder so that no dust is left on the router.
+fn split_offer_amount(
+    offer_amount: Uint128,
+    routes: &[SwapRoute],
+) -> Result<Vec<Uint128>, ContractError> {
+    let mut remaining = offer_amount;
+    let mut amounts = vec![];
+    for (index, route) in routes.iter().enumerate() {
+        let amount = if index + 1 == routes.len() {
+            remaining
+        } else {
+            offer_amount * route.offer_share
+        };
+
+        remaining = remaining.checked_sub(amount)?;
+        amounts.push(amount);
+    }
+
+    Ok(amounts)
+}
+
 #[test]
 fn test_invalid_operations() {
     use cosmwasm_std::testing::mock_dependencies;
@@ -386,6 +1420,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0001"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::Token {
@@ -394,6 +1430,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "uluna".to_string(),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 }
             ]
         )
@@ -417,6 +1455,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0001"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::Token {
@@ -425,6 +1465,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "uluna".to_string(),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -433,6 +1475,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0002"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
             ]
         )
@@ -456,6 +1500,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0001"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::Token {
@@ -464,6 +1510,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "uaud".to_string(),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -472,6 +1520,8 @@ fn test_invalid_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0002"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
             ]
         )
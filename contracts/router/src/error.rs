@@ -17,6 +17,39 @@ pub enum ContractError {
 
     #[error("The limit exceeded of swap operations!")]
     SwapLimitExceeded {},
+
+    #[error("Must provide routes!")]
+    MustProvideRoutes {},
+
+    #[error("Route offer shares must be positive and sum up to one!")]
+    InvalidOfferShares {},
+
+    #[error("Offered asset doesn't match the routes offer asset!")]
+    InvalidOfferAsset {},
+
+    #[error("Route limits must be positive and can't exceed their maximums!")]
+    InvalidRouteLimits {},
+
+    #[error("No route found!")]
+    NoRouteFound {},
+
+    #[error("Exact output swaps don't support native swap operations!")]
+    NativeSwapNotSupported {},
+
+    #[error("External venue {venue} is not whitelisted!")]
+    ExternalVenueNotWhitelisted { venue: String },
+
+    #[error("Swap operation #{operation} failed: {error}")]
+    SwapOperationFailed { operation: u64, error: String },
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+
+    #[error("Required offer amount {offer_amount} exceeds the maximum offer {max_offer}")]
+    MaxOfferExceeded {
+        max_offer: Uint128,
+        offer_amount: Uint128,
+    },
 }
 
 impl From<OverflowError> for ContractError {
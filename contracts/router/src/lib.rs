@@ -2,9 +2,11 @@ pub mod contract;
 pub mod state;
 
 mod error;
+mod migration;
 
 mod operations;
 mod querier;
+mod routes;
 
 #[cfg(test)]
 mod testing;
@@ -0,0 +1,35 @@
+use cosmwasm_std::{Addr, DepsMut, StdResult};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use astroport::asset::addr_validate_to_lower;
+use astroport::router::{
+    MigrateMsg, DEFAULT_MAX_PAIRS_PAGES, DEFAULT_MAX_ROUTE_CANDIDATES, DEFAULT_MAX_ROUTE_HOPS,
+};
+
+use crate::state::{Config, CONFIG};
+
+/// Config of the router without an owner and route limits (v1.0.0)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV100 {
+    pub astroport_factory: Addr,
+}
+
+pub const CONFIG_V100: Item<ConfigV100> = Item::new("config");
+
+/// Sets the owner from the migration message and the default FindBestRoute limits
+pub fn migrate_config(deps: DepsMut, msg: MigrateMsg) -> StdResult<()> {
+    let cfg = CONFIG_V100.load(deps.storage)?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner: addr_validate_to_lower(deps.api, &msg.owner)?,
+            astroport_factory: cfg.astroport_factory,
+            max_route_hops: DEFAULT_MAX_ROUTE_HOPS,
+            max_route_candidates: DEFAULT_MAX_ROUTE_CANDIDATES,
+            max_pairs_pages: DEFAULT_MAX_PAIRS_PAGES,
+        },
+    )
+}
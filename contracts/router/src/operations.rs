@@ -1,10 +1,11 @@
 use cosmwasm_std::{
-    to_binary, Coin, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, StdResult, WasmMsg,
+    to_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
 };
 
 use crate::error::ContractError;
 use crate::querier::compute_tax;
-use crate::state::{Config, CONFIG};
+use crate::state::{Config, CONFIG, EXTERNAL_VENUES};
 
 use astroport::asset::{Asset, AssetInfo, PairInfo};
 use astroport::pair::ExecuteMsg as PairExecuteMsg;
@@ -26,39 +27,59 @@ pub fn execute_swap_operation(
         return Err(ContractError::Unauthorized {});
     }
 
-    let messages: Vec<CosmosMsg<TerraMsgWrapper>> = match operation {
+    let amount = match operation.get_offer_asset_info() {
+        AssetInfo::NativeToken { denom } => {
+            query_balance(&deps.querier, env.contract.address, denom)?
+        }
+        AssetInfo::Token { contract_addr } => {
+            query_token_balance(&deps.querier, contract_addr, env.contract.address)?
+        }
+    };
+
+    let message = swap_operation_msg(deps.as_ref(), operation, amount, to)?;
+
+    Ok(Response::new().add_message(message))
+}
+
+/// Build the message swapping the given amount of the operation offer asset
+pub fn swap_operation_msg(
+    deps: Deps,
+    operation: SwapOperation,
+    amount: Uint128,
+    to: Option<String>,
+) -> Result<CosmosMsg<TerraMsgWrapper>, ContractError> {
+    match operation {
         SwapOperation::NativeSwap {
             offer_denom,
             ask_denom,
         } => {
-            let amount =
-                query_balance(&deps.querier, env.contract.address, offer_denom.to_string())?;
             if let Some(to) = to {
                 // if the opeation is last, and requires send
                 // deduct tax from the offer_coin
-                let amount =
-                    amount.checked_sub(compute_tax(deps.as_ref(), amount, offer_denom.clone())?)?;
-                vec![create_swap_send_msg(
+                let amount = amount.checked_sub(compute_tax(deps, amount, offer_denom.clone())?)?;
+                Ok(create_swap_send_msg(
                     to,
                     Coin {
                         denom: offer_denom,
                         amount,
                     },
                     ask_denom,
-                )]
+                ))
             } else {
-                vec![create_swap_msg(
+                Ok(create_swap_msg(
                     Coin {
                         denom: offer_denom,
                         amount,
                     },
                     ask_denom,
-                )]
+                ))
             }
         }
         SwapOperation::AstroSwap {
             offer_asset_info,
             ask_asset_info,
+            belief_price,
+            max_spread,
         } => {
             let config: Config = CONFIG.load(deps.storage)?;
             let astroport_factory = config.astroport_factory;
@@ -68,36 +89,60 @@ pub fn execute_swap_operation(
                 &[offer_asset_info.clone(), ask_asset_info],
             )?;
 
-            let amount = match offer_asset_info.clone() {
-                AssetInfo::NativeToken { denom } => {
-                    query_balance(&deps.querier, env.contract.address, denom)?
-                }
-                AssetInfo::Token { contract_addr } => {
-                    query_token_balance(&deps.querier, contract_addr, env.contract.address)?
-                }
-            };
             let offer_asset: Asset = Asset {
                 info: offer_asset_info,
                 amount,
             };
 
-            vec![asset_into_swap_msg(
+            Ok(asset_into_swap_msg(
                 deps,
                 pair_info.contract_addr.to_string(),
                 offer_asset,
-                None,
+                belief_price,
+                max_spread,
                 to,
-            )?]
+            )?)
         }
-    };
+        SwapOperation::ExternalSwap {
+            venue,
+            offer_asset_info,
+            belief_price,
+            max_spread,
+            ..
+        } => {
+            load_simulation_adapter(deps, &venue)?;
 
-    Ok(Response::new().add_messages(messages))
+            let offer_asset: Asset = Asset {
+                info: offer_asset_info,
+                amount,
+            };
+
+            Ok(asset_into_swap_msg(
+                deps,
+                venue.to_string(),
+                offer_asset,
+                belief_price,
+                max_spread,
+                to,
+            )?)
+        }
+    }
+}
+
+/// Check the external venue is whitelisted and return the contract answering its simulation queries
+pub fn load_simulation_adapter(deps: Deps, venue: &Addr) -> Result<Addr, ContractError> {
+    EXTERNAL_VENUES
+        .may_load(deps.storage, venue)?
+        .ok_or_else(|| ContractError::ExternalVenueNotWhitelisted {
+            venue: venue.to_string(),
+        })
 }
 
 pub fn asset_into_swap_msg(
-    deps: DepsMut,
+    deps: Deps,
     pair_contract: String,
     offer_asset: Asset,
+    belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<String>,
 ) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
@@ -105,7 +150,7 @@ pub fn asset_into_swap_msg(
         AssetInfo::NativeToken { denom } => {
             // deduct tax first
             let amount = offer_asset.amount.checked_sub(compute_tax(
-                deps.as_ref(),
+                deps,
                 offer_asset.amount,
                 denom.clone(),
             )?)?;
@@ -117,7 +162,7 @@ pub fn asset_into_swap_msg(
                         amount,
                         ..offer_asset
                     },
-                    belief_price: None,
+                    belief_price,
                     max_spread,
                     to,
                 })?,
@@ -131,7 +176,7 @@ pub fn asset_into_swap_msg(
                 amount: offer_asset.amount,
                 msg: to_binary(&PairExecuteMsg::Swap {
                     offer_asset,
-                    belief_price: None,
+                    belief_price,
                     max_spread,
                     to,
                 })?,
@@ -1,4 +1,5 @@
-use cosmwasm_std::{Decimal, Deps, StdResult, Uint128};
+use cosmwasm_std::{Decimal, Deps, StdResult, Uint128, Uint256};
+use std::convert::TryInto;
 use terra_cosmwasm::TerraQuerier;
 
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
@@ -19,3 +20,24 @@ pub fn compute_tax(deps: Deps, amount: Uint128, denom: String) -> StdResult<Uint
         tax_cap,
     ))
 }
+
+/// Returns the tax to add to the amount so that at least `amount` is left
+/// once the tax computed by `compute_tax` is deducted
+pub fn compute_reverse_tax(deps: Deps, amount: Uint128, denom: String) -> StdResult<Uint128> {
+    if denom == "uluna" {
+        return Ok(Uint128::zero());
+    }
+
+    let terra_querier = TerraQuerier::new(&deps.querier);
+    let tax_rate: Decimal = (terra_querier.query_tax_rate()?).rate;
+    let tax_cap: Uint128 = (terra_querier.query_tax_cap(denom)?).cap;
+
+    // round up, so the amount is never short after the tax deduction
+    let tax: Uint128 = ((amount.full_mul(DECIMAL_FRACTION * tax_rate)
+        + Uint256::from(DECIMAL_FRACTION)
+        - Uint256::from(1u8))
+        / Uint256::from(DECIMAL_FRACTION))
+    .try_into()?;
+
+    Ok(std::cmp::min(tax, tax_cap))
+}
@@ -0,0 +1,117 @@
+use cosmwasm_std::{Addr, Deps, StdResult};
+use std::collections::{HashMap, VecDeque};
+
+use astroport::asset::AssetInfo;
+use astroport::querier::query_pairs_info;
+use astroport::router::SwapOperation;
+
+/// Maximum amount of pairs returned by a single factory Pairs query
+const PAIRS_PAGE_LIMIT: u32 = 30;
+
+/// Maximum amount of partial routes queued by the route search, so the search is bounded even
+/// if no candidate route is found
+pub const MAX_QUEUED_PATHS: usize = 100;
+
+/// Assets reachable from every asset through a single Astroport pair
+pub type PairsGraph = HashMap<String, Vec<AssetInfo>>;
+
+/// Read at most max_pages pages of pairs registered in the factory and build the graph of
+/// tradable asset pairs
+pub fn query_pairs_graph(
+    deps: Deps,
+    astroport_factory: Addr,
+    max_pages: u32,
+) -> StdResult<PairsGraph> {
+    let mut graph = PairsGraph::new();
+    let mut start_after = None;
+    for _ in 0..max_pages {
+        let pairs = query_pairs_info(
+            &deps.querier,
+            astroport_factory.clone(),
+            start_after,
+            Some(PAIRS_PAGE_LIMIT),
+        )?
+        .pairs;
+
+        for pair in pairs.iter() {
+            let [first, second] = pair.asset_infos.clone();
+            add_edge(&mut graph, &first, &second);
+            add_edge(&mut graph, &second, &first);
+        }
+
+        if pairs.len() < PAIRS_PAGE_LIMIT as usize {
+            break;
+        }
+
+        start_after = pairs.last().map(|pair| pair.asset_infos.clone());
+    }
+
+    Ok(graph)
+}
+
+/// Several pairs of the same assets give a single edge, the route is simulated once
+fn add_edge(graph: &mut PairsGraph, from: &AssetInfo, to: &AssetInfo) {
+    let neighbours = graph.entry(from.to_string()).or_default();
+    if !neighbours.contains(to) {
+        neighbours.push(to.clone());
+    }
+}
+
+/// Search the graph breadth-first for routes from the offer to the ask asset.
+/// Shorter routes are returned first, no asset is visited twice within a route.
+/// The search stops at max_candidates routes, at most MAX_QUEUED_PATHS partial routes are queued.
+pub fn find_candidate_routes(
+    graph: &PairsGraph,
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+    max_hops: usize,
+    max_candidates: usize,
+) -> Vec<Vec<SwapOperation>> {
+    let mut candidates = vec![];
+    if max_hops == 0 {
+        return candidates;
+    }
+
+    let mut paths: VecDeque<Vec<AssetInfo>> = VecDeque::new();
+    paths.push_back(vec![offer_asset_info.clone()]);
+    let mut queued_paths = 1;
+
+    while let Some(path) = paths.pop_front() {
+        let neighbours = match graph.get(&path.last().unwrap().to_string()) {
+            Some(neighbours) => neighbours,
+            None => continue,
+        };
+
+        for next in neighbours {
+            if path.contains(next) {
+                continue;
+            }
+
+            let mut next_path = path.clone();
+            next_path.push(next.clone());
+
+            if next == ask_asset_info {
+                candidates.push(path_into_operations(&next_path));
+                if candidates.len() >= max_candidates {
+                    return candidates;
+                }
+            } else if path.len() < max_hops && queued_paths < MAX_QUEUED_PATHS {
+                paths.push_back(next_path);
+                queued_paths += 1;
+            }
+        }
+    }
+
+    candidates
+}
+
+fn path_into_operations(path: &[AssetInfo]) -> Vec<SwapOperation> {
+    path.windows(2)
+        .map(|assets| SwapOperation::AstroSwap {
+            offer_asset_info: assets[0].clone(),
+            ask_asset_info: assets[1].clone(),
+            belief_price: None,
+            max_spread: None,
+        })
+        .collect()
+}
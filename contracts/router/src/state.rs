@@ -1,12 +1,50 @@
-use cw_storage_plus::Item;
+use cw_storage_plus::{Bound, Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
+use astroport::common::OwnershipProposal;
+use astroport::router::ExternalVenueInfo;
+use cosmwasm_std::{Addr, Deps, Order, StdResult};
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+/// Whitelisted external venues and their simulation adapters
+pub const EXTERNAL_VENUES: Map<&Addr, Addr> = Map::new("external_venues");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    pub owner: Addr,
     pub astroport_factory: Addr,
+    /// Maximum number of hops in a route found by FindBestRoute
+    pub max_route_hops: u32,
+    /// Maximum number of candidate routes simulated by FindBestRoute
+    pub max_route_candidates: u32,
+    /// Maximum number of factory pages of pairs read by FindBestRoute
+    pub max_pairs_pages: u32,
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+pub fn read_external_venues(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ExternalVenueInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|venue| Bound::exclusive(venue.as_bytes()));
+
+    EXTERNAL_VENUES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (venue, simulation_adapter) = item?;
+            Ok(ExternalVenueInfo {
+                venue: Addr::unchecked(String::from_utf8_lossy(&venue)),
+                simulation_adapter,
+            })
+        })
+        .collect()
 }
@@ -8,8 +8,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use astroport::asset::{Asset, AssetInfo, PairInfo};
-use astroport::factory::PairType;
-use astroport::pair::SimulationResponse;
+use astroport::factory::{PairType, PairsResponse};
+use astroport::pair::{ReverseSimulationResponse, SimulationResponse};
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use terra_cosmwasm::{
     SwapResponse, TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute,
@@ -18,8 +18,19 @@ use terra_cosmwasm::{
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    Pair { asset_infos: [AssetInfo; 2] },
-    Simulation { offer_asset: Asset },
+    Pair {
+        asset_infos: [AssetInfo; 2],
+    },
+    Pairs {
+        start_after: Option<[AssetInfo; 2]>,
+        limit: Option<u32>,
+    },
+    Simulation {
+        offer_asset: Asset,
+    },
+    ReverseSimulation {
+        ask_asset: Asset,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -100,12 +111,16 @@ pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint1
 #[derive(Clone, Default)]
 pub struct AstroportFactoryQuerier {
     pairs: HashMap<String, String>,
+    pair_infos: Vec<PairInfo>,
+    // simulation return rates by pair contract, 1:1 if not set
+    simulation_rates: HashMap<String, Decimal>,
 }
 
 impl AstroportFactoryQuerier {
     pub fn new(pairs: &[(&String, &String)]) -> Self {
         AstroportFactoryQuerier {
             pairs: pairs_to_map(pairs),
+            ..AstroportFactoryQuerier::default()
         }
     }
 }
@@ -187,14 +202,14 @@ impl WasmMockQuerier {
                 {
                     self.handle_cw20(contract_addr, msg)
                 } else {
-                    self.handle_default(msg)
+                    self.handle_default(contract_addr, msg)
                 }
             }
             _ => self.base.handle_query(request),
         }
     }
 
-    fn handle_default(&self, msg: &Binary) -> QuerierResult {
+    fn handle_default(&self, contract_addr: &String, msg: &Binary) -> QuerierResult {
         match from_binary(&msg).unwrap() {
             QueryMsg::Pair { asset_infos } => {
                 let key = asset_infos[0].to_string() + asset_infos[1].to_string().as_str();
@@ -218,13 +233,36 @@ impl WasmMockQuerier {
                     }),
                 }
             }
+            QueryMsg::Pairs { start_after, .. } => {
+                // every pair is returned within the first page
+                let pairs = if start_after.is_none() {
+                    self.astroport_factory_querier.pair_infos.clone()
+                } else {
+                    vec![]
+                };
+                SystemResult::Ok(ContractResult::from(to_binary(&PairsResponse { pairs })))
+            }
             QueryMsg::Simulation { offer_asset } => {
+                let rate = self
+                    .astroport_factory_querier
+                    .simulation_rates
+                    .get(contract_addr)
+                    .copied()
+                    .unwrap_or_else(Decimal::one);
                 SystemResult::Ok(ContractResult::from(to_binary(&SimulationResponse {
-                    return_amount: offer_asset.amount,
+                    return_amount: offer_asset.amount * rate,
                     commission_amount: Uint128::zero(),
                     spread_amount: Uint128::zero(),
                 })))
             }
+            // reverse simulation is always 1:1
+            QueryMsg::ReverseSimulation { ask_asset } => SystemResult::Ok(ContractResult::from(
+                to_binary(&ReverseSimulationResponse {
+                    offer_amount: ask_asset.amount,
+                    commission_amount: Uint128::zero(),
+                    spread_amount: Uint128::zero(),
+                }),
+            )),
         }
     }
 
@@ -304,4 +342,30 @@ impl WasmMockQuerier {
     pub fn with_astroport_pairs(&mut self, pairs: &[(&String, &String)]) {
         self.astroport_factory_querier = AstroportFactoryQuerier::new(pairs);
     }
+
+    // registers the pairs in the factory so they are returned by both Pair and Pairs queries
+    pub fn with_astroport_pair_infos(&mut self, pair_infos: &[PairInfo]) {
+        let mut pairs = HashMap::new();
+        for pair_info in pair_infos {
+            for key in [
+                pair_info.asset_infos[0].to_string()
+                    + pair_info.asset_infos[1].to_string().as_str(),
+                pair_info.asset_infos[1].to_string()
+                    + pair_info.asset_infos[0].to_string().as_str(),
+            ] {
+                pairs.insert(key, pair_info.contract_addr.to_string());
+            }
+        }
+
+        self.astroport_factory_querier.pairs = pairs;
+        self.astroport_factory_querier.pair_infos = pair_infos.to_vec();
+    }
+
+    pub fn with_simulation_rates(&mut self, rates: &[(&String, Decimal)]) {
+        for (pair, rate) in rates {
+            self.astroport_factory_querier
+                .simulation_rates
+                .insert(pair.to_string(), *rate);
+        }
+    }
 }
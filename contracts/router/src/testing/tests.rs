@@ -1,19 +1,29 @@
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Coin, Decimal, ReplyOn, SubMsg, Uint128, WasmMsg,
+    from_binary, to_binary, Addr, BankMsg, Binary, Coin, ContractResult, Decimal, Reply, ReplyOn,
+    StdError, SubMsg, Uint128, WasmMsg,
 };
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query, reply};
 use crate::error::ContractError;
+use crate::migration::{ConfigV100, CONFIG_V100};
+use crate::routes::{find_candidate_routes, query_pairs_graph, PairsGraph, MAX_QUEUED_PATHS};
 use crate::testing::mock_querier::mock_dependencies;
 
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
-use astroport::asset::{Asset, AssetInfo};
+use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::factory::PairType;
 use astroport::pair::ExecuteMsg as PairExecuteMsg;
 use astroport::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation, MAX_SWAP_OPERATIONS,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, ExternalVenue, ExternalVenueInfo,
+    ExternalVenuesResponse, FindBestRouteResponse, InstantiateMsg, MigrateMsg, QueryMsg,
+    SimulateReverseSwapOperationsResponse, SimulateSplitSwapResponse,
+    SimulateSwapOperationsDetailedResponse, SimulateSwapOperationsResponse, SwapOperation,
+    SwapOperationSimulation, SwapRoute, DEFAULT_MAX_PAIRS_PAGES, DEFAULT_MAX_ROUTE_CANDIDATES,
+    DEFAULT_MAX_ROUTE_HOPS, MAX_PAIRS_PAGES, MAX_ROUTE_CANDIDATES, MAX_ROUTE_HOPS,
+    MAX_SWAP_OPERATIONS,
 };
 use terra_cosmwasm::{create_swap_msg, create_swap_send_msg};
 
@@ -22,7 +32,11 @@ fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
+        owner: String::from("owner"),
         astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
     };
 
     let env = mock_env();
@@ -34,14 +48,145 @@ fn proper_initialization() {
     // it worked, let's query the state
     let config: ConfigResponse =
         from_binary(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!("owner", config.owner.as_str());
     assert_eq!("astroportfactory", config.astroport_factory.as_str());
+    assert_eq!(DEFAULT_MAX_ROUTE_HOPS, config.max_route_hops);
+    assert_eq!(DEFAULT_MAX_ROUTE_CANDIDATES, config.max_route_candidates);
+    assert_eq!(DEFAULT_MAX_PAIRS_PAGES, config.max_pairs_pages);
+
+    // route limits must be positive
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: Some(0),
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidRouteLimits {});
+
+    // route search is exponential in hops, so they are capped
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: Some(MAX_ROUTE_HOPS + 1),
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidRouteLimits {});
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        max_route_hops: Some(2),
+        max_route_candidates: None,
+        max_pairs_pages: Some(MAX_PAIRS_PAGES),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(2, config.max_route_hops);
+    assert_eq!(DEFAULT_MAX_ROUTE_CANDIDATES, config.max_route_candidates);
+    assert_eq!(MAX_PAIRS_PAGES, config.max_pairs_pages);
+
+    for msg in [
+        ExecuteMsg::UpdateConfig {
+            max_route_hops: Some(MAX_ROUTE_HOPS + 1),
+            max_route_candidates: None,
+            max_pairs_pages: None,
+        },
+        ExecuteMsg::UpdateConfig {
+            max_route_hops: None,
+            max_route_candidates: Some(MAX_ROUTE_CANDIDATES + 1),
+            max_pairs_pages: None,
+        },
+        ExecuteMsg::UpdateConfig {
+            max_route_hops: None,
+            max_route_candidates: None,
+            max_pairs_pages: Some(0),
+        },
+    ] {
+        let info = mock_info("owner", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(res, ContractError::InvalidRouteLimits {});
+    }
+}
+
+#[test]
+fn migrate_from_v100() {
+    let mut deps = mock_dependencies(&[]);
+
+    set_contract_version(deps.as_mut().storage, "astroport-router", "1.0.0").unwrap();
+    CONFIG_V100
+        .save(
+            deps.as_mut().storage,
+            &ConfigV100 {
+                astroport_factory: Addr::unchecked("astroportfactory"),
+            },
+        )
+        .unwrap();
+
+    let msg = MigrateMsg {
+        owner: String::from("owner"),
+    };
+    migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: String::from("owner"),
+            astroport_factory: String::from("astroportfactory"),
+            max_route_hops: DEFAULT_MAX_ROUTE_HOPS,
+            max_route_candidates: DEFAULT_MAX_ROUTE_CANDIDATES,
+            max_pairs_pages: DEFAULT_MAX_PAIRS_PAGES,
+        }
+    );
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // the migrated version can't be migrated again
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(res, ContractError::MigrationError {});
 }
 
 #[test]
 fn execute_swap_operations() {
     let mut deps = mock_dependencies(&[]);
     let msg = InstantiateMsg {
+        owner: String::from("owner"),
         astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
     };
 
     let env = mock_env();
@@ -54,6 +199,8 @@ fn execute_swap_operations() {
         operations: vec![],
         minimum_receive: None,
         to: None,
+        max_spread: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -74,6 +221,8 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0001"),
                 },
+                belief_price: None,
+                max_spread: None,
             },
             SwapOperation::AstroSwap {
                 offer_asset_info: AssetInfo::Token {
@@ -82,6 +231,8 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "uluna".to_string(),
                 },
+                belief_price: None,
+                max_spread: None,
             },
             SwapOperation::AstroSwap {
                 offer_asset_info: AssetInfo::NativeToken {
@@ -90,10 +241,14 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0002"),
                 },
+                belief_price: None,
+                max_spread: None,
             },
         ],
         minimum_receive: Some(Uint128::from(1000000u128)),
         to: None,
+        max_spread: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -116,9 +271,9 @@ fn execute_swap_operations() {
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 1,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -132,15 +287,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::Token {
                                 contract_addr: Addr::unchecked("asset0001"),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: None,
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 2,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -154,15 +311,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::NativeToken {
                                 denom: "uluna".to_string(),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: None,
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 3,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -176,15 +335,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::Token {
                                 contract_addr: Addr::unchecked("asset0002"),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: Some(String::from("addr0000")),
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 4,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -224,6 +385,8 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0001"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::Token {
@@ -232,6 +395,8 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "uluna".to_string(),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
                 SwapOperation::AstroSwap {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -240,10 +405,14 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: Addr::unchecked("asset0002"),
                     },
+                    belief_price: None,
+                    max_spread: None,
                 },
             ],
             minimum_receive: None,
             to: Some(String::from("addr0002")),
+            max_spread: None,
+            callback: None,
         })
         .unwrap(),
     });
@@ -268,9 +437,9 @@ fn execute_swap_operations() {
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 1,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -284,15 +453,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::Token {
                                 contract_addr: Addr::unchecked("asset0001"),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: None,
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 2,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -306,15 +477,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::NativeToken {
                                 denom: "uluna".to_string(),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: None,
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 3,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             },
             SubMsg {
                 msg: WasmMsg::Execute {
@@ -328,15 +501,17 @@ fn execute_swap_operations() {
                             ask_asset_info: AssetInfo::Token {
                                 contract_addr: Addr::unchecked("asset0002"),
                             },
+                            belief_price: None,
+                            max_spread: None,
                         },
                         to: Some(String::from("addr0002")),
                     })
                     .unwrap(),
                 }
                 .into(),
-                id: 0,
+                id: 4,
                 gas_limit: None,
-                reply_on: ReplyOn::Never,
+                reply_on: ReplyOn::Error,
             }
         ]
     );
@@ -346,7 +521,11 @@ fn execute_swap_operations() {
 fn execute_swap_operation() {
     let mut deps = mock_dependencies(&[]);
     let msg = InstantiateMsg {
+        owner: String::from("owner"),
         astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
     };
 
     let env = mock_env();
@@ -445,6 +624,8 @@ fn execute_swap_operation() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
+            belief_price: None,
+            max_spread: None,
         },
         to: Some(String::from("addr0000")),
     };
@@ -488,7 +669,11 @@ fn query_buy_with_routes() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
+        owner: String::from("owner"),
         astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
     };
 
     let env = mock_env();
@@ -520,6 +705,8 @@ fn query_buy_with_routes() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: Addr::unchecked("asset0000"),
                 },
+                belief_price: None,
+                max_spread: None,
             },
             SwapOperation::AstroSwap {
                 offer_asset_info: AssetInfo::Token {
@@ -528,6 +715,8 @@ fn query_buy_with_routes() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "uluna".to_string(),
                 },
+                belief_price: None,
+                max_spread: None,
             },
         ],
     };
@@ -569,6 +758,100 @@ fn query_buy_with_routes() {
     );
 }
 
+#[test]
+fn query_swap_operations_detailed() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // set tax rate as 5%
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[
+            (&"uusd".to_string(), &Uint128::new(1000000u128)),
+            (&"ukrw".to_string(), &Uint128::new(1000000u128)),
+        ],
+    );
+    deps.querier.with_astroport_pairs(&[
+        (&"ukrwasset0000".to_string(), &String::from("pair0000")),
+        (&"asset0000uluna".to_string(), &String::from("pair0001")),
+    ]);
+
+    let msg = QueryMsg::SimulateSwapOperationsDetailed {
+        offer_amount: Uint128::from(1000000u128),
+        operations: vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "uusd".to_string(),
+                ask_denom: "ukrw".to_string(),
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                belief_price: None,
+                max_spread: None,
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                belief_price: None,
+                max_spread: None,
+            },
+        ],
+    };
+
+    let res: SimulateSwapOperationsDetailedResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SimulateSwapOperationsDetailedResponse {
+            amount: Uint128::new(952380u128),
+            operations: vec![
+                // the market swap is not the last one, no tax
+                SwapOperationSimulation {
+                    offer_amount: Uint128::new(1000000u128),
+                    offer_tax_amount: Uint128::zero(),
+                    return_amount: Uint128::new(1000000u128),
+                    return_tax_amount: Uint128::zero(),
+                    commission_amount: Uint128::zero(),
+                    spread_amount: Uint128::zero(),
+                },
+                // ukrw is taxed when sent to the pair
+                SwapOperationSimulation {
+                    offer_amount: Uint128::new(952380u128),
+                    offer_tax_amount: Uint128::new(47620u128),
+                    return_amount: Uint128::new(952380u128),
+                    return_tax_amount: Uint128::zero(),
+                    commission_amount: Uint128::zero(),
+                    spread_amount: Uint128::zero(),
+                },
+                SwapOperationSimulation {
+                    offer_amount: Uint128::new(952380u128),
+                    offer_tax_amount: Uint128::zero(),
+                    return_amount: Uint128::new(952380u128),
+                    return_tax_amount: Uint128::zero(),
+                    commission_amount: Uint128::zero(),
+                    spread_amount: Uint128::zero(),
+                },
+            ],
+        }
+    );
+}
+
 #[test]
 fn assert_minimum_receive_native_token() {
     let mut deps = mock_dependencies(&[]);
@@ -657,7 +940,11 @@ fn assert_minimum_receive_token() {
 fn assert_maximum_receive_swap_operations() {
     let mut deps = mock_dependencies(&[]);
     let msg = InstantiateMsg {
+        owner: String::from("owner"),
         astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
     };
 
     let env = mock_env();
@@ -676,6 +963,8 @@ fn assert_maximum_receive_swap_operations() {
         ],
         minimum_receive: None,
         to: None,
+        max_spread: None,
+        callback: None,
     };
 
     let env = mock_env();
@@ -684,3 +973,1277 @@ fn assert_maximum_receive_swap_operations() {
 
     assert_eq!(res, ContractError::SwapLimitExceeded {});
 }
+
+fn split_swap_routes() -> Vec<SwapRoute> {
+    vec![
+        SwapRoute {
+            operations: vec![SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0001"),
+                },
+                belief_price: None,
+                max_spread: None,
+            }],
+            offer_share: Decimal::percent(60),
+            max_spread: None,
+        },
+        SwapRoute {
+            operations: vec![
+                SwapOperation::NativeSwap {
+                    offer_denom: "uusd".to_string(),
+                    ask_denom: "uluna".to_string(),
+                },
+                SwapOperation::AstroSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0001"),
+                    },
+                    belief_price: None,
+                    max_spread: None,
+                },
+            ],
+            offer_share: Decimal::percent(40),
+            max_spread: None,
+        },
+    ]
+}
+
+#[test]
+fn execute_split_swap() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    deps.querier.with_astroport_pairs(&[
+        (&"uusdasset0001".to_string(), &String::from("pair0000")),
+        (&"ulunaasset0001".to_string(), &String::from("pair0001")),
+    ]);
+
+    // no routes
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: vec![],
+        minimum_receive: None,
+        to: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::MustProvideRoutes {});
+
+    // shares don't sum up to one
+    let mut routes = split_swap_routes();
+    routes[1].offer_share = Decimal::percent(30);
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes,
+        minimum_receive: None,
+        to: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidOfferShares {});
+
+    // routes end in different assets
+    let mut routes = split_swap_routes();
+    routes[0].operations = vec![SwapOperation::NativeSwap {
+        offer_denom: "uusd".to_string(),
+        ask_denom: "ukrw".to_string(),
+    }];
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes,
+        minimum_receive: None,
+        to: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::Std(StdError::generic_err(
+            "invalid routes; offer and target assets must be the same for all routes"
+        ))
+    );
+
+    // sent funds don't match the offer asset
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: split_swap_routes(),
+        minimum_receive: None,
+        to: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::new(1000000u128),
+            }],
+        ),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidOfferAsset {});
+
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: split_swap_routes(),
+        minimum_receive: Some(Uint128::new(900000u128)),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from("pair0000"),
+                    funds: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::new(600000u128),
+                    }],
+                    msg: to_binary(&PairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: "uusd".to_string(),
+                            },
+                            amount: Uint128::new(600000u128),
+                        },
+                        belief_price: None,
+                        max_spread: None,
+                        to: Some(String::from("addr0000")),
+                    })
+                    .unwrap(),
+                },
+                1
+            ),
+            SubMsg::reply_on_error(
+                create_swap_msg(
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::new(400000u128),
+                    },
+                    "uluna".to_string()
+                ),
+                2
+            ),
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: SwapOperation::AstroSwap {
+                            offer_asset_info: AssetInfo::NativeToken {
+                                denom: "uluna".to_string(),
+                            },
+                            ask_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("asset0001"),
+                            },
+                            belief_price: None,
+                            max_spread: None,
+                        },
+                        to: Some(String::from("addr0000")),
+                    })
+                    .unwrap(),
+                },
+                3
+            ),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0001"),
+                    },
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::new(900000u128),
+                    receiver: String::from("addr0000"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    // cw20 offer asset must be the token that was sent
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteSplitSwap {
+            routes: split_swap_routes(),
+            minimum_receive: None,
+            to: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidOfferAsset {});
+}
+
+#[test]
+fn query_split_swap() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // set tax rate as 5%
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[(&"uusd".to_string(), &Uint128::new(1000000u128))],
+    );
+    deps.querier.with_astroport_pairs(&[
+        (&"uusdasset0001".to_string(), &String::from("pair0000")),
+        (&"ulunaasset0001".to_string(), &String::from("pair0001")),
+    ]);
+
+    let msg = QueryMsg::SimulateSplitSwap {
+        offer_amount: Uint128::new(1000000u128),
+        routes: split_swap_routes(),
+    };
+
+    let res: SimulateSplitSwapResponse =
+        from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SimulateSplitSwapResponse {
+            route_amounts: vec![
+                Uint128::new(571428u128), // tax charged on uusd => asset0001
+                Uint128::new(400000u128), // no tax on uusd => uluna, uluna => asset0001
+            ],
+            amount: Uint128::new(971428u128),
+        }
+    );
+}
+
+fn pair_info(contract_addr: &str, asset_infos: [AssetInfo; 2]) -> PairInfo {
+    PairInfo {
+        contract_addr: Addr::unchecked(contract_addr),
+        liquidity_token: Addr::unchecked("liquidity"),
+        asset_infos,
+        pair_type: PairType::Xyk {},
+    }
+}
+
+#[test]
+fn query_find_best_route() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let asset0000 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let asset0002 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0002"),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+
+    // set tax rate as 5%
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[(&"uusd".to_string(), &Uint128::new(1000000u128))],
+    );
+    deps.querier.with_astroport_pair_infos(&[
+        pair_info("pair0000", [asset0000.clone(), asset0002.clone()]),
+        pair_info("pair0001", [asset0000.clone(), uluna.clone()]),
+        pair_info("pair0002", [uluna.clone(), asset0002.clone()]),
+        pair_info("pair0003", [asset0000.clone(), uusd.clone()]),
+        pair_info("pair0004", [uusd.clone(), asset0002.clone()]),
+    ]);
+    // the direct pair returns less than the routes via native tokens
+    deps.querier
+        .with_simulation_rates(&[(&String::from("pair0000"), Decimal::percent(90))]);
+
+    let offer_asset = Asset {
+        info: asset0000.clone(),
+        amount: Uint128::new(1000000u128),
+    };
+
+    // uusd route is charged tax twice, so the uluna route wins
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: asset0002.clone(),
+        max_hops: None,
+    };
+    let res: FindBestRouteResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FindBestRouteResponse {
+            operations: vec![
+                SwapOperation::AstroSwap {
+                    offer_asset_info: asset0000.clone(),
+                    ask_asset_info: uluna.clone(),
+                    belief_price: None,
+                    max_spread: None,
+                },
+                SwapOperation::AstroSwap {
+                    offer_asset_info: uluna,
+                    ask_asset_info: asset0002.clone(),
+                    belief_price: None,
+                    max_spread: None,
+                },
+            ],
+            amount: Uint128::new(1000000u128),
+        }
+    );
+
+    // only the direct pair fits in one hop
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: asset0002.clone(),
+        max_hops: Some(1),
+    };
+    let res: FindBestRouteResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FindBestRouteResponse {
+            operations: vec![SwapOperation::AstroSwap {
+                offer_asset_info: asset0000,
+                ask_asset_info: asset0002,
+                belief_price: None,
+                max_spread: None,
+            }],
+            amount: Uint128::new(900000u128),
+        }
+    );
+
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset,
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0003"),
+        },
+        max_hops: None,
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap_err();
+    assert_eq!(res, ContractError::NoRouteFound {});
+}
+
+#[test]
+fn pairs_graph_without_duplicate_edges() {
+    let mut deps = mock_dependencies(&[]);
+
+    let asset0000 = AssetInfo::Token {
+        contract_addr: Addr::unchecked("asset0000"),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+
+    deps.querier.with_astroport_pair_infos(&[
+        pair_info("pair0000", [asset0000.clone(), uluna.clone()]),
+        pair_info("pair0001", [asset0000.clone(), uluna.clone()]),
+    ]);
+
+    let graph = query_pairs_graph(deps.as_ref(), Addr::unchecked("astroportfactory"), 1).unwrap();
+    assert_eq!(graph[&asset0000.to_string()], vec![uluna.clone()]);
+    assert_eq!(graph[&uluna.to_string()], vec![asset0000.clone()]);
+
+    let candidates = find_candidate_routes(&graph, &asset0000, &uluna, 3, 10);
+    assert_eq!(candidates.len(), 1);
+}
+
+#[test]
+fn find_candidate_routes_limits_queued_paths() {
+    let offer = AssetInfo::Token {
+        contract_addr: Addr::unchecked("offer"),
+    };
+    let ask = AssetInfo::Token {
+        contract_addr: Addr::unchecked("ask"),
+    };
+
+    let mut graph = PairsGraph::new();
+    let hubs: Vec<AssetInfo> = (0..MAX_QUEUED_PATHS + 1)
+        .map(|i| AssetInfo::Token {
+            contract_addr: Addr::unchecked(format!("hub{}", i)),
+        })
+        .collect();
+    graph.insert(offer.to_string(), hubs.clone());
+    for hub in hubs.iter() {
+        graph.insert(hub.to_string(), vec![offer.clone()]);
+    }
+
+    // the ask asset is reachable via the first hub only
+    graph.insert(hubs[0].to_string(), vec![offer.clone(), ask.clone()]);
+    assert_eq!(find_candidate_routes(&graph, &offer, &ask, 2, 10).len(), 1);
+
+    // routes via hubs which don't fit in the queue aren't searched
+    graph.insert(hubs[0].to_string(), vec![offer.clone()]);
+    graph.insert(
+        hubs[MAX_QUEUED_PATHS].to_string(),
+        vec![offer.clone(), ask.clone()],
+    );
+    assert!(find_candidate_routes(&graph, &offer, &ask, 2, 10).is_empty());
+}
+
+fn exact_out_operations() -> Vec<SwapOperation> {
+    vec![
+        SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+        SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+    ]
+}
+
+#[test]
+fn query_reverse_swap_operations() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // set tax rate as 5%
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[(&"uusd".to_string(), &Uint128::new(1000000u128))],
+    );
+    deps.querier.with_astroport_pairs(&[
+        (&"uusdasset0001".to_string(), &String::from("pair0000")),
+        (&"asset0001uluna".to_string(), &String::from("pair0001")),
+    ]);
+
+    let msg = QueryMsg::SimulateReverseSwapOperations {
+        ask_amount: Uint128::new(1000000u128),
+        operations: exact_out_operations(),
+    };
+    let res: SimulateReverseSwapOperationsResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SimulateReverseSwapOperationsResponse {
+            // every hop is rounded up by one, uusd sent to the first pair is charged tax
+            offer_amount: Uint128::new(1050003u128),
+        }
+    );
+
+    // the forward simulation of the offer amount covers the ask amount
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::new(1050003u128),
+        operations: exact_out_operations(),
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert!(res.amount >= Uint128::new(1000000u128));
+
+    let msg = QueryMsg::SimulateReverseSwapOperations {
+        ask_amount: Uint128::new(1000000u128),
+        operations: vec![SwapOperation::NativeSwap {
+            offer_denom: "uusd".to_string(),
+            ask_denom: "uluna".to_string(),
+        }],
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap_err();
+    assert_eq!(res, ContractError::NativeSwapNotSupported {});
+}
+
+#[test]
+fn execute_swap_operations_exact_out() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // set tax rate as 5%
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[(&"uusd".to_string(), &Uint128::new(1000000u128))],
+    );
+    deps.querier.with_astroport_pairs(&[
+        (&"uusdasset0001".to_string(), &String::from("pair0000")),
+        (&"asset0001uluna".to_string(), &String::from("pair0001")),
+    ]);
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1100000u128),
+        }],
+    );
+
+    // max_offer must match the sent funds
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: exact_out_operations(),
+        ask_amount: Uint128::new(1000000u128),
+        max_offer: Uint128::new(1000000u128),
+        to: None,
+        max_spread: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        ))
+    );
+
+    // other coins can't be sent along with the offer coin
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: exact_out_operations(),
+        ask_amount: Uint128::new(1000000u128),
+        max_offer: Uint128::new(1100000u128),
+        to: None,
+        max_spread: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(1100000u128),
+                },
+                Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::new(1000u128),
+                },
+            ],
+        ),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidOfferAsset {});
+
+    // not enough offered
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: exact_out_operations(),
+        ask_amount: Uint128::new(1000000u128),
+        max_offer: Uint128::new(1000000u128),
+        to: None,
+        max_spread: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(1000000u128),
+            }],
+        ),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::MaxOfferExceeded {
+            max_offer: Uint128::new(1000000u128),
+            offer_amount: Uint128::new(1050003u128),
+        }
+    );
+
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: exact_out_operations(),
+        ask_amount: Uint128::new(1000000u128),
+        max_offer: Uint128::new(1100000u128),
+        to: None,
+        max_spread: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from("pair0000"),
+                    funds: vec![Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::new(1000002u128),
+                    }],
+                    msg: to_binary(&PairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::NativeToken {
+                                denom: "uusd".to_string(),
+                            },
+                            amount: Uint128::new(1000002u128),
+                        },
+                        belief_price: None,
+                        max_spread: None,
+                        to: None,
+                    })
+                    .unwrap(),
+                },
+                1
+            ),
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: exact_out_operations()[1].clone(),
+                        to: Some(String::from("addr0000")),
+                    })
+                    .unwrap(),
+                },
+                2
+            ),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::new(1000000u128),
+                    receiver: String::from("addr0000"),
+                })
+                .unwrap(),
+            }),
+            // 49997 uusd surplus minus tax
+            SubMsg::new(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(47616u128),
+                }],
+            }),
+        ]
+    );
+
+    // cw20 offer asset must be the first operation offer asset
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::new(1100000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations: exact_out_operations(),
+            ask_amount: Uint128::new(1000000u128),
+            to: None,
+            max_spread: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0001", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidOfferAsset {});
+}
+
+#[test]
+fn execute_swap_operations_max_spread() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![
+            SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0001"),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                belief_price: Some(Decimal::from_ratio(3u128, 2u128)),
+                max_spread: Some(Decimal::percent(2)),
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0002"),
+                },
+                belief_price: None,
+                max_spread: None,
+            },
+        ],
+        minimum_receive: None,
+        to: None,
+        max_spread: Some(Decimal::percent(1)),
+        callback: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // Operations without their own max_spread use the message one
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: SwapOperation::AstroSwap {
+                            offer_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("asset0001"),
+                            },
+                            ask_asset_info: AssetInfo::NativeToken {
+                                denom: "uluna".to_string(),
+                            },
+                            belief_price: Some(Decimal::from_ratio(3u128, 2u128)),
+                            max_spread: Some(Decimal::percent(2)),
+                        },
+                        to: None,
+                    })
+                    .unwrap(),
+                },
+                1
+            ),
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: SwapOperation::AstroSwap {
+                            offer_asset_info: AssetInfo::NativeToken {
+                                denom: "uluna".to_string(),
+                            },
+                            ask_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("asset0002"),
+                            },
+                            belief_price: None,
+                            max_spread: Some(Decimal::percent(1)),
+                        },
+                        to: Some(String::from("addr0000")),
+                    })
+                    .unwrap(),
+                },
+                2
+            ),
+        ]
+    );
+
+    // belief_price and max_spread are forwarded to the pair
+    deps.querier
+        .with_astroport_pairs(&[(&"asset0001uluna".to_string(), &String::from("pair"))]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0001"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(1000000u128),
+        )],
+    )]);
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+            belief_price: Some(Decimal::from_ratio(3u128, 2u128)),
+            max_spread: Some(Decimal::percent(2)),
+        },
+        to: None,
+    };
+    let env = mock_env();
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0001"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: String::from("pair"),
+                amount: Uint128::new(1000000u128),
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0001"),
+                        },
+                        amount: Uint128::new(1000000u128),
+                    },
+                    belief_price: Some(Decimal::from_ratio(3u128, 2u128)),
+                    max_spread: Some(Decimal::percent(2)),
+                    to: None,
+                })
+                .unwrap()
+            })
+            .unwrap()
+        })]
+    );
+}
+
+#[test]
+fn reply_swap_operation_failed() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = Reply {
+        id: 2,
+        result: ContractResult::Err("Operation exceeds max spread limit".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::SwapOperationFailed {
+            operation: 2,
+            error: "Operation exceeds max spread limit".to_string(),
+        }
+    );
+    assert_eq!(
+        res.to_string(),
+        "Swap operation #2 failed: Operation exceeds max spread limit"
+    );
+}
+
+#[test]
+fn external_venues() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateExternalVenues {
+        add: vec![
+            ExternalVenue {
+                venue: String::from("venue0000"),
+                simulation_adapter: Some(String::from("adapter0000")),
+            },
+            ExternalVenue {
+                venue: String::from("venue0001"),
+                simulation_adapter: None,
+            },
+        ],
+        remove: vec![],
+    };
+
+    // only owner can update venues
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let res: ExternalVenuesResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ExternalVenues {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.venues,
+        vec![
+            ExternalVenueInfo {
+                venue: Addr::unchecked("venue0000"),
+                simulation_adapter: Addr::unchecked("adapter0000"),
+            },
+            ExternalVenueInfo {
+                venue: Addr::unchecked("venue0001"),
+                simulation_adapter: Addr::unchecked("venue0001"),
+            },
+        ]
+    );
+
+    // external venues can be mixed with Astroport pairs
+    deps.querier.with_astroport_pair_infos(&[pair_info(
+        "pair0000",
+        [
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0003"),
+            },
+        ],
+    )]);
+    deps.querier.with_simulation_rates(&[
+        (&String::from("adapter0000"), Decimal::percent(200)),
+        (&String::from("pair0000"), Decimal::percent(300)),
+    ]);
+    let operations = vec![
+        SwapOperation::ExternalSwap {
+            venue: Addr::unchecked("venue0000"),
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+        SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0003"),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+    ];
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::new(1000u128),
+        operations: operations.clone(),
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg.clone()).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::new(6000u128));
+
+    // the venue receives the astroport::pair swap message
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0001"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1000u128))],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::ExecuteSwapOperation {
+            operation: operations[0].clone(),
+            to: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0001"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: String::from("venue0000"),
+                amount: Uint128::new(1000u128),
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::Token {
+                            contract_addr: Addr::unchecked("asset0001"),
+                        },
+                        amount: Uint128::new(1000u128),
+                    },
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                })
+                .unwrap()
+            })
+            .unwrap()
+        })]
+    );
+
+    // removed venues can't be used anymore
+    let msg_remove = ExecuteMsg::UpdateExternalVenues {
+        add: vec![],
+        remove: vec![String::from("venue0000")],
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        msg_remove,
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::ExternalVenueNotWhitelisted {
+            venue: String::from("venue0000"),
+        }
+    );
+}
+
+#[test]
+fn execute_swap_operations_callback() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        owner: String::from("owner"),
+        astroport_factory: String::from("astroportfactory"),
+        max_route_hops: None,
+        max_route_candidates: None,
+        max_pairs_pages: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let callback = Binary::from(br#"{"deposit":{}}"#);
+    let operations = vec![
+        SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+        SwapOperation::AstroSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0001"),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0002"),
+            },
+            belief_price: None,
+            max_spread: None,
+        },
+    ];
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: operations.clone(),
+        minimum_receive: Some(Uint128::new(1000000u128)),
+        to: Some(Addr::unchecked("vault0000")),
+        max_spread: None,
+        callback: Some(callback.clone()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the output stays in the router until it is sent with the callback
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: operations[0].clone(),
+                        to: None,
+                    })
+                    .unwrap(),
+                },
+                1
+            ),
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation: operations[1].clone(),
+                        to: None,
+                    })
+                    .unwrap(),
+                },
+                2
+            ),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::SendWithCallback {
+                    asset_info: AssetInfo::Token {
+                        contract_addr: Addr::unchecked("asset0002"),
+                    },
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Some(Uint128::new(1000000u128)),
+                    receiver: String::from("vault0000"),
+                    callback: callback.clone(),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0002"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(1000000u128),
+        )],
+    )]);
+    let msg = ExecuteMsg::SendWithCallback {
+        asset_info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0002"),
+        },
+        prev_balance: Uint128::zero(),
+        minimum_receive: Some(Uint128::new(1000000u128)),
+        receiver: String::from("vault0000"),
+        callback: callback.clone(),
+    };
+
+    // internal use only
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0002"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: String::from("vault0000"),
+                amount: Uint128::new(1000000u128),
+                msg: callback.clone(),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // native output is sent along with the callback, minimum_receive is checked after tax
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[(&"uusd".to_string(), &Uint128::new(1000000u128))],
+    );
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(1000000u128),
+        }],
+    )]);
+    let msg = ExecuteMsg::SendWithCallback {
+        asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        prev_balance: Uint128::zero(),
+        minimum_receive: Some(Uint128::new(1000000u128)),
+        receiver: String::from("vault0000"),
+        callback: callback.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::AssertionMinimumReceive {
+            receive: Uint128::new(1000000u128),
+            amount: Uint128::new(952380u128),
+        }
+    );
+
+    let msg = ExecuteMsg::SendWithCallback {
+        asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        prev_balance: Uint128::zero(),
+        minimum_receive: Some(Uint128::new(900000u128)),
+        receiver: String::from("vault0000"),
+        callback: callback.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("vault0000"),
+            funds: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(952380u128),
+            }],
+            msg: callback,
+        })]
+    );
+}
@@ -0,0 +1,26 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use astroport::gauge_controller::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolWeightsResponse, QueryMsg,
+    TuneInfoResponse, UserVotesResponse, VotingPowerResponse,
+};
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(UserVotesResponse), &out_dir);
+    export_schema(&schema_for!(TuneInfoResponse), &out_dir);
+    export_schema(&schema_for!(PoolWeightsResponse), &out_dir);
+    export_schema(&schema_for!(VotingPowerResponse), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+}
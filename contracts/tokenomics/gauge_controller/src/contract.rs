@@ -0,0 +1,509 @@
+use cosmwasm_std::{
+    entry_point, from_binary, to_binary, Addr, Api, Binary, Deps, DepsMut, Env, MessageInfo,
+    Response, StdError, StdResult, Storage, SubMsg, Uint128, Uint64, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+use crate::error::ContractError;
+use crate::state::{
+    Config, TuneInfo, VoterInfo, CONFIG, OWNERSHIP_PROPOSAL, POOL_WEIGHTS, TOTAL_VOTING_POWER,
+    TUNE_INFO, VOTERS,
+};
+use astroport::asset::{addr_validate_to_lower, AssetInfo};
+use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
+use astroport::gauge_controller::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolWeightsResponse,
+    QueryMsg, TuneInfoResponse, UserVotesResponse, VotingPowerResponse, VOTE_WEIGHT_BPS,
+};
+use astroport::generator::{
+    ActivePoolsResponse, ExecuteMsg as GeneratorExecuteMsg, QueryMsg as GeneratorQueryMsg,
+    RewardInfoResponse,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "astroport-gauge-controller";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_EPOCH_LENGTH: u64 = 7 * 86400; // a week
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let epoch_length = msg.epoch_length.unwrap_or(DEFAULT_EPOCH_LENGTH);
+    if epoch_length == 0 {
+        return Err(ContractError::InvalidEpochLength {});
+    }
+
+    let config = Config {
+        owner: addr_validate_to_lower(deps.api, &msg.owner)?,
+        generator: addr_validate_to_lower(deps.api, &msg.generator)?,
+        xastro_token: addr_validate_to_lower(deps.api, &msg.xastro_token)?,
+        epoch_length,
+        total_alloc_point: msg.total_alloc_point,
+        whitelist: vec![],
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+    TUNE_INFO.save(
+        deps.storage,
+        &TuneInfo {
+            tune_time: 0,
+            alloc_points: vec![],
+        },
+    )?;
+    TOTAL_VOTING_POWER.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Vote { votes } => vote(deps, info, votes),
+        ExecuteMsg::Tune {} => tune(deps, env),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::Unlock { amount } => unlock(deps, env, info, amount),
+        ExecuteMsg::UpdateConfig {
+            epoch_length,
+            total_alloc_point,
+        } => update_config(deps, info, epoch_length, total_alloc_point),
+        ExecuteMsg::UpdateWhitelist { add, remove } => update_whitelist(deps, info, add, remove),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            propose_new_owner(
+                deps,
+                info,
+                env,
+                owner,
+                expires_in,
+                config.owner,
+                OWNERSHIP_PROPOSAL,
+            )
+            .map_err(|e| e.into())
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = CONFIG.load(deps.storage)?;
+
+            drop_ownership_proposal(deps, info, config.owner, OWNERSHIP_PROPOSAL)
+                .map_err(|e| e.into())
+        }
+        ExecuteMsg::ClaimOwnership {} => {
+            claim_ownership(deps, info, env, OWNERSHIP_PROPOSAL, |deps, new_owner| {
+                CONFIG.update::<_, StdError>(deps.storage, |mut v| {
+                    v.owner = new_owner;
+                    Ok(v)
+                })?;
+
+                Ok(())
+            })
+            .map_err(|e| e.into())
+        }
+    }
+}
+
+// Replace votes of the sender, weights of the pools are updated by the locked xASTRO of the
+// sender. Empty votes remove votes of the sender.
+fn vote(
+    deps: DepsMut,
+    info: MessageInfo,
+    votes: Vec<(AssetInfo, u16)>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut voter = VOTERS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if voter.voting_power.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+
+    let mut user_votes: Vec<(AssetInfo, u16)> = vec![];
+    let mut total_weight: u16 = 0;
+    for (lp_token, weight) in votes {
+        let lp_token = validate_lp_token(deps.api, lp_token)?;
+        if !config.whitelist.contains(&lp_token) {
+            return Err(ContractError::PoolNotWhitelisted(lp_token));
+        }
+
+        if weight == 0 || user_votes.iter().any(|(v, _)| *v == lp_token) {
+            return Err(ContractError::InvalidVotes {});
+        }
+
+        total_weight = total_weight
+            .checked_add(weight)
+            .filter(|v| *v <= VOTE_WEIGHT_BPS)
+            .ok_or(ContractError::InvalidVotes {})?;
+
+        user_votes.push((lp_token, weight));
+    }
+
+    update_pool_weights(deps.storage, &voter, false)?;
+    voter.votes = user_votes;
+    update_pool_weights(deps.storage, &voter, true)?;
+
+    VOTERS.save(deps.storage, &info.sender, &voter)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("voter", info.sender)
+        .add_attribute("voting_power", voter.voting_power))
+}
+
+// Only xASTRO can be locked
+fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.xastro_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Lock {} => {
+            let sender = addr_validate_to_lower(deps.api, &cw20_msg.sender)?;
+            lock(deps, env, &config, sender, cw20_msg.amount)
+        }
+    }
+}
+
+// Add xASTRO to the voting power of the voter. It is locked for an epoch, so xASTRO which is
+// borrowed for a vote and tune can't be returned in the same transaction.
+fn lock(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut voter = VOTERS.may_load(deps.storage, &sender)?.unwrap_or_default();
+
+    update_pool_weights(deps.storage, &voter, false)?;
+    voter.voting_power = voter.voting_power.checked_add(amount)?;
+    update_pool_weights(deps.storage, &voter, true)?;
+
+    voter.unlock_time = env.block.time.seconds() + config.epoch_length;
+
+    VOTERS.save(deps.storage, &sender, &voter)?;
+    TOTAL_VOTING_POWER.update::<_, StdError>(deps.storage, |v| Ok(v.checked_add(amount)?))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "lock")
+        .add_attribute("voter", sender)
+        .add_attribute("amount", amount))
+}
+
+// Unlock xASTRO of the sender, votes lose the unlocked voting power at once. The voter is
+// removed with the votes if all xASTRO is unlocked.
+fn unlock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut voter = VOTERS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    if env.block.time.seconds() < voter.unlock_time {
+        return Err(ContractError::Locked(voter.unlock_time));
+    }
+
+    if amount.is_zero() || amount > voter.voting_power {
+        return Err(ContractError::InvalidUnlockAmount {});
+    }
+
+    update_pool_weights(deps.storage, &voter, false)?;
+    voter.voting_power = voter.voting_power.checked_sub(amount)?;
+
+    if voter.voting_power.is_zero() {
+        VOTERS.remove(deps.storage, &info.sender);
+    } else {
+        update_pool_weights(deps.storage, &voter, true)?;
+        VOTERS.save(deps.storage, &info.sender, &voter)?;
+    }
+
+    TOTAL_VOTING_POWER.update::<_, StdError>(deps.storage, |v| Ok(v.checked_sub(amount)?))?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: config.xastro_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("action", "unlock")
+        .add_attribute("voter", info.sender)
+        .add_attribute("amount", amount))
+}
+
+// Add or subtract voted shares of the voting power of the voter to the weights of the pools.
+fn update_pool_weights(storage: &mut dyn Storage, voter: &VoterInfo, add: bool) -> StdResult<()> {
+    for (lp_token, weight) in &voter.votes {
+        let share = voter.voting_power.multiply_ratio(*weight, VOTE_WEIGHT_BPS);
+
+        POOL_WEIGHTS.update::<_, StdError>(storage, lp_token.as_bytes(), |v| {
+            let v = v.unwrap_or_default();
+            if add {
+                Ok(v.checked_add(share)?)
+            } else {
+                Ok(v.checked_sub(share)?)
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+// Split total alloc points between whitelisted pools which are not removed in the generator by
+// their weights and set them to the generator at once. Pools which are removed from the whitelist
+// since the last tune get zero.
+fn tune(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let tune_info = TUNE_INFO.load(deps.storage)?;
+
+    let next_tune_time = tune_info.tune_time + config.epoch_length;
+    if env.block.time.seconds() < next_tune_time {
+        return Err(ContractError::TuneCooldown(next_tune_time));
+    }
+
+    let active_pools: ActivePoolsResponse = deps
+        .querier
+        .query_wasm_smart(&config.generator, &GeneratorQueryMsg::ActivePools {})?;
+    let is_active = |lp_token: &AssetInfo| active_pools.pools.contains(lp_token);
+
+    let lp_tokens: Vec<AssetInfo> = config
+        .whitelist
+        .iter()
+        .filter(|v| is_active(v))
+        .cloned()
+        .collect();
+    let weights = read_pool_weights(deps.storage, &lp_tokens)?;
+
+    let total_weight = weights
+        .iter()
+        .try_fold(Uint128::zero(), |acc, (_, weight)| acc.checked_add(*weight))?;
+    if total_weight.is_zero() {
+        return Err(ContractError::NoVotes {});
+    }
+
+    let mut alloc_points: Vec<(AssetInfo, Uint64)> = vec![];
+    for (lp_token, weight) in weights {
+        let alloc_point = Uint128::from(config.total_alloc_point.u64())
+            .multiply_ratio(weight, total_weight)
+            .u128() as u64;
+        alloc_points.push((lp_token, Uint64::from(alloc_point)));
+    }
+
+    let mut pools: Vec<(AssetInfo, Uint64)> = vec![];
+
+    for (lp_token, _) in &tune_info.alloc_points {
+        if !alloc_points.iter().any(|(v, _)| v == lp_token) && is_active(lp_token) {
+            pools.push((lp_token.clone(), Uint64::zero()));
+        }
+    }
+
+    pools.extend(alloc_points.iter().cloned());
+
+    TUNE_INFO.save(
+        deps.storage,
+        &TuneInfo {
+            tune_time: env.block.time.seconds(),
+            alloc_points,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(WasmMsg::Execute {
+            contract_addr: config.generator.to_string(),
+            msg: to_binary(&GeneratorExecuteMsg::SetAllocPoints { pools })?,
+            funds: vec![],
+        }))
+        .add_attribute("action", "tune"))
+}
+
+// Weights of the pools are the sums of voted shares of locked xASTRO.
+pub fn read_pool_weights(
+    storage: &dyn Storage,
+    lp_tokens: &[AssetInfo],
+) -> StdResult<Vec<(AssetInfo, Uint128)>> {
+    lp_tokens
+        .iter()
+        .map(|lp_token| {
+            Ok((
+                lp_token.clone(),
+                POOL_WEIGHTS
+                    .may_load(storage, lp_token.as_bytes())?
+                    .unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+// Only owner can execute it
+fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    epoch_length: Option<u64>,
+    total_alloc_point: Option<Uint64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(epoch_length) = epoch_length {
+        if epoch_length == 0 {
+            return Err(ContractError::InvalidEpochLength {});
+        }
+        config.epoch_length = epoch_length;
+    }
+
+    if let Some(total_alloc_point) = total_alloc_point {
+        config.total_alloc_point = total_alloc_point;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+// Only owner can execute it. Added LP tokens must be registered in the generator.
+fn update_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Option<Vec<AssetInfo>>,
+    remove: Option<Vec<AssetInfo>>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for lp_token in remove.unwrap_or_default() {
+        let lp_token = validate_lp_token(deps.api, lp_token)?;
+        config.whitelist.retain(|v| *v != lp_token);
+    }
+
+    for lp_token in add.unwrap_or_default() {
+        let lp_token = validate_lp_token(deps.api, lp_token)?;
+
+        let _: RewardInfoResponse = deps.querier.query_wasm_smart(
+            &config.generator,
+            &GeneratorQueryMsg::RewardInfo {
+                lp_token: lp_token.clone(),
+            },
+        )?;
+
+        if !config.whitelist.contains(&lp_token) {
+            config.whitelist.push(lp_token);
+        }
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_whitelist"))
+}
+
+// Validate an LP token, addresses of CW20 LP tokens are lowercased like in the generator.
+fn validate_lp_token(api: &dyn Api, lp_token: AssetInfo) -> StdResult<AssetInfo> {
+    lp_token.check(api)?;
+
+    Ok(match lp_token {
+        AssetInfo::Token { contract_addr } => AssetInfo::Token {
+            contract_addr: addr_validate_to_lower(api, contract_addr.as_str())?,
+        },
+        AssetInfo::NativeToken { denom } => AssetInfo::NativeToken { denom },
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::UserVotes { user } => to_binary(&query_user_votes(deps, user)?),
+        QueryMsg::TuneInfo {} => to_binary(&query_tune_info(deps)?),
+        QueryMsg::PoolWeights {} => to_binary(&query_pool_weights(deps)?),
+        QueryMsg::VotingPower { user } => to_binary(&query_voting_power(deps, user)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigResponse {
+        owner: config.owner,
+        generator: config.generator,
+        xastro_token: config.xastro_token,
+        epoch_length: config.epoch_length,
+        total_alloc_point: config.total_alloc_point,
+        whitelist: config.whitelist,
+    })
+}
+
+fn query_user_votes(deps: Deps, user: String) -> StdResult<UserVotesResponse> {
+    let user = addr_validate_to_lower(deps.api, &user)?;
+    let voter = VOTERS.may_load(deps.storage, &user)?.unwrap_or_default();
+
+    Ok(UserVotesResponse {
+        voting_power: voter.voting_power,
+        unlock_time: voter.unlock_time,
+        votes: voter.votes,
+    })
+}
+
+fn query_tune_info(deps: Deps) -> StdResult<TuneInfoResponse> {
+    let tune_info = TUNE_INFO.load(deps.storage)?;
+
+    Ok(TuneInfoResponse {
+        tune_time: tune_info.tune_time,
+        alloc_points: tune_info.alloc_points,
+    })
+}
+
+fn query_pool_weights(deps: Deps) -> StdResult<PoolWeightsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(PoolWeightsResponse {
+        weights: read_pool_weights(deps.storage, &config.whitelist)?,
+    })
+}
+
+fn query_voting_power(deps: Deps, user: String) -> StdResult<VotingPowerResponse> {
+    let user = addr_validate_to_lower(deps.api, &user)?;
+    let voter = VOTERS.may_load(deps.storage, &user)?.unwrap_or_default();
+
+    Ok(VotingPowerResponse {
+        voting_power: voter.voting_power,
+        total_voting_power: TOTAL_VOTING_POWER.load(deps.storage)?,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
@@ -0,0 +1,42 @@
+use astroport::asset::AssetInfo;
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Pool {0} is not whitelisted")]
+    PoolNotWhitelisted(AssetInfo),
+
+    #[error("Votes must be given once per pool with a total weight up to 10000 bps")]
+    InvalidVotes {},
+
+    #[error("Sender doesn't have locked xASTRO to vote")]
+    NoVotingPower {},
+
+    #[error("xASTRO is locked until {0}")]
+    Locked(u64),
+
+    #[error("Unlock amount must be positive and can't exceed locked xASTRO")]
+    InvalidUnlockAmount {},
+
+    #[error("Next tune is available at {0}")]
+    TuneCooldown(u64),
+
+    #[error("There are no votes for whitelisted pools")]
+    NoVotes {},
+
+    #[error("Epoch length must be positive")]
+    InvalidEpochLength {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
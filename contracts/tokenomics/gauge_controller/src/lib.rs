@@ -0,0 +1,9 @@
+pub mod contract;
+mod error;
+pub mod state;
+
+#[cfg(test)]
+mod testing;
+
+#[cfg(test)]
+mod mock_querier;
@@ -0,0 +1,118 @@
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Addr, Coin, ContractResult, Empty, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+};
+use std::collections::HashMap;
+
+use astroport::asset::AssetInfo;
+use astroport::generator::{
+    ActivePoolsResponse, QueryMsg as GeneratorQueryMsg, RewardInfoResponse,
+};
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    // generator contract -> registered LP tokens
+    generator_pools: HashMap<String, Vec<AssetInfo>>,
+    // generator contract -> removed LP tokens
+    removed_pools: HashMap<String, Vec<AssetInfo>>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                let pools = self
+                    .generator_pools
+                    .get(contract_addr)
+                    .expect("DO NOT ENTER HERE");
+                let removed_pools = self
+                    .removed_pools
+                    .get(contract_addr)
+                    .cloned()
+                    .unwrap_or_default();
+
+                match from_binary(msg).unwrap() {
+                    GeneratorQueryMsg::RewardInfo { lp_token } => {
+                        if pools.contains(&lp_token) {
+                            SystemResult::Ok(
+                                to_binary(&RewardInfoResponse {
+                                    base_reward_token: Addr::unchecked("astro"),
+                                    proxy_reward_token: None,
+                                    external_reward_tokens: vec![],
+                                })
+                                .into(),
+                            )
+                        } else {
+                            SystemResult::Ok(ContractResult::Err("PoolInfo not found".to_string()))
+                        }
+                    }
+                    GeneratorQueryMsg::ActivePools {} => SystemResult::Ok(
+                        to_binary(&ActivePoolsResponse {
+                            pools: pools
+                                .iter()
+                                .filter(|v| !removed_pools.contains(v))
+                                .cloned()
+                                .collect(),
+                        })
+                        .into(),
+                    ),
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            generator_pools: HashMap::new(),
+            removed_pools: HashMap::new(),
+        }
+    }
+
+    // configure LP tokens registered in the generator
+    pub fn with_generator_pools(&mut self, generator: &str, lp_tokens: &[AssetInfo]) {
+        self.generator_pools
+            .insert(generator.to_string(), lp_tokens.to_vec());
+    }
+
+    // configure LP tokens removed in the generator
+    pub fn with_removed_pools(&mut self, generator: &str, lp_tokens: &[AssetInfo]) {
+        self.removed_pools
+            .insert(generator.to_string(), lp_tokens.to_vec());
+    }
+}
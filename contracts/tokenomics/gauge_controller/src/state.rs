@@ -0,0 +1,47 @@
+use astroport::asset::AssetInfo;
+use astroport::common::OwnershipProposal;
+use cosmwasm_std::{Addr, Uint128, Uint64};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: Addr,
+    pub generator: Addr,
+    pub xastro_token: Addr,
+    // Time in seconds between tunes
+    pub epoch_length: u64,
+    // Alloc points which are split between pools on tune
+    pub total_alloc_point: Uint64,
+    // LP tokens which can be voted for
+    pub whitelist: Vec<AssetInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TuneInfo {
+    pub tune_time: u64,
+    pub alloc_points: Vec<(AssetInfo, Uint64)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct VoterInfo {
+    // xASTRO locked by the voter
+    pub voting_power: Uint128,
+    // Time when locked xASTRO can be unlocked
+    pub unlock_time: u64,
+    // LP tokens and weights in basis points
+    pub votes: Vec<(AssetInfo, u16)>,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const TUNE_INFO: Item<TuneInfo> = Item::new("tune_info");
+
+pub const VOTERS: Map<&Addr, VoterInfo> = Map::new("voters");
+// xASTRO locked by all voters
+pub const TOTAL_VOTING_POWER: Item<Uint128> = Item::new("total_voting_power");
+// Sums of voted shares of locked xASTRO by LP tokens, they are updated on every vote, lock and
+// unlock so tune doesn't iterate voters. Keyed by bytes of LP tokens, so native LP tokens can be
+// voted for too
+pub const POOL_WEIGHTS: Map<&[u8], Uint128> = Map::new("pool_weights");
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
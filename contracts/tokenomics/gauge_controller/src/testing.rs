@@ -0,0 +1,634 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Env, OwnedDeps, Response, SubMsg, Uint128, Uint64, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+use astroport::asset::AssetInfo;
+use astroport::gauge_controller::{
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolWeightsResponse, QueryMsg,
+    TuneInfoResponse, UserVotesResponse, VotingPowerResponse,
+};
+use astroport::generator::ExecuteMsg as GeneratorExecuteMsg;
+
+const OWNER: &str = "owner";
+const GENERATOR: &str = "generator";
+const XASTRO: &str = "xastro";
+const LP_1: &str = "lp_token1";
+const LP_2: &str = "lp_token2";
+const LP_3: &str = "lp_token3";
+const EPOCH: u64 = 604800;
+
+const NATIVE_LP: &str = "native_lp";
+
+fn token(lp_token: &str) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: Addr::unchecked(lp_token),
+    }
+}
+
+fn native_token(denom: &str) -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    }
+}
+
+fn set_querier(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+    deps.querier.with_generator_pools(
+        GENERATOR,
+        &[
+            token(LP_1),
+            token(LP_2),
+            token(LP_3),
+            native_token(NATIVE_LP),
+        ],
+    );
+}
+
+fn set_alloc_points_msg(pools: &[(AssetInfo, u64)]) -> SubMsg {
+    SubMsg::new(WasmMsg::Execute {
+        contract_addr: GENERATOR.to_string(),
+        msg: to_binary(&GeneratorExecuteMsg::SetAllocPoints {
+            pools: pools
+                .iter()
+                .map(|(lp_token, alloc_point)| (lp_token.clone(), Uint64::new(*alloc_point)))
+                .collect(),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+fn env_after(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn lock(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    user: &str,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: user.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&Cw20HookMsg::Lock {}).unwrap(),
+    });
+    execute(deps.as_mut(), env, mock_info(XASTRO, &[]), msg)
+}
+
+fn query_user_votes(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    user: &str,
+) -> UserVotesResponse {
+    let msg = QueryMsg::UserVotes {
+        user: user.to_string(),
+    };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+fn query_voting_power(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    user: &str,
+) -> VotingPowerResponse {
+    let msg = QueryMsg::VotingPower {
+        user: user.to_string(),
+    };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+fn query_pool_weights(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+) -> Vec<(AssetInfo, Uint128)> {
+    let res: PoolWeightsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PoolWeights {}).unwrap()).unwrap();
+    res.weights
+}
+
+fn instantiate_with_whitelist(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>) {
+    let msg = InstantiateMsg {
+        owner: OWNER.to_string(),
+        generator: GENERATOR.to_string(),
+        xastro_token: XASTRO.to_string(),
+        epoch_length: None,
+        total_alloc_point: Uint64::new(1000),
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: Some(vec![token(LP_1), token(LP_2)]),
+        remove: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: OWNER.to_string(),
+        generator: GENERATOR.to_string(),
+        xastro_token: XASTRO.to_string(),
+        epoch_length: None,
+        total_alloc_point: Uint64::new(1000),
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            owner: Addr::unchecked(OWNER),
+            generator: Addr::unchecked(GENERATOR),
+            xastro_token: Addr::unchecked(XASTRO),
+            epoch_length: EPOCH,
+            total_alloc_point: Uint64::new(1000),
+            whitelist: vec![],
+        }
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+
+    let msg = InstantiateMsg {
+        owner: OWNER.to_string(),
+        generator: GENERATOR.to_string(),
+        xastro_token: XASTRO.to_string(),
+        epoch_length: Some(0),
+        total_alloc_point: Uint64::new(1000),
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidEpochLength {});
+
+    instantiate_with_whitelist(&mut deps);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        epoch_length: Some(86400),
+        total_alloc_point: Some(Uint64::new(500)),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.epoch_length, 86400);
+    assert_eq!(config.total_alloc_point, Uint64::new(500));
+
+    // Zero epoch length would allow tunes in every block
+    let msg = ExecuteMsg::UpdateConfig {
+        epoch_length: Some(0),
+        total_alloc_point: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidEpochLength {});
+}
+
+#[test]
+fn update_whitelist() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+    instantiate_with_whitelist(&mut deps);
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: Some(vec![token(LP_3)]),
+        remove: Some(vec![token(LP_1)]),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.whitelist, vec![token(LP_2), token(LP_3)]);
+
+    // LP token must be registered in the generator
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: Some(vec![token("unknown")]),
+        remove: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+}
+
+#[test]
+fn vote() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+    instantiate_with_whitelist(&mut deps);
+
+    lock(&mut deps, mock_env(), "user1", 100).unwrap();
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_3), 10000)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::PoolNotWhitelisted(token(LP_3)));
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 6000), (token(LP_2), 5000)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidVotes {});
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 1000), (token(LP_1), 1000)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidVotes {});
+
+    // xASTRO must be locked to vote
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 10000)],
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("user2", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::NoVotingPower {});
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 6000), (token(LP_2), 4000)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    assert_eq!(
+        query_user_votes(&deps, "user1"),
+        UserVotesResponse {
+            voting_power: Uint128::new(100),
+            unlock_time: mock_env().block.time.seconds() + EPOCH,
+            votes: vec![(token(LP_1), 6000), (token(LP_2), 4000)],
+        }
+    );
+    assert_eq!(
+        query_pool_weights(&deps),
+        vec![
+            (token(LP_1), Uint128::new(60)),
+            (token(LP_2), Uint128::new(40))
+        ]
+    );
+
+    // Votes are replaced
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_2), 5000)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    assert_eq!(
+        query_pool_weights(&deps),
+        vec![
+            (token(LP_1), Uint128::zero()),
+            (token(LP_2), Uint128::new(50))
+        ]
+    );
+
+    // Empty votes remove votes of the voter
+    let msg = ExecuteMsg::Vote { votes: vec![] };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    assert_eq!(query_user_votes(&deps, "user1").votes, vec![]);
+    assert_eq!(
+        query_pool_weights(&deps),
+        vec![
+            (token(LP_1), Uint128::zero()),
+            (token(LP_2), Uint128::zero())
+        ]
+    );
+}
+
+#[test]
+fn lock_and_unlock() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+    instantiate_with_whitelist(&mut deps);
+
+    // Only xASTRO can be locked
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "user1".to_string(),
+        amount: Uint128::new(100),
+        msg: to_binary(&Cw20HookMsg::Lock {}).unwrap(),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("astro", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    lock(&mut deps, mock_env(), "user1", 100).unwrap();
+    lock(&mut deps, mock_env(), "user2", 20).unwrap();
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 10000)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    // Votes get the locked voting power at once, the lock is extended
+    lock(&mut deps, env_after(100), "user1", 50).unwrap();
+
+    let unlock_time = mock_env().block.time.seconds() + 100 + EPOCH;
+    assert_eq!(
+        query_user_votes(&deps, "user1"),
+        UserVotesResponse {
+            voting_power: Uint128::new(150),
+            unlock_time,
+            votes: vec![(token(LP_1), 10000)],
+        }
+    );
+    assert_eq!(
+        query_pool_weights(&deps)[0],
+        (token(LP_1), Uint128::new(150))
+    );
+    assert_eq!(
+        query_voting_power(&deps, "user1"),
+        VotingPowerResponse {
+            voting_power: Uint128::new(150),
+            total_voting_power: Uint128::new(170),
+        }
+    );
+
+    let msg = ExecuteMsg::Unlock {
+        amount: Uint128::new(50),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env_after(EPOCH),
+        mock_info("user1", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Locked(unlock_time));
+
+    for amount in [0, 200] {
+        let msg = ExecuteMsg::Unlock {
+            amount: Uint128::new(amount),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env_after(100 + EPOCH),
+            mock_info("user1", &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidUnlockAmount {});
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env_after(100 + EPOCH),
+        mock_info("user1", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: XASTRO.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "user1".to_string(),
+                amount: Uint128::new(50),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        query_pool_weights(&deps)[0],
+        (token(LP_1), Uint128::new(100))
+    );
+    assert_eq!(
+        query_voting_power(&deps, "user1"),
+        VotingPowerResponse {
+            voting_power: Uint128::new(100),
+            total_voting_power: Uint128::new(120),
+        }
+    );
+
+    // The voter is removed with the votes
+    let msg = ExecuteMsg::Unlock {
+        amount: Uint128::new(100),
+    };
+    execute(
+        deps.as_mut(),
+        env_after(100 + EPOCH),
+        mock_info("user1", &[]),
+        msg,
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_user_votes(&deps, "user1"),
+        UserVotesResponse {
+            voting_power: Uint128::zero(),
+            unlock_time: 0,
+            votes: vec![],
+        }
+    );
+    assert_eq!(query_pool_weights(&deps)[0], (token(LP_1), Uint128::zero()));
+    assert_eq!(
+        query_voting_power(&deps, "user1"),
+        VotingPowerResponse {
+            voting_power: Uint128::zero(),
+            total_voting_power: Uint128::new(20),
+        }
+    );
+}
+
+#[test]
+fn tune() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+    instantiate_with_whitelist(&mut deps);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVotes {});
+
+    lock(&mut deps, mock_env(), "user1", 100).unwrap();
+    lock(&mut deps, mock_env(), "user2", 300).unwrap();
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 10000)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(token(LP_1), 5000), (token(LP_2), 5000)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user2", &[]), msg).unwrap();
+
+    // LP_1: 100 + 150, LP_2: 150, alloc points are set at once
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![set_alloc_points_msg(&[
+            (token(LP_1), 625),
+            (token(LP_2), 375)
+        ])]
+    );
+
+    let res: TuneInfoResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TuneInfo {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        TuneInfoResponse {
+            tune_time: mock_env().block.time.seconds(),
+            alloc_points: vec![
+                (token(LP_1), Uint64::new(625)),
+                (token(LP_2), Uint64::new(375))
+            ],
+        }
+    );
+
+    // Tune is available once per epoch
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TuneCooldown(mock_env().block.time.seconds() + EPOCH)
+    );
+
+    // Voting power is changed by locks and unlocks, removed pools get zero
+    lock(&mut deps, env_after(EPOCH), "user1", 200).unwrap();
+
+    let msg = ExecuteMsg::Unlock {
+        amount: Uint128::new(200),
+    };
+    execute(
+        deps.as_mut(),
+        env_after(EPOCH),
+        mock_info("user2", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: None,
+        remove: Some(vec![token(LP_2)]),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env_after(EPOCH),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![set_alloc_points_msg(&[
+            (token(LP_2), 0),
+            (token(LP_1), 1000)
+        ])]
+    );
+
+    // Pools which are removed in the generator are skipped
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: Some(vec![token(LP_2)]),
+        remove: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    deps.querier.with_removed_pools(GENERATOR, &[token(LP_1)]);
+
+    let res = execute(
+        deps.as_mut(),
+        env_after(2 * EPOCH),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![set_alloc_points_msg(&[(token(LP_2), 1000)])]
+    );
+
+    let res: PoolWeightsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PoolWeights {}).unwrap()).unwrap();
+    assert_eq!(
+        res.weights,
+        vec![
+            (token(LP_1), Uint128::new(350)),
+            (token(LP_2), Uint128::new(50))
+        ]
+    );
+}
+
+#[test]
+fn native_lp_token() {
+    let mut deps = mock_dependencies(&[]);
+    set_querier(&mut deps);
+    instantiate_with_whitelist(&mut deps);
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        add: Some(vec![native_token(NATIVE_LP)]),
+        remove: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+    lock(&mut deps, mock_env(), "user1", 100).unwrap();
+
+    let msg = ExecuteMsg::Vote {
+        votes: vec![(native_token(NATIVE_LP), 7500), (token(LP_1), 2500)],
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("user1", &[]), msg).unwrap();
+
+    assert_eq!(
+        query_pool_weights(&deps),
+        vec![
+            (token(LP_1), Uint128::new(25)),
+            (token(LP_2), Uint128::zero()),
+            (native_token(NATIVE_LP), Uint128::new(75))
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Tune {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![set_alloc_points_msg(&[
+            (token(LP_1), 250),
+            (token(LP_2), 0),
+            (native_token(NATIVE_LP), 750)
+        ])]
+    );
+}
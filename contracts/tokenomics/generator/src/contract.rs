@@ -1,33 +1,54 @@
 use cosmwasm_std::{
-    entry_point, from_binary, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo,
-    Reply, ReplyOn, Response, StdError, StdResult, SubMsg, Uint128, Uint64, WasmMsg,
+    entry_point, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal,
+    Deps, DepsMut, Env, MessageInfo, Order, QuerierWrapper, Reply, ReplyOn, Response, StdError,
+    StdResult, Storage, SubMsg, Uint128, Uint64, WasmMsg,
 };
-use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_storage_plus::U64Key;
 
 use crate::error::ContractError;
+use crate::migration::{self, MIGRATION_CURSOR};
 use crate::state::{
-    Config, ExecuteOnReply, PoolInfo, CONFIG, OWNERSHIP_PROPOSAL, POOL_INFO, TMP_USER_ACTION,
-    USER_INFO,
+    read_pools, read_user_positions, Config, ExecuteOnReply, ExternalReward, PoolInfo,
+    RewardSchedule, UserInfo, ACTIVE_POOLS, CONFIG, LAST_LOCK_ID, LAST_REWARD_ID, LOCKED_POSITIONS,
+    OWNERSHIP_PROPOSAL, POOL_INFO, TMP_USER_ACTION, USER_INFO, USER_POSITIONS,
 };
-use astroport::asset::addr_validate_to_lower;
+use astroport::asset::{addr_validate_to_lower, Asset, AssetInfo};
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::DecimalCheckedOps;
 use astroport::{
+    gauge_controller::{QueryMsg as GaugeQueryMsg, VotingPowerResponse},
     generator::{
-        ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PendingTokenResponse,
-        PoolLengthResponse, QueryMsg, RewardInfoResponse,
+        ActivePoolsResponse, ConfigResponse, Cw20HookMsg, EmissionPeriod, ExecuteMsg,
+        InstantiateMsg, LockDuration, LockedPosition, LockedPositionsResponse, MigrateMsg,
+        PendingTokenResponse, PoolInfoResponse, PoolLengthResponse, PoolsResponse, QueryMsg,
+        RewardInfoResponse, UserPositionResponse, UserPositionsResponse,
     },
     generator_proxy::{
-        Cw20HookMsg as ProxyCw20HookMsg, ExecuteMsg as ProxyExecuteMsg, QueryMsg as ProxyQueryMsg,
+        ConfigResponse as ProxyConfigResponse, Cw20HookMsg as ProxyCw20HookMsg,
+        ExecuteMsg as ProxyExecuteMsg, QueryMsg as ProxyQueryMsg,
+    },
+    vesting::{
+        ExecuteMsg as VestingExecuteMsg, QueryMsg as VestingQueryMsg, VestingAccountResponse,
     },
-    vesting::ExecuteMsg as VestingExecuteMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "astroport-generator";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Percent of the LP amount which is counted without locked xASTRO, the boost is up to 100 / 40 = 2.5x
+const TOKENLESS_PRODUCTION: u64 = 40;
+
+// Percent of locked LP tokens which is charged on emergency withdrawal before the unlock time
+const EARLY_WITHDRAW_PENALTY: u64 = 10;
+
+// Maximum number of external rewards of a pool and of not finished schedules of every reward,
+// anyone can add them for the allowed tokens
+const MAX_EXTERNAL_REWARDS: usize = 10;
+const MAX_REWARD_SCHEDULES: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -42,18 +63,30 @@ pub fn instantiate(
         allowed_reward_proxies.push(addr_validate_to_lower(deps.api, &proxy)?);
     }
 
+    validate_emission_schedule(&msg.emission_schedule, msg.end_time)?;
+
     let config = Config {
         owner: addr_validate_to_lower(deps.api, &msg.owner)?,
         astro_token: addr_validate_to_lower(deps.api, &msg.astro_token)?,
-        tokens_per_block: msg.tokens_per_block,
+        emission_schedule: msg.emission_schedule,
+        end_time: msg.end_time,
         total_alloc_point: Uint64::from(0u64),
-        start_block: msg.start_block,
         allowed_reward_proxies,
         vesting_contract: addr_validate_to_lower(deps.api, &msg.vesting_contract)?,
+        gauge_controller: msg
+            .gauge_controller
+            .map(|v| addr_validate_to_lower(deps.api, &v))
+            .transpose()?,
+        generator_controller: msg
+            .generator_controller
+            .map(|v| addr_validate_to_lower(deps.api, &v))
+            .transpose()?,
+        allowed_external_rewards: vec![],
     };
 
     CONFIG.save(deps.storage, &config)?;
     TMP_USER_ACTION.save(deps.storage, &None)?;
+    ACTIVE_POOLS.save(deps.storage, &vec![])?;
 
     Ok(Response::default())
 }
@@ -65,10 +98,25 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Users are migrated before anything else
+    if MIGRATION_CURSOR.may_load(deps.storage)?.is_some()
+        && !matches!(msg, ExecuteMsg::MigrateUsers { .. })
+    {
+        return Err(ContractError::MigrationInProgress {});
+    }
+
     match msg {
-        ExecuteMsg::UpdateConfig { vesting_contract } => {
-            execute_update_config(deps, info, vesting_contract)
-        }
+        ExecuteMsg::UpdateConfig {
+            vesting_contract,
+            gauge_controller,
+            generator_controller,
+        } => execute_update_config(
+            deps,
+            info,
+            vesting_contract,
+            gauge_controller,
+            generator_controller,
+        ),
         ExecuteMsg::Add {
             lp_token,
             alloc_point,
@@ -95,7 +143,7 @@ pub fn execute(
             alloc_point,
         } => {
             let cfg = CONFIG.load(deps.storage)?;
-            if info.sender != cfg.owner {
+            if !is_alloc_points_setter(&cfg, &info.sender) {
                 return Err(ContractError::Unauthorized {});
             }
 
@@ -109,19 +157,35 @@ pub fn execute(
                 },
             )
         }
+        ExecuteMsg::SetAllocPoints { pools } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            if !is_alloc_points_setter(&cfg, &info.sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            update_rewards_and_execute(deps, env, None, ExecuteOnReply::SetAllocPoints { pools })
+        }
+        ExecuteMsg::RemovePool { lp_token } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            if info.sender != cfg.owner {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            update_rewards_and_execute(deps, env, None, ExecuteOnReply::RemovePool { lp_token })
+        }
         ExecuteMsg::MassUpdatePools {} => {
             update_rewards_and_execute(deps, env, None, ExecuteOnReply::MassUpdatePools {})
         }
         ExecuteMsg::UpdatePool { lp_token } => update_rewards_and_execute(
             deps,
             env,
-            Some(lp_token.clone()),
+            Some(vec![lp_token.clone()]),
             ExecuteOnReply::UpdatePool { lp_token },
         ),
         ExecuteMsg::Withdraw { lp_token, amount } => update_rewards_and_execute(
             deps,
             env,
-            Some(lp_token.clone()),
+            Some(vec![lp_token.clone()]),
             ExecuteOnReply::Withdraw {
                 lp_token,
                 account: info.sender,
@@ -129,25 +193,141 @@ pub fn execute(
             },
         ),
         ExecuteMsg::EmergencyWithdraw { lp_token } => emergency_withdraw(deps, env, info, lp_token),
+        ExecuteMsg::ClaimRewards {
+            lp_tokens,
+            recipient,
+        } => {
+            let pools = validate_lp_tokens(deps.as_ref(), lp_tokens)?;
+
+            let recipient = match recipient {
+                Some(recipient) => addr_validate_to_lower(deps.api, &recipient)?,
+                None => info.sender.clone(),
+            };
+
+            update_rewards_and_execute(
+                deps,
+                env,
+                Some(pools.clone()),
+                ExecuteOnReply::ClaimRewards {
+                    lp_tokens: pools,
+                    account: info.sender,
+                    recipient,
+                },
+            )
+        }
+        ExecuteMsg::AddRewardSchedule {
+            lp_token,
+            reward,
+            start_time,
+            end_time,
+        } => execute_add_reward_schedule(deps, env, info, lp_token, reward, start_time, end_time),
+        ExecuteMsg::CancelRewardSchedules {
+            lp_token,
+            reward_info,
+            recipient,
+            remove_reward,
+        } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            if info.sender != cfg.owner {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let recipient = recipient
+                .map(|v| addr_validate_to_lower(deps.api, &v))
+                .transpose()?;
+
+            update_rewards_and_execute(
+                deps,
+                env,
+                Some(vec![lp_token.clone()]),
+                ExecuteOnReply::CancelRewardSchedules {
+                    lp_token,
+                    reward_info,
+                    recipient,
+                    remove_reward,
+                },
+            )
+        }
+        ExecuteMsg::CheckpointUserBoost { lp_tokens, user } => {
+            let pools = validate_lp_tokens(deps.as_ref(), lp_tokens)?;
+
+            let user = match user {
+                Some(user) => addr_validate_to_lower(deps.api, &user)?,
+                None => info.sender,
+            };
+
+            update_rewards_and_execute(
+                deps,
+                env,
+                Some(pools.clone()),
+                ExecuteOnReply::CheckpointUserBoost {
+                    lp_tokens: pools,
+                    user,
+                },
+            )
+        }
         ExecuteMsg::SetAllowedRewardProxies { proxies } => {
             set_allowed_reward_proxies(deps, info, proxies)
         }
+        ExecuteMsg::SetAllowedExternalRewards { rewards } => {
+            set_allowed_external_rewards(deps, info, rewards)
+        }
         ExecuteMsg::SendOrphanProxyReward {
             recipient,
             lp_token,
         } => send_orphan_proxy_rewards(deps, info, recipient, lp_token),
+        ExecuteMsg::MigrateProxy {
+            lp_token,
+            new_proxy,
+        } => {
+            let cfg = CONFIG.load(deps.storage)?;
+            if info.sender != cfg.owner {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            update_rewards_and_execute(
+                deps,
+                env,
+                Some(vec![lp_token.clone()]),
+                ExecuteOnReply::MigrateProxy {
+                    lp_token,
+                    new_proxy,
+                },
+            )
+        }
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::SetTokensPerBlock { amount } => {
+        ExecuteMsg::Deposit {
+            beneficiary,
+            lock_duration,
+        } => receive_native(deps, env, info, beneficiary, lock_duration),
+        ExecuteMsg::SetEmissionSchedule {
+            emission_schedule,
+            end_time,
+        } => {
             let cfg = CONFIG.load(deps.storage)?;
             if info.sender != cfg.owner {
                 return Err(ContractError::Unauthorized {});
             }
 
+            validate_emission_schedule(&emission_schedule, end_time)?;
+            let unclaimed = unclaimed_astro(deps.as_ref(), &env, &cfg)?;
+            assert_emission_covered(
+                deps.as_ref(),
+                &env,
+                &cfg,
+                &emission_schedule,
+                end_time,
+                unclaimed,
+            )?;
+
             update_rewards_and_execute(
                 deps,
                 env,
                 None,
-                ExecuteOnReply::SetTokensPerBlock { amount },
+                ExecuteOnReply::SetEmissionSchedule {
+                    emission_schedule,
+                    end_time,
+                },
             )
         }
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
@@ -170,6 +350,7 @@ pub fn execute(
             drop_ownership_proposal(deps, info, config.owner, OWNERSHIP_PROPOSAL)
                 .map_err(|e| e.into())
         }
+        ExecuteMsg::MigrateUsers { limit } => migrate_users(deps, limit),
         ExecuteMsg::ClaimOwnership {} => {
             claim_ownership(deps, info, env, OWNERSHIP_PROPOSAL, |deps, new_owner| {
                 CONFIG.update::<_, StdError>(deps.storage, |mut v| {
@@ -189,6 +370,8 @@ pub fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
     vesting_contract: Option<String>,
+    gauge_controller: Option<String>,
+    generator_controller: Option<String>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
@@ -201,6 +384,15 @@ pub fn execute_update_config(
         config.vesting_contract = addr_validate_to_lower(deps.api, vesting_contract.as_str())?;
     }
 
+    if let Some(gauge_controller) = gauge_controller {
+        config.gauge_controller = Some(addr_validate_to_lower(deps.api, &gauge_controller)?);
+    }
+
+    if let Some(generator_controller) = generator_controller {
+        config.generator_controller =
+            Some(addr_validate_to_lower(deps.api, &generator_controller)?);
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -210,15 +402,15 @@ pub fn execute_update_config(
 pub fn add(
     mut deps: DepsMut,
     env: Env,
-    lp_token: Addr,
+    lp_token: AssetInfo,
     alloc_point: Uint64,
     reward_proxy: Option<String>,
 ) -> Result<Response, ContractError> {
     let mut cfg = CONFIG.load(deps.storage)?;
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
-    if POOL_INFO.load(deps.storage, &lp_token).is_ok() {
+    if POOL_INFO.load(deps.storage, lp_token.as_bytes()).is_ok() {
         return Err(ContractError::TokenPoolAlreadyExists {});
     }
 
@@ -227,9 +419,15 @@ pub fn add(
         .transpose()?;
 
     if let Some(proxy) = &reward_proxy {
+        if lp_token.is_native_token() {
+            return Err(ContractError::RewardProxyNotSupported {});
+        }
+
         if !cfg.allowed_reward_proxies.contains(proxy) {
             return Err(ContractError::RewardProxyNotAllowed {});
         }
+
+        validate_reward_proxy(deps.as_ref(), &lp_token, proxy)?;
     }
 
     mass_update_pools(deps.branch(), env.clone())?;
@@ -237,35 +435,59 @@ pub fn add(
     cfg.total_alloc_point = cfg.total_alloc_point.checked_add(alloc_point)?;
 
     let pool_info = PoolInfo {
+        lp_token: lp_token.clone(),
         alloc_point,
-        last_reward_block: (cfg.start_block).max(Uint64::from(env.block.height)),
+        last_reward_time: Uint64::from(env.block.time.seconds()),
         acc_per_share: Decimal::zero(),
         reward_proxy,
         acc_per_share_on_proxy: Decimal::zero(),
         proxy_reward_balance_before_update: Uint128::zero(),
         orphan_proxy_rewards: Uint128::zero(),
+        external_rewards: vec![],
+        working_supply: Uint128::zero(),
+        deposited: Uint128::zero(),
+        reward_debt_supply: Uint128::zero(),
     };
 
+    // The vesting account of the generator is registered after the instantiation,
+    // so the emission is checked when pools start to get it
+    assert_emission_covered(
+        deps.as_ref(),
+        &env,
+        &cfg,
+        &cfg.emission_schedule,
+        cfg.end_time,
+        unclaimed_astro(deps.as_ref(), &env, &cfg)?,
+    )?;
+
     CONFIG.save(deps.storage, &cfg)?;
-    POOL_INFO.save(deps.storage, &lp_token, &pool_info)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool_info)?;
+    ACTIVE_POOLS.update::<_, StdError>(deps.storage, |mut v| {
+        v.push(lp_token.clone());
+        Ok(v)
+    })?;
 
     Ok(Response::new()
         .add_attribute("action", "add_pool")
-        .add_attribute("lp_token", lp_token))
+        .add_attribute("lp_token", lp_token.to_string()))
 }
 
-// Update the given pool's ASTRO allocation point. Can only be called by the owner.
+// Update the given pool's ASTRO allocation point. Can only be called by the owner or the generator
+// controller.
 pub fn set(
     mut deps: DepsMut,
     env: Env,
-    lp_token: Addr,
+    lp_token: AssetInfo,
     alloc_point: Uint64,
 ) -> Result<Response, ContractError> {
     let mut cfg = CONFIG.load(deps.storage)?;
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
-    let mut pool_info = POOL_INFO.load(deps.storage, &lp_token)?;
+    let mut pool_info = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    if !ACTIVE_POOLS.load(deps.storage)?.contains(&lp_token) {
+        return Err(ContractError::PoolRemoved {});
+    }
 
     mass_update_pools(deps.branch(), env)?;
 
@@ -276,17 +498,88 @@ pub fn set(
     pool_info.alloc_point = alloc_point;
 
     CONFIG.save(deps.storage, &cfg)?;
-    POOL_INFO.save(deps.storage, &lp_token, &pool_info)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool_info)?;
 
     Ok(Response::new()
         .add_attribute("action", "set_pool")
-        .add_attribute("lp_token", lp_token))
+        .add_attribute("lp_token", lp_token.to_string()))
+}
+
+// Set alloc points of several active pools, pools are updated once. Can only be called by the
+// owner or the generator controller.
+pub fn set_alloc_points(
+    mut deps: DepsMut,
+    env: Env,
+    pools: Vec<(AssetInfo, Uint64)>,
+) -> Result<Response, ContractError> {
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let active_pools = ACTIVE_POOLS.load(deps.storage)?;
+
+    mass_update_pools(deps.branch(), env)?;
+
+    for (lp_token, alloc_point) in pools {
+        let lp_token = validate_lp_token(deps.api, lp_token)?;
+        if !active_pools.contains(&lp_token) {
+            return Err(ContractError::PoolRemoved {});
+        }
+
+        let mut pool_info = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+
+        cfg.total_alloc_point = cfg
+            .total_alloc_point
+            .checked_sub(pool_info.alloc_point)?
+            .checked_add(alloc_point)?;
+        pool_info.alloc_point = alloc_point;
+
+        POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool_info)?;
+    }
+
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new().add_attribute("action", "set_alloc_points"))
+}
+
+// The generator controller may set alloc points besides the owner
+fn is_alloc_points_setter(cfg: &Config, sender: &Addr) -> bool {
+    *sender == cfg.owner || cfg.generator_controller.as_ref() == Some(sender)
+}
+
+// Remove the pool from active pools, ASTRO rewards of the pool are frozen. Can only be called by
+// the owner.
+pub fn remove_pool(
+    mut deps: DepsMut,
+    env: Env,
+    lp_token: AssetInfo,
+) -> Result<Response, ContractError> {
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+
+    let mut active_pools = ACTIVE_POOLS.load(deps.storage)?;
+    if !active_pools.contains(&lp_token) {
+        return Err(ContractError::PoolRemoved {});
+    }
+
+    mass_update_pools(deps.branch(), env)?;
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+
+    cfg.total_alloc_point = cfg.total_alloc_point.checked_sub(pool.alloc_point)?;
+    pool.alloc_point = Uint64::zero();
+    active_pools.retain(|v| *v != lp_token);
+
+    CONFIG.save(deps.storage, &cfg)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+    ACTIVE_POOLS.save(deps.storage, &active_pools)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_pool")
+        .add_attribute("lp_token", lp_token.to_string()))
 }
 
 fn update_rewards_and_execute(
     mut deps: DepsMut,
     env: Env,
-    update_single_pool: Option<Addr>,
+    update_pools: Option<Vec<AssetInfo>>,
     on_reply: ExecuteOnReply,
 ) -> Result<Response, ContractError> {
     TMP_USER_ACTION.update(deps.storage, |v| {
@@ -299,29 +592,25 @@ fn update_rewards_and_execute(
 
     let mut messages: Vec<SubMsg> = vec![];
 
-    match update_single_pool {
-        Some(lp_token) => {
-            let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
-
-            let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
-            if let Some(reward_proxy) = pool.reward_proxy.clone() {
-                messages.append(&mut get_pool_rewards_from_proxy(
-                    deps.branch(),
-                    &lp_token,
-                    &mut pool,
-                    &reward_proxy,
-                )?);
+    match update_pools {
+        Some(lp_tokens) => {
+            for lp_token in lp_tokens {
+                let lp_token = validate_lp_token(deps.api, lp_token)?;
+
+                let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+                if let Some(reward_proxy) = pool.reward_proxy.clone() {
+                    messages.append(&mut get_pool_rewards_from_proxy(
+                        deps.branch(),
+                        &lp_token,
+                        &mut pool,
+                        &reward_proxy,
+                    )?);
+                }
             }
         }
         None => {
-            let pools: Vec<(Addr, PoolInfo)> = POOL_INFO
-                .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-                .filter_map(|v| {
-                    v.ok()
-                        .map(|v| (Addr::unchecked(String::from_utf8(v.0).unwrap()), v.1))
-                })
-                .collect();
-            for (lp_token, mut pool) in pools {
+            for lp_token in ACTIVE_POOLS.load(deps.storage)? {
+                let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
                 if let Some(reward_proxy) = pool.reward_proxy.clone() {
                     messages.append(&mut get_pool_rewards_from_proxy(
                         deps.branch(),
@@ -344,7 +633,7 @@ fn update_rewards_and_execute(
 
 fn get_pool_rewards_from_proxy(
     deps: DepsMut,
-    lp_token: &Addr,
+    lp_token: &AssetInfo,
     pool: &mut PoolInfo,
     reward_proxy: &Addr,
 ) -> Result<Vec<SubMsg>, ContractError> {
@@ -353,7 +642,7 @@ fn get_pool_rewards_from_proxy(
         .query_wasm_smart(reward_proxy, &ProxyQueryMsg::Reward {})?;
 
     pool.proxy_reward_balance_before_update = reward_amount;
-    POOL_INFO.save(deps.storage, lp_token, pool)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), pool)?;
 
     let msg = ProxyQueryMsg::PendingToken {};
     let res: Uint128 = deps.querier.query_wasm_smart(reward_proxy, &msg)?;
@@ -380,6 +669,7 @@ fn process_after_update(deps: DepsMut, env: Env) -> Result<Response, ContractErr
             TMP_USER_ACTION.save(deps.storage, &None)?;
             match action {
                 ExecuteOnReply::MassUpdatePools {} => mass_update_pools(deps, env),
+                ExecuteOnReply::RemovePool { lp_token } => remove_pool(deps, env, lp_token),
                 ExecuteOnReply::Add {
                     lp_token,
                     alloc_point,
@@ -389,130 +679,284 @@ fn process_after_update(deps: DepsMut, env: Env) -> Result<Response, ContractErr
                     lp_token,
                     alloc_point,
                 } => set(deps, env, lp_token, alloc_point),
+                ExecuteOnReply::SetAllocPoints { pools } => set_alloc_points(deps, env, pools),
                 ExecuteOnReply::UpdatePool { lp_token } => update_pool(deps, env, lp_token),
                 ExecuteOnReply::Deposit {
                     lp_token,
                     account,
                     amount,
-                } => deposit(deps, env, lp_token, account, amount),
+                    lock_duration,
+                } => deposit(deps, env, lp_token, account, amount, lock_duration),
                 ExecuteOnReply::Withdraw {
                     lp_token,
                     account,
                     amount,
                 } => withdraw(deps, env, lp_token, account, amount),
-                ExecuteOnReply::SetTokensPerBlock { amount } => {
-                    set_tokens_per_block(deps, env, amount)
+                ExecuteOnReply::ClaimRewards {
+                    lp_tokens,
+                    account,
+                    recipient,
+                } => claim_rewards(deps, env, lp_tokens, account, recipient),
+                ExecuteOnReply::CheckpointUserBoost { lp_tokens, user } => {
+                    checkpoint_user_boost(deps, env, lp_tokens, user)
                 }
+                ExecuteOnReply::SetEmissionSchedule {
+                    emission_schedule,
+                    end_time,
+                } => set_emission_schedule(deps, env, emission_schedule, end_time),
+                ExecuteOnReply::MigrateProxy {
+                    lp_token,
+                    new_proxy,
+                } => migrate_proxy(deps, env, lp_token, new_proxy),
+                ExecuteOnReply::MoveProxyRewards {
+                    lp_token,
+                    new_proxy,
+                    lp_supply,
+                } => move_proxy_rewards(deps, lp_token, new_proxy, lp_supply),
+                ExecuteOnReply::CancelRewardSchedules {
+                    lp_token,
+                    reward_info,
+                    recipient,
+                    remove_reward,
+                } => cancel_reward_schedules(
+                    deps,
+                    env,
+                    lp_token,
+                    reward_info,
+                    recipient,
+                    remove_reward,
+                ),
             }
         }
         None => Ok(Response::default()),
     }
 }
 
-fn set_tokens_per_block(
+fn set_emission_schedule(
     mut deps: DepsMut,
     env: Env,
-    amount: Uint128,
+    emission_schedule: Vec<EmissionPeriod>,
+    end_time: Option<Uint64>,
 ) -> Result<Response, ContractError> {
     mass_update_pools(deps.branch(), env)?;
     CONFIG.update::<_, ContractError>(deps.storage, |mut v| {
-        v.tokens_per_block = amount;
+        v.emission_schedule = emission_schedule;
+        v.end_time = end_time;
         Ok(v)
     })?;
-    Ok(Response::new().add_attribute("action", "set_tokens_per_block"))
+    Ok(Response::new().add_attribute("action", "set_emission_schedule"))
+}
+
+// Periods must be sorted by start time and the end must be after the start of the last period.
+// The last period can last forever only if it doesn't emit ASTRO.
+fn validate_emission_schedule(
+    emission_schedule: &[EmissionPeriod],
+    end_time: Option<Uint64>,
+) -> Result<(), ContractError> {
+    let last_period = match emission_schedule.last() {
+        Some(period) => period,
+        None => return Err(ContractError::InvalidEmissionSchedule {}),
+    };
+
+    if emission_schedule
+        .windows(2)
+        .any(|v| v[0].start_time >= v[1].start_time)
+        || matches!(end_time, Some(v) if v <= last_period.start_time)
+    {
+        return Err(ContractError::InvalidEmissionSchedule {});
+    }
+
+    if end_time.is_none() && !last_period.tokens_per_second.is_zero() {
+        return Err(ContractError::EmissionNotBounded {});
+    }
+
+    Ok(())
+}
+
+// ASTRO left in the vesting must cover the emission from now on and ASTRO which is distributed
+// to the users but not claimed yet.
+fn assert_emission_covered(
+    deps: Deps,
+    env: &Env,
+    cfg: &Config,
+    emission_schedule: &[EmissionPeriod],
+    end_time: Option<Uint64>,
+    unclaimed: Uint128,
+) -> Result<(), ContractError> {
+    // ASTRO isn't emitted after the start of the last period if there is no end
+    let emission = calculate_emission(
+        emission_schedule,
+        end_time,
+        env.block.time.seconds(),
+        end_time
+            .or_else(|| emission_schedule.last().map(|v| v.start_time))
+            .unwrap_or_default()
+            .u64(),
+    )?;
+
+    if emission.checked_add(unclaimed)?
+        > query_vesting_left(&deps.querier, cfg, &env.contract.address)?
+    {
+        return Err(ContractError::EmissionExceedsVesting {});
+    }
+
+    Ok(())
 }
 
-// Update reward variables for all pools.
+// Returns ASTRO which is accrued to the users of all pools, removed ones included, and ASTRO
+// emitted to the active pools since their last update. Pool rewards which aren't accrued to anyone
+// while the working supply is zero stay in the vesting, rounding dust of the payouts is counted.
+fn unclaimed_astro(deps: Deps, env: &Env, cfg: &Config) -> StdResult<Uint128> {
+    POOL_INFO
+        .range(deps.storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |acc, v| {
+            let (_, pool) = v?;
+            let mut unclaimed = pool
+                .acc_per_share
+                .checked_mul(pool.working_supply)?
+                .saturating_sub(pool.reward_debt_supply);
+            if !pool.working_supply.is_zero() {
+                unclaimed = unclaimed.checked_add(calculate_rewards(env, &pool, cfg)?)?;
+            }
+            Ok(acc.checked_add(unclaimed)?)
+        })
+}
+
+// Returns ASTRO which is not released from the vesting account of the generator yet.
+fn query_vesting_left(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    generator: &Addr,
+) -> StdResult<Uint128> {
+    let res: VestingAccountResponse = querier.query_wasm_smart(
+        &cfg.vesting_contract,
+        &VestingQueryMsg::VestingAccount {
+            address: generator.clone(),
+        },
+    )?;
+
+    let total = res
+        .info
+        .schedules
+        .iter()
+        .try_fold(Uint128::zero(), |acc, v| {
+            acc.checked_add(v.end_point.as_ref().unwrap_or(&v.start_point).amount)
+        })?;
+
+    Ok(total.saturating_sub(res.info.released_amount))
+}
+
+// Update reward variables for all active pools.
 pub fn mass_update_pools(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
     let response = Response::default();
 
     let cfg = CONFIG.load(deps.storage)?;
-    let pools: Vec<(Addr, PoolInfo)> = POOL_INFO
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|v| {
-            v.ok()
-                .map(|v| (Addr::unchecked(String::from_utf8(v.0).unwrap()), v.1))
-        })
-        .collect();
+    let pools = ACTIVE_POOLS.load(deps.storage)?;
 
     if pools.is_empty() {
         return Ok(response);
     }
-    for (lp_token, mut pool) in pools {
-        update_pool_rewards(deps.branch(), &env, &lp_token, &mut pool, &cfg, None)?;
-        POOL_INFO.save(deps.storage, &lp_token, &pool)?;
+    for lp_token in pools {
+        let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+        update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
+        POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
     }
     Ok(response.add_attribute("action", "mass_update_pools"))
 }
 
 // Update reward variables of the given pool to be up-to-date.
-pub fn update_pool(mut deps: DepsMut, env: Env, lp_token: Addr) -> Result<Response, ContractError> {
+pub fn update_pool(
+    mut deps: DepsMut,
+    env: Env,
+    lp_token: AssetInfo,
+) -> Result<Response, ContractError> {
     let response = Response::default();
 
     let cfg = CONFIG.load(deps.storage)?;
-    let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
 
-    update_pool_rewards(deps.branch(), &env, &lp_token, &mut pool, &cfg, None)?;
+    update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
 
-    POOL_INFO.save(deps.storage, &lp_token, &pool)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
 
     Ok(response.add_attribute("action", "update_pool"))
 }
 
-// Update reward variables of the given pool to be up-to-date.
+// Update reward variables of the given pool to be up-to-date. Returns LP tokens deposited to the
+// pool.
 pub fn update_pool_rewards(
     deps: DepsMut,
     env: &Env,
-    lp_token: &Addr,
     pool: &mut PoolInfo,
     cfg: &Config,
-    deposited: Option<Uint128>,
-) -> StdResult<()> {
-    let lp_supply: Uint128;
+) -> StdResult<Uint128> {
+    let lp_supply = pool.deposited;
 
-    match &pool.reward_proxy {
-        Some(proxy) => {
-            lp_supply = deps
+    if let Some(proxy) = &pool.reward_proxy {
+        if !lp_supply.is_zero() {
+            let reward_amount: Uint128 = deps
                 .querier
-                .query_wasm_smart(proxy, &ProxyQueryMsg::Deposit {})?;
+                .query_wasm_smart(proxy, &ProxyQueryMsg::Reward {})?;
 
-            if !lp_supply.is_zero() {
-                let reward_amount: Uint128 = deps
-                    .querier
-                    .query_wasm_smart(proxy, &ProxyQueryMsg::Reward {})?;
-
-                let token_rewards =
-                    reward_amount.checked_sub(pool.proxy_reward_balance_before_update)?;
-
-                let share = Decimal::from_ratio(token_rewards, lp_supply);
-                pool.acc_per_share_on_proxy = pool.acc_per_share_on_proxy.checked_add(share)?;
-            }
-        }
-        None => {
-            let res: BalanceResponse = deps.querier.query_wasm_smart(
-                lp_token,
-                &cw20::Cw20QueryMsg::Balance {
-                    address: env.contract.address.to_string(),
-                },
-            )?;
+            let token_rewards =
+                reward_amount.checked_sub(pool.proxy_reward_balance_before_update)?;
 
-            if let Some(amount) = deposited {
-                lp_supply = res.balance.checked_sub(amount)?;
-            } else {
-                lp_supply = res.balance;
-            }
+            let share = Decimal::from_ratio(token_rewards, lp_supply);
+            pool.acc_per_share_on_proxy = pool.acc_per_share_on_proxy.checked_add(share)?;
         }
-    };
+    }
 
-    if env.block.height > pool.last_reward_block.u64() {
-        if !lp_supply.is_zero() {
+    if env.block.time.seconds() > pool.last_reward_time.u64() {
+        if !pool.working_supply.is_zero() {
             let token_rewards = calculate_rewards(env, pool, cfg)?;
 
-            let share = Decimal::from_ratio(token_rewards, lp_supply);
+            let share = Decimal::from_ratio(token_rewards, pool.working_supply);
             pool.acc_per_share = pool.acc_per_share.checked_add(share)?;
         }
 
-        pool.last_reward_block = Uint64::from(env.block.height);
+        pool.last_reward_time = Uint64::from(env.block.time.seconds());
+    }
+
+    update_external_rewards(env, pool, lp_supply)?;
+
+    Ok(lp_supply)
+}
+
+// Update external reward variables of the pool. Rewards are not distributed while the pool is
+// empty, so they are received by the next depositors.
+pub fn update_external_rewards(
+    env: &Env,
+    pool: &mut PoolInfo,
+    lp_supply: Uint128,
+) -> StdResult<()> {
+    if lp_supply.is_zero() {
+        return Ok(());
+    }
+
+    let now = env.block.time.seconds();
+
+    for reward in pool.external_rewards.iter_mut() {
+        let last_reward_time = reward.last_reward_time.u64();
+        if now <= last_reward_time {
+            continue;
+        }
+
+        let mut token_rewards = Uint128::zero();
+        for schedule in &reward.schedules {
+            let from = schedule.start_time.u64().max(last_reward_time);
+            let to = schedule.end_time.u64().min(now);
+            if to > from {
+                token_rewards = token_rewards.checked_add(schedule.amount.multiply_ratio(
+                    to - from,
+                    schedule.end_time.u64() - schedule.start_time.u64(),
+                ))?;
+            }
+        }
+
+        let share = Decimal::from_ratio(token_rewards, lp_supply);
+        reward.acc_per_share = reward.acc_per_share.checked_add(share)?;
+        reward.last_reward_time = Uint64::from(now);
+        reward.schedules.retain(|v| v.end_time.u64() > now);
     }
 
     Ok(())
@@ -525,122 +969,180 @@ fn receive_cw20(
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
     let amount = cw20_msg.amount;
-    let lp_token = info.sender;
+    let sender = Addr::unchecked(cw20_msg.sender);
+
+    let (account, lock_duration) = match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Deposit {} => (sender, None),
+        Cw20HookMsg::DepositFor(beneficiary) => (beneficiary, None),
+        Cw20HookMsg::DepositLocked { lock_duration } => (sender, Some(lock_duration)),
+        Cw20HookMsg::DepositLockedFor {
+            beneficiary,
+            lock_duration,
+        } => (beneficiary, Some(lock_duration)),
+        Cw20HookMsg::AddRewardSchedule {
+            lp_token,
+            start_time,
+            end_time,
+        } => {
+            let reward = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender,
+                },
+                amount,
+            };
+
+            return add_reward_schedule(deps, env, lp_token, reward, start_time, end_time);
+        }
+    };
 
-    if POOL_INFO.load(deps.storage, &lp_token).is_err() {
+    let lp_token = AssetInfo::Token {
+        contract_addr: info.sender,
+    };
+
+    if POOL_INFO.load(deps.storage, lp_token.as_bytes()).is_err() {
         return Err(ContractError::Unauthorized {});
     }
 
-    match from_binary(&cw20_msg.msg)? {
-        Cw20HookMsg::Deposit {} => update_rewards_and_execute(
-            deps,
-            env,
-            Some(lp_token.clone()),
-            ExecuteOnReply::Deposit {
-                lp_token,
-                account: Addr::unchecked(cw20_msg.sender),
-                amount,
-            },
-        ),
-        Cw20HookMsg::DepositFor(beneficiary) => update_rewards_and_execute(
-            deps,
-            env,
-            Some(lp_token.clone()),
-            ExecuteOnReply::Deposit {
-                lp_token,
-                account: beneficiary,
-                amount,
-            },
-        ),
+    if !ACTIVE_POOLS.load(deps.storage)?.contains(&lp_token) {
+        return Err(ContractError::PoolRemoved {});
+    }
+
+    update_rewards_and_execute(
+        deps,
+        env,
+        Some(vec![lp_token.clone()]),
+        ExecuteOnReply::Deposit {
+            lp_token,
+            account,
+            amount,
+            lock_duration,
+        },
+    )
+}
+
+// Deposit native LP tokens sent with the message.
+fn receive_native(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    beneficiary: Option<String>,
+    lock_duration: Option<LockDuration>,
+) -> Result<Response, ContractError> {
+    let (denom, amount) = match info.funds.as_slice() {
+        [coin] if !coin.amount.is_zero() => (coin.denom.clone(), coin.amount),
+        _ => return Err(ContractError::InvalidFunds {}),
+    };
+    let lp_token = AssetInfo::NativeToken { denom };
+
+    if POOL_INFO.load(deps.storage, lp_token.as_bytes()).is_err() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !ACTIVE_POOLS.load(deps.storage)?.contains(&lp_token) {
+        return Err(ContractError::PoolRemoved {});
     }
+
+    let beneficiary = match beneficiary {
+        Some(beneficiary) => addr_validate_to_lower(deps.api, &beneficiary)?,
+        None => info.sender,
+    };
+
+    update_rewards_and_execute(
+        deps,
+        env,
+        Some(vec![lp_token.clone()]),
+        ExecuteOnReply::Deposit {
+            lp_token,
+            account: beneficiary,
+            amount,
+            lock_duration,
+        },
+    )
 }
 
-// Deposit LP tokens to MasterChef for ASTRO allocation.
+// Deposit LP tokens to MasterChef for ASTRO allocation. Locked LP tokens get a multiplier of
+// ASTRO rewards.
 pub fn deposit(
     mut deps: DepsMut,
     env: Env,
-    lp_token: Addr,
+    lp_token: AssetInfo,
     beneficiary: Addr,
     amount: Uint128,
+    lock_duration: Option<LockDuration>,
 ) -> Result<Response, ContractError> {
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
     let beneficiary = addr_validate_to_lower(deps.api, beneficiary.as_str())?;
 
     let mut response = Response::new().add_attribute("Action", "Deposit");
 
     let mut user = USER_INFO
-        .load(deps.storage, (&lp_token, &beneficiary))
+        .load(deps.storage, (lp_token.as_bytes(), &beneficiary))
         .unwrap_or_default();
 
     let cfg = CONFIG.load(deps.storage)?;
-    let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
 
-    update_pool_rewards(
-        deps.branch(),
-        &env,
-        &lp_token,
-        &mut pool,
-        &cfg,
-        Some(amount),
-    )?;
+    update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
 
     if !user.amount.is_zero() {
-        let pending = pool
-            .acc_per_share
-            .checked_mul(user.amount)?
-            .checked_sub(user.reward_debt)?;
-        if !pending.is_zero() {
+        response.messages.append(&mut send_pending_rewards(
+            &deps.querier,
+            &cfg,
+            &pool,
+            &user,
+            &beneficiary,
+        )?);
+    }
+
+    //call transfer function for lp token from: info.sender to: env.contract.address amount:_amount
+    if let (Some(proxy), AssetInfo::Token { contract_addr }) = (&pool.reward_proxy, &lp_token) {
+        if !amount.is_zero() {
             response.messages.push(SubMsg::new(WasmMsg::Execute {
-                contract_addr: cfg.vesting_contract.to_string(),
-                msg: to_binary(&VestingExecuteMsg::Claim {
-                    recipient: Some(beneficiary.to_string()),
-                    amount: Some(pending),
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: proxy.to_string(),
+                    msg: to_binary(&ProxyCw20HookMsg::Deposit {})?,
+                    amount,
                 })?,
                 funds: vec![],
             }));
         }
-        if let Some(proxy) = &pool.reward_proxy {
-            let pending_on_proxy = pool
-                .acc_per_share_on_proxy
-                .checked_mul(user.amount)?
-                .checked_sub(user.reward_debt_proxy)?;
-            if !pending_on_proxy.is_zero() {
-                response.messages.push(SubMsg::new(WasmMsg::Execute {
-                    contract_addr: proxy.to_string(),
-                    funds: vec![],
-                    msg: to_binary(&ProxyExecuteMsg::SendRewards {
-                        account: beneficiary.clone(),
-                        amount: pending_on_proxy,
-                    })?,
-                }));
-            }
-        }
-    }
-
-    //call transfer function for lp token from: info.sender to: env.contract.address amount:_amount
-    if !amount.is_zero() && pool.reward_proxy.is_some() {
-        response.messages.push(SubMsg::new(WasmMsg::Execute {
-            contract_addr: lp_token.to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Send {
-                contract: pool.reward_proxy.clone().unwrap().to_string(),
-                msg: to_binary(&ProxyCw20HookMsg::Deposit {})?,
-                amount,
-            })?,
-            funds: vec![],
-        }));
     }
 
     //Change user balance
     user.amount = user.amount.checked_add(amount)?;
-    if !pool.acc_per_share.is_zero() {
-        user.reward_debt = pool.acc_per_share.checked_mul(user.amount)?;
-    };
+    pool.deposited = pool.deposited.checked_add(amount)?;
+    release_expired_positions(deps.storage, &env, &lp_token, &beneficiary, &mut user)?;
+    if let Some(lock_duration) = lock_duration {
+        let lock_id = LAST_LOCK_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+        LAST_LOCK_ID.save(deps.storage, &lock_id)?;
+
+        let position = LockedPosition {
+            amount,
+            lock_duration,
+            unlock_time: Uint64::from(env.block.time.seconds() + lock_duration.seconds()),
+        };
+        LOCKED_POSITIONS.save(
+            deps.storage,
+            (lp_token.as_bytes(), &beneficiary, U64Key::new(lock_id)),
+            &position,
+        )?;
+
+        user.locked_amount = user.locked_amount.checked_add(amount)?;
+        user.lockup_bonus = user.lockup_bonus.checked_add(lockup_bonus(&position))?;
+
+        response = response.add_attribute("unlock_time", position.unlock_time);
+    }
+    update_working_amount(&deps.querier, &cfg, &mut pool, &mut user, &beneficiary)?;
+    update_reward_debt(&mut pool, &mut user)?;
     if !pool.acc_per_share_on_proxy.is_zero() {
         user.reward_debt_proxy = pool.acc_per_share_on_proxy.checked_mul(user.amount)?;
     };
+    user.reward_debt_external = external_reward_debts(&pool, user.amount)?;
 
-    POOL_INFO.save(deps.storage, &lp_token, &pool)?;
-    USER_INFO.save(deps.storage, (&lp_token, &beneficiary), &user)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+    USER_INFO.save(deps.storage, (lp_token.as_bytes(), &beneficiary), &user)?;
+    USER_POSITIONS.save(deps.storage, (&beneficiary, lp_token.as_bytes()), &lp_token)?;
 
     Ok(response
         .add_attribute("action", "deposit")
@@ -651,33 +1153,314 @@ pub fn deposit(
 pub fn withdraw(
     mut deps: DepsMut,
     env: Env,
-    lp_token: Addr,
+    lp_token: AssetInfo,
     account: Addr,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let mut response = Response::new().add_attribute("Action", "Withdraw");
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
     let mut user = USER_INFO
-        .load(deps.storage, (&lp_token, &account))
+        .load(deps.storage, (lp_token.as_bytes(), &account))
         .unwrap_or_default();
     if user.amount < amount {
         return Err(ContractError::BalanceTooSmall {});
     }
+    release_expired_positions(deps.storage, &env, &lp_token, &account, &mut user)?;
+    if user.amount.checked_sub(user.locked_amount)? < amount {
+        return Err(ContractError::PositionLocked {});
+    }
     let cfg = CONFIG.load(deps.storage)?;
-    let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
-    update_pool_rewards(deps.branch(), &env, &lp_token, &mut pool, &cfg, None)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
+
+    response.messages.append(&mut send_pending_rewards(
+        &deps.querier,
+        &cfg,
+        &pool,
+        &user,
+        &account,
+    )?);
+
+    // call to transfer function for lp token
+    if !amount.is_zero() {
+        match &pool.reward_proxy {
+            Some(proxy) => {
+                response.messages.push(SubMsg::new(WasmMsg::Execute {
+                    contract_addr: proxy.to_string(),
+                    funds: vec![],
+                    msg: to_binary(&ProxyExecuteMsg::Withdraw {
+                        account: account.clone(),
+                        amount,
+                    })?,
+                }));
+            }
+            None => {
+                response
+                    .messages
+                    .push(SubMsg::new(transfer_lp_msg(&lp_token, amount, &account)?));
+            }
+        };
+    }
+
+    // Update user balance
+    user.amount = user.amount.checked_sub(amount)?;
+    pool.deposited = pool.deposited.checked_sub(amount)?;
+    update_working_amount(&deps.querier, &cfg, &mut pool, &mut user, &account)?;
+    update_reward_debt(&mut pool, &mut user)?;
+    if !pool.acc_per_share_on_proxy.is_zero() {
+        user.reward_debt_proxy = pool.acc_per_share_on_proxy.checked_mul(user.amount)?;
+    }
+    user.reward_debt_external = external_reward_debts(&pool, user.amount)?;
+
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+    if !user.amount.is_zero() {
+        USER_INFO.save(deps.storage, (lp_token.as_bytes(), &account), &user)?;
+    } else {
+        USER_INFO.remove(deps.storage, (lp_token.as_bytes(), &account));
+        USER_POSITIONS.remove(deps.storage, (&account, lp_token.as_bytes()));
+    }
+
+    Ok(response
+        .add_attribute("action", "withdraw")
+        .add_attribute("amount", amount))
+}
+
+// Claim pending rewards of the account in the given pools.
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    lp_tokens: Vec<AssetInfo>,
+    account: Addr,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let messages = claim_pending_rewards(deps, env, lp_tokens, &account, &recipient)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("recipient", recipient))
+}
+
+// Recalculate working amounts of the user in the given pools, pending rewards are sent to the
+// user.
+pub fn checkpoint_user_boost(
+    deps: DepsMut,
+    env: Env,
+    lp_tokens: Vec<AssetInfo>,
+    user: Addr,
+) -> Result<Response, ContractError> {
+    let messages = claim_pending_rewards(deps, env, lp_tokens, &user, &user)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "checkpoint_user_boost")
+        .add_attribute("user", user))
+}
+
+fn claim_pending_rewards(
+    mut deps: DepsMut,
+    env: Env,
+    lp_tokens: Vec<AssetInfo>,
+    account: &Addr,
+    recipient: &Addr,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let mut messages = vec![];
+
+    let cfg = CONFIG.load(deps.storage)?;
+
+    for lp_token in lp_tokens {
+        let mut user = USER_INFO.load(deps.storage, (lp_token.as_bytes(), account))?;
+        let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+
+        update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
+
+        messages.append(&mut send_pending_rewards(
+            &deps.querier,
+            &cfg,
+            &pool,
+            &user,
+            recipient,
+        )?);
+
+        release_expired_positions(deps.storage, &env, &lp_token, account, &mut user)?;
+
+        update_working_amount(&deps.querier, &cfg, &mut pool, &mut user, account)?;
+        update_reward_debt(&mut pool, &mut user)?;
+        user.reward_debt_proxy = pool.acc_per_share_on_proxy.checked_mul(user.amount)?;
+        user.reward_debt_external = external_reward_debts(&pool, user.amount)?;
+
+        POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+        USER_INFO.save(deps.storage, (lp_token.as_bytes(), account), &user)?;
+    }
+
+    Ok(messages)
+}
+
+// Release expired locked positions of the user, their LP tokens can be withdrawn and don't get
+// the multiplier anymore.
+fn release_expired_positions(
+    storage: &mut dyn Storage,
+    env: &Env,
+    lp_token: &AssetInfo,
+    account: &Addr,
+    user: &mut UserInfo,
+) -> StdResult<()> {
+    if user.locked_amount.is_zero() {
+        return Ok(());
+    }
+
+    let positions: Vec<(Vec<u8>, LockedPosition)> = LOCKED_POSITIONS
+        .prefix((lp_token.as_bytes(), account))
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (lock_id, position) in positions {
+        if position.unlock_time.u64() > env.block.time.seconds() {
+            continue;
+        }
+
+        user.locked_amount = user.locked_amount.checked_sub(position.amount)?;
+        user.lockup_bonus = user.lockup_bonus.checked_sub(lockup_bonus(&position))?;
+        LOCKED_POSITIONS.remove(
+            storage,
+            (lp_token.as_bytes(), account, U64Key::from(lock_id)),
+        );
+    }
+
+    Ok(())
+}
+
+// Extra LP amount of the locked position by its multiplier.
+fn lockup_bonus(position: &LockedPosition) -> Uint128 {
+    position
+        .amount
+        .multiply_ratio(position.lock_duration.multiplier() - 100, 100u64)
+}
+
+// Set the ASTRO reward debt of the user by the working amount, the sum of reward debts of the pool
+// is updated with it.
+pub fn update_reward_debt(pool: &mut PoolInfo, user: &mut UserInfo) -> StdResult<()> {
+    let reward_debt = pool.acc_per_share.checked_mul(user.working_amount)?;
+
+    pool.reward_debt_supply = pool
+        .reward_debt_supply
+        .checked_sub(user.reward_debt)?
+        .checked_add(reward_debt)?;
+    user.reward_debt = reward_debt;
+
+    Ok(())
+}
+
+// Update the working amount of the user and the working supply of the pool.
+fn update_working_amount(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    pool: &mut PoolInfo,
+    user: &mut UserInfo,
+    account: &Addr,
+) -> StdResult<()> {
+    let working_amount = calculate_working_amount(
+        querier,
+        cfg,
+        account,
+        user.amount.checked_add(user.lockup_bonus)?,
+        pool.deposited,
+    )?;
+
+    pool.working_supply = pool
+        .working_supply
+        .checked_sub(user.working_amount)?
+        .checked_add(working_amount)?;
+    user.working_amount = working_amount;
+
+    Ok(())
+}
+
+/// Calculates the LP amount boosted by the share of xASTRO locked by the account in the gauge
+/// controller: min(0.4 * amount + 0.6 * lp_supply * voting_power / total_voting_power, amount),
+/// where the amount includes the lockup bonus. xASTRO which isn't locked doesn't boost rewards,
+/// so it can't be transferred between accounts to boost them in turn
+pub fn calculate_working_amount(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    account: &Addr,
+    amount: Uint128,
+    lp_supply: Uint128,
+) -> StdResult<Uint128> {
+    let gauge_controller = match &cfg.gauge_controller {
+        Some(gauge_controller) => gauge_controller,
+        None => return Ok(amount),
+    };
+
+    let mut working_amount = amount.multiply_ratio(TOKENLESS_PRODUCTION, 100u64);
+
+    let voting_power: VotingPowerResponse = querier.query_wasm_smart(
+        gauge_controller,
+        &GaugeQueryMsg::VotingPower {
+            user: account.to_string(),
+        },
+    )?;
+    if !voting_power.total_voting_power.is_zero() {
+        working_amount = working_amount.checked_add(
+            lp_supply
+                .multiply_ratio(voting_power.voting_power, voting_power.total_voting_power)
+                .multiply_ratio(100 - TOKENLESS_PRODUCTION, 100u64),
+        )?;
+    }
+
+    Ok(working_amount.min(amount))
+}
+
+// Validate an LP token, addresses of CW20 LP tokens are lowercased.
+fn validate_lp_token(api: &dyn Api, lp_token: AssetInfo) -> StdResult<AssetInfo> {
+    lp_token.check(api)?;
+
+    Ok(match lp_token {
+        AssetInfo::Token { contract_addr } => AssetInfo::Token {
+            contract_addr: addr_validate_to_lower(api, contract_addr.as_str())?,
+        },
+        AssetInfo::NativeToken { denom } => AssetInfo::NativeToken { denom },
+    })
+}
+
+// Validate LP tokens, every pool can be given once.
+fn validate_lp_tokens(
+    deps: Deps,
+    lp_tokens: Vec<AssetInfo>,
+) -> Result<Vec<AssetInfo>, ContractError> {
+    let mut pools: Vec<AssetInfo> = vec![];
+    for lp_token in lp_tokens {
+        let lp_token = validate_lp_token(deps.api, lp_token)?;
+        if pools.contains(&lp_token) {
+            return Err(ContractError::PoolDuplicate {});
+        }
+        pools.push(lp_token);
+    }
+
+    Ok(pools)
+}
+
+// Create messages sending pending ASTRO and proxy rewards of the user to the recipient.
+fn send_pending_rewards(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    pool: &PoolInfo,
+    user: &UserInfo,
+    to: &Addr,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let mut messages = vec![];
 
     let pending = pool
         .acc_per_share
-        .checked_mul(user.amount)?
+        .checked_mul(user.working_amount)?
         .checked_sub(user.reward_debt)?;
     if !pending.is_zero() {
-        response.messages.push(SubMsg::new(WasmMsg::Execute {
+        messages.push(SubMsg::new(WasmMsg::Execute {
             contract_addr: cfg.vesting_contract.to_string(),
             msg: to_binary(&VestingExecuteMsg::Claim {
-                recipient: Some(account.to_string()),
+                recipient: Some(to.to_string()),
                 amount: Some(pending),
             })?,
             funds: vec![],
@@ -690,77 +1473,253 @@ pub fn withdraw(
             .checked_mul(user.amount)?
             .checked_sub(user.reward_debt_proxy)?;
         if !pending_on_proxy.is_zero() {
-            response.messages.push(SubMsg::new(WasmMsg::Execute {
+            messages.push(SubMsg::new(WasmMsg::Execute {
                 contract_addr: proxy.to_string(),
                 funds: vec![],
                 msg: to_binary(&ProxyExecuteMsg::SendRewards {
-                    account: account.clone(),
+                    account: to.clone(),
                     amount: pending_on_proxy,
                 })?,
             }));
         }
     }
 
-    // call to transfer function for lp token
-    if !amount.is_zero() {
-        match &pool.reward_proxy {
-            Some(proxy) => {
-                response.messages.push(SubMsg::new(WasmMsg::Execute {
-                    contract_addr: proxy.to_string(),
-                    funds: vec![],
-                    msg: to_binary(&ProxyExecuteMsg::Withdraw {
-                        account: account.clone(),
-                        amount,
-                    })?,
-                }));
+    for reward in pending_external_rewards(pool, user)? {
+        if !reward.amount.is_zero() {
+            messages.push(SubMsg::new(reward.into_msg(querier, to.clone())?));
+        }
+    }
+
+    Ok(messages)
+}
+
+// Calculate pending external rewards of the user.
+fn pending_external_rewards(pool: &PoolInfo, user: &UserInfo) -> StdResult<Vec<Asset>> {
+    pool.external_rewards
+        .iter()
+        .map(|reward| {
+            let reward_debt = user
+                .reward_debt_external
+                .iter()
+                .find(|(id, _)| *id == reward.id)
+                .map(|(_, debt)| *debt)
+                .unwrap_or_default();
+
+            Ok(Asset {
+                info: reward.info.clone(),
+                amount: reward
+                    .acc_per_share
+                    .checked_mul(user.amount)?
+                    .checked_sub(reward_debt)?,
+            })
+        })
+        .collect()
+}
+
+fn external_reward_debts(pool: &PoolInfo, amount: Uint128) -> StdResult<Vec<(u64, Uint128)>> {
+    pool.external_rewards
+        .iter()
+        .map(|reward| Ok((reward.id, reward.acc_per_share.checked_mul(amount)?)))
+        .collect()
+}
+
+// Add a schedule of the external reward funded by the sender to the pool. Anyone can execute it.
+pub fn execute_add_reward_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lp_token: AssetInfo,
+    reward: Asset,
+    start_time: Uint64,
+    end_time: Uint64,
+) -> Result<Response, ContractError> {
+    reward.info.check(deps.api)?;
+
+    let mut messages: Vec<SubMsg> = vec![];
+
+    match &reward.info {
+        AssetInfo::Token { contract_addr } => {
+            messages.push(SubMsg::new(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: reward.amount,
+                })?,
+                funds: vec![],
+            }));
+        }
+        AssetInfo::NativeToken { .. } => reward.assert_sent_native_token_balance(&info)?,
+    }
+
+    Ok(
+        add_reward_schedule(deps, env, lp_token, reward, start_time, end_time)?
+            .add_submessages(messages),
+    )
+}
+
+// Add a schedule of the already received allowed external reward to the active pool.
+fn add_reward_schedule(
+    deps: DepsMut,
+    env: Env,
+    lp_token: AssetInfo,
+    reward: Asset,
+    start_time: Uint64,
+    end_time: Uint64,
+) -> Result<Response, ContractError> {
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    if !ACTIVE_POOLS.load(deps.storage)?.contains(&lp_token) {
+        return Err(ContractError::PoolRemoved {});
+    }
+
+    if reward.info.equal(&lp_token) {
+        return Err(ContractError::InvalidRewardToken {});
+    }
+
+    // Rewards are sent on every deposit, withdrawal and claim, so tokens are checked by the owner
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg
+        .allowed_external_rewards
+        .iter()
+        .any(|v| v.equal(&reward.info))
+    {
+        return Err(ContractError::ExternalRewardNotAllowed {});
+    }
+
+    if reward.amount.is_zero()
+        || start_time.u64() < env.block.time.seconds()
+        || end_time <= start_time
+    {
+        return Err(ContractError::InvalidRewardSchedule {});
+    }
+
+    let schedule = RewardSchedule {
+        amount: reward.amount,
+        start_time,
+        end_time,
+    };
+
+    match pool
+        .external_rewards
+        .iter_mut()
+        .find(|v| v.info.equal(&reward.info))
+    {
+        Some(external_reward) => {
+            if external_reward.schedules.len() >= MAX_REWARD_SCHEDULES {
+                return Err(ContractError::TooManyRewardSchedules {});
             }
-            None => {
-                response.messages.push(SubMsg::new(WasmMsg::Execute {
-                    contract_addr: lp_token.to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                        recipient: account.to_string(),
-                        amount,
-                    })?,
-                    funds: vec![],
-                }));
+            external_reward.schedules.push(schedule)
+        }
+        None => {
+            if pool.external_rewards.len() >= MAX_EXTERNAL_REWARDS {
+                return Err(ContractError::TooManyRewardSchedules {});
             }
-        };
+
+            let id = LAST_REWARD_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+            LAST_REWARD_ID.save(deps.storage, &id)?;
+
+            pool.external_rewards.push(ExternalReward {
+                id,
+                info: reward.info.clone(),
+                schedules: vec![schedule],
+                acc_per_share: Decimal::zero(),
+                last_reward_time: Uint64::from(env.block.time.seconds()),
+            })
+        }
     }
 
-    // Update user balance
-    user.amount = user.amount.checked_sub(amount)?;
-    if !pool.acc_per_share.is_zero() {
-        user.reward_debt = pool.acc_per_share.checked_mul(user.amount)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_reward_schedule")
+        .add_attribute("lp_token", lp_token.to_string())
+        .add_attribute("reward", reward.to_string())
+        .add_attribute("start_time", start_time)
+        .add_attribute("end_time", end_time))
+}
+
+// Cancel the schedules of the external reward and send the amount which is not distributed yet
+// to the recipient if it is set. The removed reward can't be claimed anymore. Can only be called
+// by the owner.
+fn cancel_reward_schedules(
+    mut deps: DepsMut,
+    env: Env,
+    lp_token: AssetInfo,
+    reward_info: AssetInfo,
+    recipient: Option<Addr>,
+    remove_reward: bool,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+
+    update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
+
+    let position = pool
+        .external_rewards
+        .iter()
+        .position(|v| v.info.equal(&reward_info))
+        .ok_or(ContractError::ExternalRewardNotFound {})?;
+
+    // rewards are distributed up to the last reward time, it stays behind while the pool is empty
+    let reward = &mut pool.external_rewards[position];
+    let last_reward_time = reward.last_reward_time.u64();
+    let mut amount = Uint128::zero();
+    for schedule in &reward.schedules {
+        let from = schedule.start_time.u64().max(last_reward_time);
+        if schedule.end_time.u64() > from {
+            amount = amount.checked_add(schedule.amount.multiply_ratio(
+                schedule.end_time.u64() - from,
+                schedule.end_time.u64() - schedule.start_time.u64(),
+            ))?;
+        }
     }
-    if !pool.acc_per_share_on_proxy.is_zero() {
-        user.reward_debt_proxy = pool.acc_per_share_on_proxy.checked_mul(user.amount)?;
+    reward.schedules.clear();
+
+    let refund = Asset {
+        info: reward.info.clone(),
+        amount,
+    };
+
+    if remove_reward {
+        pool.external_rewards.remove(position);
     }
 
-    POOL_INFO.save(deps.storage, &lp_token, &pool)?;
-    if !user.amount.is_zero() {
-        USER_INFO.save(deps.storage, (&lp_token, &account), &user)?;
-    } else {
-        USER_INFO.remove(deps.storage, (&lp_token, &account));
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "cancel_reward_schedules")
+        .add_attribute("lp_token", lp_token.to_string());
+
+    if let Some(recipient) = recipient {
+        if !refund.amount.is_zero() {
+            response =
+                response.add_message(refund.clone().into_msg(&deps.querier, recipient.clone())?);
+        }
+        response = response
+            .add_attribute("recipient", recipient.to_string())
+            .add_attribute("refund", refund.to_string());
     }
 
-    Ok(response
-        .add_attribute("action", "withdraw")
-        .add_attribute("amount", amount))
+    Ok(response)
 }
 
 // Withdraw without caring about rewards. EMERGENCY ONLY.
 pub fn emergency_withdraw(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    lp_token: Addr,
+    lp_token: AssetInfo,
 ) -> Result<Response, ContractError> {
     let mut response = Response::new().add_attribute("Action", "EmergencyWithdraw");
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
-    let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
-    let user = USER_INFO.load(deps.storage, (&lp_token, &info.sender))?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    let user = USER_INFO.load(deps.storage, (lp_token.as_bytes(), &info.sender))?;
 
     pool.orphan_proxy_rewards = pool.orphan_proxy_rewards.checked_add(
         pool.acc_per_share_on_proxy
@@ -768,35 +1727,90 @@ pub fn emergency_withdraw(
             .saturating_sub(user.reward_debt_proxy),
     )?;
 
+    // Locked positions are charged with the penalty
+    let positions: Vec<(Vec<u8>, LockedPosition)> = LOCKED_POSITIONS
+        .prefix((lp_token.as_bytes(), &info.sender))
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut penalty = Uint128::zero();
+    for (lock_id, position) in positions {
+        if position.unlock_time.u64() > env.block.time.seconds() {
+            penalty = penalty.checked_add(
+                position
+                    .amount
+                    .multiply_ratio(EARLY_WITHDRAW_PENALTY, 100u64),
+            )?;
+        }
+        LOCKED_POSITIONS.remove(
+            deps.storage,
+            (lp_token.as_bytes(), &info.sender, U64Key::from(lock_id)),
+        );
+    }
+
+    let amount = user.amount.checked_sub(penalty)?;
+
     //call to transfer function for lp token
-    response
-        .messages
-        .push(if let Some(proxy) = &pool.reward_proxy {
-            SubMsg::new(WasmMsg::Execute {
-                contract_addr: proxy.to_string(),
-                msg: to_binary(&ProxyExecuteMsg::EmergencyWithdraw {
-                    account: info.sender.clone(),
-                    amount: user.amount,
-                })?,
-                funds: vec![],
-            })
-        } else {
-            SubMsg::new(WasmMsg::Execute {
-                contract_addr: lp_token.to_string(),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: info.sender.to_string(),
-                    amount: user.amount,
-                })?,
-                funds: vec![],
-            })
-        });
+    for (recipient, amount) in [(&info.sender, amount), (&cfg.owner, penalty)] {
+        if amount.is_zero() {
+            continue;
+        }
+
+        response
+            .messages
+            .push(if let Some(proxy) = &pool.reward_proxy {
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: proxy.to_string(),
+                    msg: to_binary(&ProxyExecuteMsg::EmergencyWithdraw {
+                        account: recipient.clone(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+            } else {
+                SubMsg::new(transfer_lp_msg(&lp_token, amount, recipient)?)
+            });
+    }
 
-    // Change user balance
-    USER_INFO.remove(deps.storage, (&lp_token, &info.sender));
-    POOL_INFO.save(deps.storage, &lp_token, &pool)?;
+    // Change user balance, pending ASTRO is forfeited and stays in the vesting
+    pool.working_supply = pool.working_supply.checked_sub(user.working_amount)?;
+    pool.reward_debt_supply = pool.reward_debt_supply.checked_sub(user.reward_debt)?;
+    pool.deposited = pool.deposited.checked_sub(user.amount)?;
+    USER_INFO.remove(deps.storage, (lp_token.as_bytes(), &info.sender));
+    USER_POSITIONS.remove(deps.storage, (&info.sender, lp_token.as_bytes()));
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
     Ok(response
         .add_attribute("action", "emergency_withdraw")
-        .add_attribute("amount", user.amount))
+        .add_attribute("amount", amount)
+        .add_attribute("penalty", penalty))
+}
+
+/// Transfers the exact amount of LP tokens, native LP tokens are sent by a plain bank send
+/// as LP denoms aren't taxed
+fn transfer_lp_msg(
+    lp_token: &AssetInfo,
+    amount: Uint128,
+    recipient: &Addr,
+) -> StdResult<CosmosMsg> {
+    Ok(match lp_token {
+        AssetInfo::Token { contract_addr } => WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+        AssetInfo::NativeToken { denom } => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+    })
 }
 
 fn set_allowed_reward_proxies(
@@ -822,11 +1836,33 @@ fn set_allowed_reward_proxies(
     Ok(Response::new().add_attribute("action", "set_allowed_reward_proxies"))
 }
 
+fn set_allowed_external_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    rewards: Vec<AssetInfo>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for reward in &rewards {
+        reward.check(deps.api)?;
+    }
+
+    CONFIG.update::<_, StdError>(deps.storage, |mut v| {
+        v.allowed_external_rewards = rewards;
+        Ok(v)
+    })?;
+    Ok(Response::new().add_attribute("action", "set_allowed_external_rewards"))
+}
+
 fn send_orphan_proxy_rewards(
     deps: DepsMut,
     info: MessageInfo,
     recipient: String,
-    lp_token: String,
+    lp_token: AssetInfo,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
@@ -834,13 +1870,13 @@ fn send_orphan_proxy_rewards(
         return Err(ContractError::Unauthorized {});
     };
 
-    let lp_token = addr_validate_to_lower(deps.api, &lp_token)?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
     let recipient = addr_validate_to_lower(deps.api, &recipient)?;
 
     let mut response = Response::new();
 
-    let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
     let proxy = match &pool.reward_proxy {
         Some(proxy) => proxy.clone(),
         None => return Err(ContractError::PoolDoesNotHaveAdditionalRewards {}),
@@ -848,7 +1884,7 @@ fn send_orphan_proxy_rewards(
 
     let amount = pool.orphan_proxy_rewards;
     pool.orphan_proxy_rewards = Uint128::zero();
-    POOL_INFO.save(deps.storage, &lp_token, &pool)?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
 
     if amount.is_zero() {
         return Err(ContractError::OrphanRewardsTooSmall {});
@@ -868,14 +1904,198 @@ fn send_orphan_proxy_rewards(
     Ok(response
         .add_attribute("action", "send_orphan_rewards")
         .add_attribute("recipient", recipient.to_string())
-        .add_attribute("lp_token", lp_token)
+        .add_attribute("lp_token", lp_token.to_string())
         .add_attribute("amount", amount))
 }
 
+// The reward proxy must stake the LP token of the pool and mustn't be used by another pool, as
+// proxy rewards of a pool are calculated by the reward balance of its proxy.
+fn validate_reward_proxy(
+    deps: Deps,
+    lp_token: &AssetInfo,
+    proxy: &Addr,
+) -> Result<(), ContractError> {
+    let res: ProxyConfigResponse = deps
+        .querier
+        .query_wasm_smart(proxy, &ProxyQueryMsg::Config {})?;
+    if !lp_token.equal(&AssetInfo::Token {
+        contract_addr: res.lp_token_addr,
+    }) {
+        return Err(ContractError::ProxyLpTokenMismatch {});
+    }
+
+    for v in POOL_INFO.range(deps.storage, None, None, Order::Ascending) {
+        let (_, pool) = v?;
+        if pool.reward_proxy.as_ref() == Some(proxy) {
+            return Err(ContractError::RewardProxyInUse {});
+        }
+    }
+
+    Ok(())
+}
+
+// Withdraw the pool's LP tokens from the old reward proxy, they are moved to the new reward proxy
+// with proxy rewards on reply, as the withdrawal may claim rewards too. Proxy rewards of the old
+// proxy are claimed before, so users keep their pending proxy rewards. Can only be called by the
+// owner.
+fn migrate_proxy(
+    mut deps: DepsMut,
+    env: Env,
+    lp_token: AssetInfo,
+    new_proxy: String,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+    let new_proxy = addr_validate_to_lower(deps.api, &new_proxy)?;
+
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    let old_proxy = match &pool.reward_proxy {
+        Some(proxy) => proxy.clone(),
+        None => return Err(ContractError::PoolDoesNotHaveAdditionalRewards {}),
+    };
+
+    if old_proxy == new_proxy {
+        return Err(ContractError::SameRewardProxy {});
+    }
+
+    if !cfg.allowed_reward_proxies.contains(&new_proxy) {
+        return Err(ContractError::RewardProxyNotAllowed {});
+    }
+
+    validate_reward_proxy(deps.as_ref(), &lp_token, &new_proxy)?;
+
+    let old_reward_token: Addr = deps
+        .querier
+        .query_wasm_smart(&old_proxy, &ProxyQueryMsg::RewardInfo {})?;
+    let new_reward_token: Addr = deps
+        .querier
+        .query_wasm_smart(&new_proxy, &ProxyQueryMsg::RewardInfo {})?;
+    if old_reward_token != new_reward_token {
+        return Err(ContractError::ProxyRewardTokenMismatch {});
+    }
+
+    let lp_supply = update_pool_rewards(deps.branch(), &env, &mut pool, &cfg)?;
+
+    // rewards which are received by the withdrawal are accounted on reply
+    pool.proxy_reward_balance_before_update = deps
+        .querier
+        .query_wasm_smart(&old_proxy, &ProxyQueryMsg::Reward {})?;
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+
+    if lp_supply.is_zero() {
+        return move_proxy_rewards(deps, lp_token, new_proxy, lp_supply);
+    }
+
+    TMP_USER_ACTION.save(
+        deps.storage,
+        &Some(ExecuteOnReply::MoveProxyRewards {
+            lp_token,
+            new_proxy,
+            lp_supply,
+        }),
+    )?;
+
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: old_proxy.to_string(),
+            funds: vec![],
+            msg: to_binary(&ProxyExecuteMsg::Withdraw {
+                account: env.contract.address,
+                amount: lp_supply,
+            })?,
+        },
+        0,
+    )))
+}
+
+// Send LP tokens withdrawn from the old reward proxy and all its rewards to the new reward proxy.
+// Rewards which are received by the withdrawal are distributed to the pool users.
+fn move_proxy_rewards(
+    deps: DepsMut,
+    lp_token: AssetInfo,
+    new_proxy: Addr,
+    lp_supply: Uint128,
+) -> Result<Response, ContractError> {
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
+    let old_proxy = pool
+        .reward_proxy
+        .clone()
+        .ok_or(ContractError::PoolDoesNotHaveAdditionalRewards {})?;
+
+    let reward_amount: Uint128 = deps
+        .querier
+        .query_wasm_smart(&old_proxy, &ProxyQueryMsg::Reward {})?;
+    let new_proxy_reward: Uint128 = deps
+        .querier
+        .query_wasm_smart(&new_proxy, &ProxyQueryMsg::Reward {})?;
+
+    if !lp_supply.is_zero() {
+        let token_rewards = reward_amount.checked_sub(pool.proxy_reward_balance_before_update)?;
+        let share = Decimal::from_ratio(token_rewards, lp_supply);
+        pool.acc_per_share_on_proxy = pool.acc_per_share_on_proxy.checked_add(share)?;
+    }
+
+    let mut messages: Vec<WasmMsg> = vec![];
+
+    if !reward_amount.is_zero() {
+        messages.push(WasmMsg::Execute {
+            contract_addr: old_proxy.to_string(),
+            funds: vec![],
+            msg: to_binary(&ProxyExecuteMsg::SendRewards {
+                account: new_proxy.clone(),
+                amount: reward_amount,
+            })?,
+        });
+    }
+
+    if !lp_supply.is_zero() {
+        messages.push(WasmMsg::Execute {
+            contract_addr: lp_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: new_proxy.to_string(),
+                msg: to_binary(&ProxyCw20HookMsg::Deposit {})?,
+                amount: lp_supply,
+            })?,
+        });
+    }
+
+    // rewards moved from the old proxy are already accounted in acc_per_share_on_proxy
+    pool.proxy_reward_balance_before_update = new_proxy_reward.checked_add(reward_amount)?;
+    pool.reward_proxy = Some(new_proxy.clone());
+    POOL_INFO.save(deps.storage, lp_token.as_bytes(), &pool)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "migrate_proxy")
+        .add_attribute("lp_token", lp_token.to_string())
+        .add_attribute("old_proxy", old_proxy.to_string())
+        .add_attribute("new_proxy", new_proxy.to_string()))
+}
+
+// Migrate the next batch of users after the migration from block based emissions. Anyone can
+// execute it.
+fn migrate_users(deps: DepsMut, limit: Option<u32>) -> Result<Response, ContractError> {
+    if MIGRATION_CURSOR.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::UsersMigrated {});
+    }
+
+    let (migrated, finished) = migration::migrate_users(deps, limit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate_users")
+        .add_attribute("migrated", migrated.to_string())
+        .add_attribute("finished", finished.to_string()))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::PoolLength {} => Ok(to_binary(&pool_length(deps)?)?),
+        QueryMsg::ActivePools {} => Ok(to_binary(&ActivePoolsResponse {
+            pools: ACTIVE_POOLS.load(deps.storage)?,
+        })?),
         QueryMsg::Deposit { lp_token, user } => {
             Ok(to_binary(&query_deposit(deps, lp_token, user)?)?)
         }
@@ -887,22 +2107,41 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
         QueryMsg::OrphanProxyRewards { lp_token } => {
             Ok(to_binary(&query_orphan_proxy_rewards(deps, lp_token)?)?)
         }
+        QueryMsg::LockedPositions { lp_token, user } => {
+            Ok(to_binary(&query_locked_positions(deps, lp_token, user)?)?)
+        }
+        QueryMsg::UserPositions {
+            user,
+            start_after,
+            limit,
+        } => Ok(to_binary(&query_user_positions(
+            deps,
+            env,
+            user,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::Pools { start_after, limit } => {
+            Ok(to_binary(&query_pools(deps, env, start_after, limit)?)?)
+        }
     }
 }
 
 pub fn pool_length(deps: Deps) -> Result<PoolLengthResponse, ContractError> {
-    let length = POOL_INFO
-        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .count();
+    let length = ACTIVE_POOLS.load(deps.storage)?.len();
     Ok(PoolLengthResponse { length })
 }
 
-pub fn query_deposit(deps: Deps, lp_token: Addr, user: Addr) -> Result<Uint128, ContractError> {
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+pub fn query_deposit(
+    deps: Deps,
+    lp_token: AssetInfo,
+    user: Addr,
+) -> Result<Uint128, ContractError> {
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
     let user = addr_validate_to_lower(deps.api, user.as_str())?;
 
     let user_info = USER_INFO
-        .load(deps.storage, (&lp_token, &user))
+        .load(deps.storage, (lp_token.as_bytes(), &user))
         .unwrap_or_default();
     Ok(user_info.amount)
 }
@@ -911,69 +2150,63 @@ pub fn query_deposit(deps: Deps, lp_token: Addr, user: Addr) -> Result<Uint128,
 pub fn pending_token(
     deps: Deps,
     env: Env,
-    lp_token: Addr,
+    lp_token: AssetInfo,
     user: Addr,
 ) -> Result<PendingTokenResponse, ContractError> {
+    // Working amounts of the users aren't set until they are migrated
+    if MIGRATION_CURSOR.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::MigrationInProgress {});
+    }
+
     let cfg = CONFIG.load(deps.storage)?;
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
     let user = addr_validate_to_lower(deps.api, user.as_str())?;
 
-    let pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let mut pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
     let user_info = USER_INFO
-        .load(deps.storage, (&lp_token, &user))
+        .load(deps.storage, (lp_token.as_bytes(), &user))
         .unwrap_or_default();
 
     let mut pending_on_proxy = None;
 
-    let lp_supply: Uint128;
+    let lp_supply = pool.deposited;
 
-    match &pool.reward_proxy {
-        Some(proxy) => {
-            lp_supply = deps
+    if let Some(proxy) = &pool.reward_proxy {
+        if !lp_supply.is_zero() {
+            let res: Option<Uint128> = deps
                 .querier
-                .query_wasm_smart(proxy, &ProxyQueryMsg::Deposit {})?;
-
-            if !lp_supply.is_zero() {
-                let res: Option<Uint128> = deps
-                    .querier
-                    .query_wasm_smart(proxy, &ProxyQueryMsg::PendingToken {})?;
-                let mut acc_per_share_on_proxy = pool.acc_per_share_on_proxy;
-                if let Some(token_rewards) = res {
-                    let share = Decimal::from_ratio(token_rewards, lp_supply);
-                    acc_per_share_on_proxy = pool.acc_per_share_on_proxy.checked_add(share)?;
-                }
-                pending_on_proxy = Some(
-                    acc_per_share_on_proxy
-                        .checked_mul(user_info.amount)?
-                        .checked_sub(user_info.reward_debt_proxy)?,
-                );
+                .query_wasm_smart(proxy, &ProxyQueryMsg::PendingToken {})?;
+            let mut acc_per_share_on_proxy = pool.acc_per_share_on_proxy;
+            if let Some(token_rewards) = res {
+                let share = Decimal::from_ratio(token_rewards, lp_supply);
+                acc_per_share_on_proxy = pool.acc_per_share_on_proxy.checked_add(share)?;
             }
-        }
-        None => {
-            let res: BalanceResponse = deps.querier.query_wasm_smart(
-                lp_token,
-                &cw20::Cw20QueryMsg::Balance {
-                    address: env.contract.address.to_string(),
-                },
-            )?;
-            lp_supply = res.balance;
+            pending_on_proxy = Some(
+                acc_per_share_on_proxy
+                    .checked_mul(user_info.amount)?
+                    .checked_sub(user_info.reward_debt_proxy)?,
+            );
         }
     }
 
     let mut acc_per_share = pool.acc_per_share;
-    if env.block.height > pool.last_reward_block.u64() && !lp_supply.is_zero() {
+    if env.block.time.seconds() > pool.last_reward_time.u64() && !pool.working_supply.is_zero() {
         let token_rewards = calculate_rewards(&env, &pool, &cfg)?;
-        let share = Decimal::from_ratio(token_rewards, lp_supply);
+        let share = Decimal::from_ratio(token_rewards, pool.working_supply);
         acc_per_share = pool.acc_per_share.checked_add(share)?;
     }
     let pending = acc_per_share
-        .checked_mul(user_info.amount)?
+        .checked_mul(user_info.working_amount)?
         .checked_sub(user_info.reward_debt)?;
 
+    update_external_rewards(&env, &mut pool, lp_supply)?;
+    let pending_external = pending_external_rewards(&pool, &user_info)?;
+
     Ok(PendingTokenResponse {
         pending,
         pending_on_proxy,
+        pending_external,
     })
 }
 
@@ -984,19 +2217,28 @@ fn query_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
         allowed_reward_proxies: config.allowed_reward_proxies,
         astro_token: config.astro_token,
         owner: config.owner,
-        start_block: config.start_block,
-        tokens_per_block: config.tokens_per_block,
+        emission_schedule: config.emission_schedule,
+        end_time: config.end_time,
         total_alloc_point: config.total_alloc_point,
         vesting_contract: config.vesting_contract,
+        gauge_controller: config.gauge_controller,
+        generator_controller: config.generator_controller,
+        allowed_external_rewards: config.allowed_external_rewards,
     })
 }
 
-fn query_reward_info(deps: Deps, lp_token: Addr) -> Result<RewardInfoResponse, ContractError> {
+fn query_reward_info(deps: Deps, lp_token: AssetInfo) -> Result<RewardInfoResponse, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+
+    let pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
 
-    let pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let external_reward_tokens = pool
+        .external_rewards
+        .iter()
+        .map(|v| v.info.clone())
+        .collect();
 
     let proxy_reward_token = match pool.reward_proxy {
         Some(proxy) => {
@@ -1011,13 +2253,14 @@ fn query_reward_info(deps: Deps, lp_token: Addr) -> Result<RewardInfoResponse, C
     Ok(RewardInfoResponse {
         base_reward_token: config.astro_token,
         proxy_reward_token,
+        external_reward_tokens,
     })
 }
 
-fn query_orphan_proxy_rewards(deps: Deps, lp_token: Addr) -> Result<Uint128, ContractError> {
-    let lp_token = addr_validate_to_lower(deps.api, lp_token.as_str())?;
+fn query_orphan_proxy_rewards(deps: Deps, lp_token: AssetInfo) -> Result<Uint128, ContractError> {
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
 
-    let pool = POOL_INFO.load(deps.storage, &lp_token)?;
+    let pool = POOL_INFO.load(deps.storage, lp_token.as_bytes())?;
     if pool.reward_proxy.is_none() {
         return Err(ContractError::PoolDoesNotHaveAdditionalRewards {});
     }
@@ -1025,18 +2268,176 @@ fn query_orphan_proxy_rewards(deps: Deps, lp_token: Addr) -> Result<Uint128, Con
     Ok(pool.orphan_proxy_rewards)
 }
 
+fn query_locked_positions(
+    deps: Deps,
+    lp_token: AssetInfo,
+    user: Addr,
+) -> Result<LockedPositionsResponse, ContractError> {
+    let lp_token = validate_lp_token(deps.api, lp_token)?;
+    let user = addr_validate_to_lower(deps.api, user.as_str())?;
+
+    let positions = LOCKED_POSITIONS
+        .prefix((lp_token.as_bytes(), &user))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|v| v.map(|(_, position)| position))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LockedPositionsResponse { positions })
+}
+
+fn query_user_positions(
+    deps: Deps,
+    env: Env,
+    user: Addr,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> Result<UserPositionsResponse, ContractError> {
+    let user = addr_validate_to_lower(deps.api, user.as_str())?;
+    let start_after = start_after
+        .map(|v| validate_lp_token(deps.api, v))
+        .transpose()?;
+
+    let positions = read_user_positions(deps.storage, &user, start_after, limit)?
+        .into_iter()
+        .map(|(lp_token, user_info)| {
+            let res = pending_token(deps, env.clone(), lp_token.clone(), user.clone())?;
+
+            Ok(UserPositionResponse {
+                lp_token,
+                amount: user_info.amount,
+                pending: res.pending,
+                pending_on_proxy: res.pending_on_proxy,
+                pending_external: res.pending_external,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    Ok(UserPositionsResponse { positions })
+}
+
+fn query_pools(
+    deps: Deps,
+    env: Env,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> Result<PoolsResponse, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let active_pools = ACTIVE_POOLS.load(deps.storage)?;
+    let start_after = start_after
+        .map(|v| validate_lp_token(deps.api, v))
+        .transpose()?;
+
+    let tokens_per_second = tokens_per_second_at(&cfg, env.block.time.seconds());
+
+    let pools = read_pools(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|pool| {
+            let astro_per_second = if cfg.total_alloc_point.is_zero() {
+                Uint128::zero()
+            } else {
+                tokens_per_second
+                    .multiply_ratio(pool.alloc_point.u64(), cfg.total_alloc_point.u64())
+            };
+
+            Ok(PoolInfoResponse {
+                active: active_pools.contains(&pool.lp_token),
+                lp_token: pool.lp_token,
+                alloc_point: pool.alloc_point,
+                astro_per_second,
+                lp_supply: pool.deposited,
+                working_supply: pool.working_supply,
+                reward_proxy: pool.reward_proxy,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PoolsResponse { pools })
+}
+
 pub fn calculate_rewards(env: &Env, pool: &PoolInfo, cfg: &Config) -> StdResult<Uint128> {
-    let n_blocks = Uint128::from(env.block.height).checked_sub(pool.last_reward_block.into())?;
+    // Removed pools don't get ASTRO
+    if pool.alloc_point.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let emission = calculate_emission(
+        &cfg.emission_schedule,
+        cfg.end_time,
+        pool.last_reward_time.u64(),
+        env.block.time.seconds(),
+    )?;
 
-    let r = n_blocks
-        .checked_mul(cfg.tokens_per_block)?
+    let r = emission
         .checked_mul(Uint128::from(pool.alloc_point.u64()))?
         .checked_div(Uint128::from(cfg.total_alloc_point.u64()))?;
 
     Ok(r)
 }
 
+// Returns ASTRO emitted per second at the given time.
+fn tokens_per_second_at(cfg: &Config, time: u64) -> Uint128 {
+    if matches!(cfg.end_time, Some(end_time) if end_time.u64() <= time) {
+        return Uint128::zero();
+    }
+
+    cfg.emission_schedule
+        .iter()
+        .rev()
+        .find(|v| v.start_time.u64() <= time)
+        .map(|v| v.tokens_per_second)
+        .unwrap_or_default()
+}
+
+// Returns ASTRO emitted between the given times, every period lasts until the start of the
+// next one and the last period lasts until the end time.
+pub fn calculate_emission(
+    emission_schedule: &[EmissionPeriod],
+    end_time: Option<Uint64>,
+    from: u64,
+    to: u64,
+) -> StdResult<Uint128> {
+    let to = end_time.map_or(to, |v| to.min(v.u64()));
+
+    let mut emission = Uint128::zero();
+    for (i, period) in emission_schedule.iter().enumerate() {
+        let period_from = period.start_time.u64().max(from);
+        let period_to = emission_schedule
+            .get(i + 1)
+            .map_or(to, |next| next.start_time.u64().min(to));
+
+        if period_to > period_from {
+            emission = emission.checked_add(
+                Uint128::from(period_to - period_from).checked_mul(period.tokens_per_second)?,
+            )?;
+        }
+    }
+
+    Ok(emission)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+
+    match contract_version.version.as_ref() {
+        "1.0.0" => {
+            validate_emission_schedule(&msg.emission_schedule, msg.end_time)?;
+            let unclaimed = migration::migrate_to_time_based_emissions(deps.branch(), &env, msg)?;
+
+            let cfg = CONFIG.load(deps.storage)?;
+            assert_emission_covered(
+                deps.as_ref(),
+                &env,
+                &cfg,
+                &cfg.emission_schedule,
+                cfg.end_time,
+                unclaimed,
+            )?;
+        }
+        _ => return Err(ContractError::MigrationError {}),
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attribute("action", "migrate"))
 }
@@ -15,14 +15,74 @@ pub enum ContractError {
     #[error("Pool with the LP token already exists!")]
     TokenPoolAlreadyExists {},
 
+    #[error("Pool is removed!")]
+    PoolRemoved {},
+
     #[error("Reward proxy not allowed!")]
     RewardProxyNotAllowed {},
 
+    #[error("Reward proxies are supported by CW20 LP tokens only!")]
+    RewardProxyNotSupported {},
+
+    #[error("Exactly one coin of a native LP token must be sent!")]
+    InvalidFunds {},
+
     #[error("Pool doesn't have additional rewards!")]
     PoolDoesNotHaveAdditionalRewards {},
 
+    #[error("The pool already uses the reward proxy!")]
+    SameRewardProxy {},
+
+    #[error("Reward tokens of the old and the new proxies must match!")]
+    ProxyRewardTokenMismatch {},
+
+    #[error("The reward proxy stakes another LP token!")]
+    ProxyLpTokenMismatch {},
+
+    #[error("The reward proxy is used by another pool!")]
+    RewardProxyInUse {},
+
     #[error("Insufficient amount of orphan rewards!")]
     OrphanRewardsTooSmall {},
+
+    #[error("Duplicate of pool")]
+    PoolDuplicate {},
+
+    #[error("Invalid reward schedule!")]
+    InvalidRewardSchedule {},
+
+    #[error("LP token can't be an external reward!")]
+    InvalidRewardToken {},
+
+    #[error("Too many external rewards or reward schedules in the pool!")]
+    TooManyRewardSchedules {},
+
+    #[error("The pool doesn't have the external reward!")]
+    ExternalRewardNotFound {},
+
+    #[error("The token isn't allowed as an external reward!")]
+    ExternalRewardNotAllowed {},
+
+    #[error("Locked LP tokens can't be withdrawn before the unlock time!")]
+    PositionLocked {},
+
+    #[error("Invalid emission schedule!")]
+    InvalidEmissionSchedule {},
+
+    #[error("Emission schedule must end if the last period emits ASTRO!")]
+    EmissionNotBounded {},
+
+    #[error("Emission schedule exceeds ASTRO left in the vesting!")]
+    EmissionExceedsVesting {},
+
+    #[error("Contract can't be migrated!")]
+    MigrationError {},
+
+    #[error("Users must be migrated first!")]
+    MigrationInProgress {},
+
+    #[error("Users are already migrated!")]
+    UsersMigrated {},
 }
 
 impl From<OverflowError> for ContractError {
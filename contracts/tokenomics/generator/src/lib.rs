@@ -1,3 +1,10 @@
 pub mod contract;
 pub mod error;
+pub mod migration;
 pub mod state;
+
+#[cfg(test)]
+mod testing;
+
+#[cfg(test)]
+mod mock_querier;
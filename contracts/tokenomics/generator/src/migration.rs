@@ -0,0 +1,220 @@
+use astroport::asset::AssetInfo;
+use astroport::generator::MigrateMsg;
+use astroport::generator_proxy::QueryMsg as ProxyQueryMsg;
+use astroport::DecimalCheckedOps;
+use cosmwasm_std::{Addr, Decimal, DepsMut, Env, Order, StdError, StdResult, Uint128, Uint64};
+use cw20::BalanceResponse;
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    Config, PoolInfo, UserInfo, ACTIVE_POOLS, CONFIG, POOL_INFO, USER_INFO, USER_POSITIONS,
+};
+
+/// Pool info of the block based emissions (v1.0.0)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolInfoV100 {
+    pub alloc_point: Uint64,
+    pub last_reward_block: Uint64,
+    pub acc_per_share: Decimal,
+    pub reward_proxy: Option<Addr>,
+    pub acc_per_share_on_proxy: Decimal,
+    pub proxy_reward_balance_before_update: Uint128,
+    pub orphan_proxy_rewards: Uint128,
+}
+
+/// Config of the block based emissions (v1.0.0)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV100 {
+    pub owner: Addr,
+    pub astro_token: Addr,
+    pub tokens_per_block: Uint128,
+    pub total_alloc_point: Uint64,
+    pub start_block: Uint64,
+    pub allowed_reward_proxies: Vec<Addr>,
+    pub vesting_contract: Addr,
+}
+
+pub const CONFIG_V100: Item<ConfigV100> = Item::new("config");
+pub const POOL_INFO_V100: Map<&Addr, PoolInfoV100> = Map::new("pool_info");
+
+// Raw key of the last migrated user, it is kept until all users are migrated
+pub const MIGRATION_CURSOR: Item<Vec<u8>> = Item::new("migration_cursor");
+
+const DEFAULT_USERS_LIMIT: u32 = 30;
+const MAX_USERS_LIMIT: u32 = 100;
+
+/// Rewards every pool per block up to the current block, then switches the config and
+/// the pools to the emission schedule starting at the current time, all pools are active.
+/// Users are migrated by `migrate_users` in batches, the contract is paused until then.
+/// Returns the upper bound of ASTRO which is accrued to the users but not claimed yet.
+pub fn migrate_to_time_based_emissions(
+    deps: DepsMut,
+    env: &Env,
+    msg: MigrateMsg,
+) -> StdResult<Uint128> {
+    let cfg = CONFIG_V100.load(deps.storage)?;
+
+    let pools: Vec<(Addr, PoolInfoV100)> = POOL_INFO_V100
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|v| {
+            v.ok()
+                .map(|v| (Addr::unchecked(String::from_utf8(v.0).unwrap()), v.1))
+        })
+        .collect();
+
+    let last_reward_time = Uint64::from(env.block.time.seconds());
+    let mut unclaimed_astro = Uint128::zero();
+
+    let active_pools: Vec<AssetInfo> = pools
+        .iter()
+        .map(|(lp_token, _)| AssetInfo::Token {
+            contract_addr: lp_token.clone(),
+        })
+        .collect();
+
+    for (lp_token, pool) in pools {
+        let mut acc_per_share = pool.acc_per_share;
+
+        let lp_supply: Uint128 = match &pool.reward_proxy {
+            Some(proxy) => deps
+                .querier
+                .query_wasm_smart(proxy, &ProxyQueryMsg::Deposit {})?,
+            None => {
+                let res: BalanceResponse = deps.querier.query_wasm_smart(
+                    &lp_token,
+                    &cw20::Cw20QueryMsg::Balance {
+                        address: env.contract.address.to_string(),
+                    },
+                )?;
+                res.balance
+            }
+        };
+
+        if env.block.height > pool.last_reward_block.u64()
+            && !cfg.total_alloc_point.is_zero()
+            && !lp_supply.is_zero()
+        {
+            let token_rewards = Uint128::from(env.block.height - pool.last_reward_block.u64())
+                .checked_mul(cfg.tokens_per_block)?
+                .checked_mul(Uint128::from(pool.alloc_point.u64()))?
+                .checked_div(Uint128::from(cfg.total_alloc_point.u64()))?;
+
+            let share = Decimal::from_ratio(token_rewards, lp_supply);
+            acc_per_share = acc_per_share.checked_add(share)?;
+        }
+
+        // Deposits of the users don't exceed the LP supply
+        unclaimed_astro = unclaimed_astro.checked_add(acc_per_share.checked_mul(lp_supply)?)?;
+
+        // Working supply, deposits and reward debts are summed by migrate_users
+        POOL_INFO.save(
+            deps.storage,
+            lp_token.as_bytes(),
+            &PoolInfo {
+                lp_token: AssetInfo::Token {
+                    contract_addr: lp_token.clone(),
+                },
+                alloc_point: pool.alloc_point,
+                last_reward_time,
+                acc_per_share,
+                reward_proxy: pool.reward_proxy,
+                acc_per_share_on_proxy: pool.acc_per_share_on_proxy,
+                proxy_reward_balance_before_update: pool.proxy_reward_balance_before_update,
+                orphan_proxy_rewards: pool.orphan_proxy_rewards,
+                external_rewards: vec![],
+                working_supply: Uint128::zero(),
+                deposited: Uint128::zero(),
+                reward_debt_supply: Uint128::zero(),
+            },
+        )?;
+    }
+
+    ACTIVE_POOLS.save(deps.storage, &active_pools)?;
+    MIGRATION_CURSOR.save(deps.storage, &vec![])?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner: cfg.owner,
+            astro_token: cfg.astro_token,
+            emission_schedule: msg.emission_schedule,
+            end_time: msg.end_time,
+            total_alloc_point: cfg.total_alloc_point,
+            allowed_reward_proxies: cfg.allowed_reward_proxies,
+            vesting_contract: cfg.vesting_contract,
+            gauge_controller: None,
+            generator_controller: None,
+            allowed_external_rewards: vec![],
+        },
+    )?;
+
+    Ok(unclaimed_astro)
+}
+
+/// Migrates the next batch of users: working amounts are set to their deposits and summed by
+/// pools with the deposits and the reward debts, so pending ASTRO of the users is kept and
+/// counted as not claimed. Deposits of the users are indexed by user.
+/// Pools aren't updated until all users are migrated, so the emission since the migration is
+/// distributed by the full working supply. Returns the number of migrated users and whether
+/// all users are migrated.
+pub fn migrate_users(deps: DepsMut, limit: Option<u32>) -> StdResult<(usize, bool)> {
+    let cursor = MIGRATION_CURSOR.load(deps.storage)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_USERS_LIMIT)
+        .clamp(1, MAX_USERS_LIMIT) as usize;
+    let start = if cursor.is_empty() {
+        None
+    } else {
+        Some(Bound::Exclusive(cursor))
+    };
+
+    let users: Vec<(Vec<u8>, UserInfo)> = USER_INFO
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (key, mut user_info) in users.iter().cloned() {
+        let (lp_token, user) = parse_user_key(&key)?;
+
+        let mut pool = POOL_INFO.load(deps.storage, &lp_token)?;
+        user_info.working_amount = user_info.amount;
+        pool.working_supply = pool.working_supply.checked_add(user_info.working_amount)?;
+        pool.deposited = pool.deposited.checked_add(user_info.amount)?;
+        pool.reward_debt_supply = pool.reward_debt_supply.checked_add(user_info.reward_debt)?;
+
+        POOL_INFO.save(deps.storage, &lp_token, &pool)?;
+        USER_INFO.save(deps.storage, (&lp_token, &user), &user_info)?;
+        if !user_info.amount.is_zero() {
+            USER_POSITIONS.save(deps.storage, (&user, &lp_token), &pool.lp_token)?;
+        }
+    }
+
+    let finished = users.len() < limit;
+    if finished {
+        MIGRATION_CURSOR.remove(deps.storage);
+    } else if let Some((key, _)) = users.last() {
+        MIGRATION_CURSOR.save(deps.storage, key)?;
+    }
+
+    Ok((users.len(), finished))
+}
+
+// Split the raw key of USER_INFO into the LP token bytes and the user, the first part is prefixed
+// by its length.
+fn parse_user_key(key: &[u8]) -> StdResult<(Vec<u8>, Addr)> {
+    let invalid_key = || StdError::generic_err("Invalid user info key");
+
+    let len = match key {
+        [a, b, ..] => u16::from_be_bytes([*a, *b]) as usize,
+        _ => return Err(invalid_key()),
+    };
+    if key.len() < 2 + len {
+        return Err(invalid_key());
+    }
+
+    let user = String::from_utf8(key[2 + len..].to_vec()).map_err(|_| invalid_key())?;
+
+    Ok((key[2..2 + len].to_vec(), Addr::unchecked(user)))
+}
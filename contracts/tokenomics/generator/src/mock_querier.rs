@@ -0,0 +1,130 @@
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Coin, ContractResult, Empty, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Timestamp, Uint128, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use std::collections::HashMap;
+
+use astroport::vesting::{
+    QueryMsg as VestingQueryMsg, VestingAccountResponse, VestingInfo, VestingSchedule,
+    VestingSchedulePoint,
+};
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    // token contract -> holder -> balance
+    token_balances: HashMap<String, HashMap<String, Uint128>>,
+    // vesting contract -> ASTRO left in the vesting
+    vesting_amounts: HashMap<String, Uint128>,
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if let Some(amount) = self.vesting_amounts.get(contract_addr) {
+                    return match from_binary(msg).unwrap() {
+                        VestingQueryMsg::VestingAccount { address } => SystemResult::Ok(
+                            to_binary(&VestingAccountResponse {
+                                address,
+                                info: VestingInfo {
+                                    schedules: vec![VestingSchedule {
+                                        start_point: VestingSchedulePoint {
+                                            time: Timestamp::from_seconds(0),
+                                            amount: *amount,
+                                        },
+                                        end_point: None,
+                                    }],
+                                    released_amount: Uint128::zero(),
+                                },
+                            })
+                            .into(),
+                        ),
+                        _ => panic!("DO NOT ENTER HERE"),
+                    };
+                }
+
+                let balances = match self.token_balances.get(contract_addr) {
+                    Some(v) => v,
+                    None => {
+                        return SystemResult::Ok(ContractResult::Err(format!(
+                            "No balance info exists for the contract {}",
+                            contract_addr
+                        )))
+                    }
+                };
+
+                match from_binary(msg).unwrap() {
+                    Cw20QueryMsg::Balance { address } => SystemResult::Ok(
+                        to_binary(&BalanceResponse {
+                            balance: balances.get(&address).cloned().unwrap_or_default(),
+                        })
+                        .into(),
+                    ),
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            token_balances: HashMap::new(),
+            vesting_amounts: HashMap::new(),
+        }
+    }
+
+    // configure the token balances
+    pub fn with_token_balances(&mut self, balances: &[(&str, &[(&str, u128)])]) {
+        for (token, holders) in balances {
+            self.token_balances.insert(
+                token.to_string(),
+                holders
+                    .iter()
+                    .map(|(holder, amount)| (holder.to_string(), Uint128::new(*amount)))
+                    .collect(),
+            );
+        }
+    }
+
+    // configure ASTRO left in the vesting for the generator
+    pub fn with_vesting(&mut self, vesting: &str, amount: u128) {
+        self.vesting_amounts
+            .insert(vesting.to_string(), Uint128::new(amount));
+    }
+}
@@ -1,6 +1,8 @@
+use astroport::asset::AssetInfo;
 use astroport::common::OwnershipProposal;
-use cosmwasm_std::{Addr, Decimal, Uint128, Uint64};
-use cw_storage_plus::{Item, Map};
+use astroport::generator::{EmissionPeriod, LockDuration, LockedPosition};
+use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage, Uint128, Uint64};
+use cw_storage_plus::{Bound, Item, Map, U64Key};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -10,12 +12,27 @@ pub struct UserInfo {
     pub amount: Uint128,
     pub reward_debt: Uint128,
     pub reward_debt_proxy: Uint128,
+    /// Reward debts of the external rewards of the pool by reward ids
+    #[serde(default)]
+    pub reward_debt_external: Vec<(u64, Uint128)>,
+    /// LP amount boosted by the locked xASTRO share, ASTRO rewards are distributed pro rata to it
+    #[serde(default)]
+    pub working_amount: Uint128,
+    /// LP amount in locked positions which are not released yet
+    #[serde(default)]
+    pub locked_amount: Uint128,
+    /// Extra LP amount by multipliers of the locked positions, it is added to the amount on
+    /// calculation of the working amount
+    #[serde(default)]
+    pub lockup_bonus: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PoolInfo {
+    /// CW20 or native LP token of the pool, pools are keyed by its bytes
+    pub lp_token: AssetInfo,
     pub alloc_point: Uint64,
-    pub last_reward_block: Uint64,
+    pub last_reward_time: Uint64,
     pub acc_per_share: Decimal,
     pub reward_proxy: Option<Addr>,
     pub acc_per_share_on_proxy: Decimal,
@@ -23,6 +40,41 @@ pub struct PoolInfo {
     pub proxy_reward_balance_before_update: Uint128,
     /// Orphan proxy rewards which are left by emergency withdrawals
     pub orphan_proxy_rewards: Uint128,
+    /// External rewards funded directly to the generator
+    #[serde(default)]
+    pub external_rewards: Vec<ExternalReward>,
+    /// Sum of working amounts of the pool users
+    #[serde(default)]
+    pub working_supply: Uint128,
+    /// LP tokens deposited by the pool users, the balance of the generator isn't used as
+    /// tokens can be sent to it directly or be rewards of another pool
+    #[serde(default)]
+    pub deposited: Uint128,
+    /// Sum of ASTRO reward debts of the pool users, ASTRO accrued to the users and not claimed
+    /// yet is acc_per_share * working_supply - reward_debt_supply
+    #[serde(default)]
+    pub reward_debt_supply: Uint128,
+}
+
+/// Reward token of a pool which is distributed by schedules
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalReward {
+    /// Unique id of the reward, a token which is removed and added again gets a new one, so
+    /// reward debts of the removed reward are ignored
+    pub id: u64,
+    pub info: AssetInfo,
+    /// Schedules which are not finished yet
+    pub schedules: Vec<RewardSchedule>,
+    pub acc_per_share: Decimal,
+    pub last_reward_time: Uint64,
+}
+
+/// The amount is distributed linearly from the start to the end time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardSchedule {
+    pub amount: Uint128,
+    pub start_time: Uint64,
+    pub end_time: Uint64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -30,52 +82,140 @@ pub struct Config {
     pub owner: Addr,
     // ASTRO token address
     pub astro_token: Addr,
-    // ASTRO tokens created per block.
-    pub tokens_per_block: Uint128,
+    // ASTRO emission periods sorted by start time.
+    pub emission_schedule: Vec<EmissionPeriod>,
+    // The time in seconds when ASTRO mining ends.
+    pub end_time: Option<Uint64>,
     // Total allocation points. Must be the sum of all allocation points in all pools.
     pub total_alloc_point: Uint64,
-    // The block number when ASTRO mining starts.
-    pub start_block: Uint64,
     // List of allowed reward proxy contracts
     pub allowed_reward_proxies: Vec<Addr>,
     // Vesting contract from which rewards are received
     pub vesting_contract: Addr,
+    // Gauge controller by which xASTRO locked by users ASTRO rewards are boosted
+    #[serde(default)]
+    pub gauge_controller: Option<Addr>,
+    // Contract which may set alloc points of the pools besides the owner
+    #[serde(default)]
+    pub generator_controller: Option<Addr>,
+    // List of tokens which can be external rewards of the pools
+    #[serde(default)]
+    pub allowed_external_rewards: Vec<AssetInfo>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum ExecuteOnReply {
     MassUpdatePools {},
+    RemovePool {
+        lp_token: AssetInfo,
+    },
     Add {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         alloc_point: Uint64,
         reward_proxy: Option<String>,
     },
     Set {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         alloc_point: Uint64,
     },
+    SetAllocPoints {
+        pools: Vec<(AssetInfo, Uint64)>,
+    },
     UpdatePool {
-        lp_token: Addr,
+        lp_token: AssetInfo,
     },
     Deposit {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         account: Addr,
         amount: Uint128,
+        lock_duration: Option<LockDuration>,
     },
     Withdraw {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         account: Addr,
         amount: Uint128,
     },
-    SetTokensPerBlock {
-        amount: Uint128,
+    ClaimRewards {
+        lp_tokens: Vec<AssetInfo>,
+        account: Addr,
+        recipient: Addr,
+    },
+    CheckpointUserBoost {
+        lp_tokens: Vec<AssetInfo>,
+        user: Addr,
+    },
+    SetEmissionSchedule {
+        emission_schedule: Vec<EmissionPeriod>,
+        end_time: Option<Uint64>,
+    },
+    MigrateProxy {
+        lp_token: AssetInfo,
+        new_proxy: String,
+    },
+    MoveProxyRewards {
+        lp_token: AssetInfo,
+        new_proxy: Addr,
+        lp_supply: Uint128,
+    },
+    CancelRewardSchedules {
+        lp_token: AssetInfo,
+        reward_info: AssetInfo,
+        recipient: Option<Addr>,
+        remove_reward: bool,
     },
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const POOL_INFO: Map<&Addr, PoolInfo> = Map::new("pool_info");
+pub const POOL_INFO: Map<&[u8], PoolInfo> = Map::new("pool_info");
+// LP tokens of the pools which are not removed
+pub const ACTIVE_POOLS: Item<Vec<AssetInfo>> = Item::new("active_pools");
 pub const TMP_USER_ACTION: Item<Option<ExecuteOnReply>> = Item::new("tmp_user_action");
 
-// first key part is token, second - depositor
-pub const USER_INFO: Map<(&Addr, &Addr), UserInfo> = Map::new("user_info");
+// first key part is LP token bytes, second - depositor
+pub const USER_INFO: Map<(&[u8], &Addr), UserInfo> = Map::new("user_info");
+// first key part is depositor, second - LP token bytes, lists pools with deposits of the user
+pub const USER_POSITIONS: Map<(&Addr, &[u8]), AssetInfo> = Map::new("user_positions");
+// first key part is LP token bytes, second - depositor, third - lock id
+pub const LOCKED_POSITIONS: Map<(&[u8], &Addr, U64Key), LockedPosition> =
+    Map::new("locked_positions");
+pub const LAST_LOCK_ID: Item<u64> = Item::new("last_lock_id");
+pub const LAST_REWARD_ID: Item<u64> = Item::new("last_reward_id");
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn read_pools(
+    storage: &dyn Storage,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PoolInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|v| Bound::Exclusive(v.as_bytes().to_vec()));
+
+    POOL_INFO
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|v| v.map(|(_, v)| v))
+        .collect()
+}
+
+pub fn read_user_positions(
+    storage: &dyn Storage,
+    user: &Addr,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(AssetInfo, UserInfo)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|v| Bound::Exclusive(v.as_bytes().to_vec()));
+
+    USER_POSITIONS
+        .prefix(user)
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|v| {
+            let (key, lp_token) = v?;
+            Ok((lp_token, USER_INFO.load(storage, (&key, user))?))
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{from_binary, Addr, Decimal, Env, OwnedDeps, Uint128, Uint64};
+use cw2::set_contract_version;
+
+use crate::contract::{execute, migrate, query};
+use crate::error::ContractError;
+use crate::migration::{ConfigV100, PoolInfoV100, CONFIG_V100, POOL_INFO_V100};
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+use crate::state::{UserInfo, POOL_INFO, TMP_USER_ACTION, USER_INFO};
+use astroport::asset::AssetInfo;
+use astroport::generator::{
+    EmissionPeriod, ExecuteMsg, MigrateMsg, PendingTokenResponse, QueryMsg,
+};
+
+const OWNER: &str = "owner";
+const VESTING: &str = "vesting";
+const LP_1: &str = "lp_token1";
+const LP_2: &str = "lp_token2";
+
+fn token(lp_token: &str) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: Addr::unchecked(lp_token),
+    }
+}
+
+// Deposits of the users by block based emissions (v1.0.0), pending ASTRO of the users is
+// 20 and 65 in the first pool and 40 in the second one at the current block
+fn instantiate_v100(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, env: &Env) {
+    set_contract_version(deps.as_mut().storage, "astroport-generator", "1.0.0").unwrap();
+
+    CONFIG_V100
+        .save(
+            deps.as_mut().storage,
+            &ConfigV100 {
+                owner: Addr::unchecked(OWNER),
+                astro_token: Addr::unchecked("astro"),
+                tokens_per_block: Uint128::new(10),
+                total_alloc_point: Uint64::new(100),
+                start_block: Uint64::new(1),
+                allowed_reward_proxies: vec![],
+                vesting_contract: Addr::unchecked(VESTING),
+            },
+        )
+        .unwrap();
+    TMP_USER_ACTION.save(deps.as_mut().storage, &None).unwrap();
+
+    for (lp_token, alloc_point, acc_per_share) in [
+        (LP_1, 60, Decimal::from_ratio(1u128, 10u128)),
+        (LP_2, 40, Decimal::zero()),
+    ] {
+        POOL_INFO_V100
+            .save(
+                deps.as_mut().storage,
+                &Addr::unchecked(lp_token),
+                &PoolInfoV100 {
+                    alloc_point: Uint64::new(alloc_point),
+                    last_reward_block: Uint64::new(env.block.height - 10),
+                    acc_per_share,
+                    reward_proxy: None,
+                    acc_per_share_on_proxy: Decimal::zero(),
+                    proxy_reward_balance_before_update: Uint128::zero(),
+                    orphan_proxy_rewards: Uint128::zero(),
+                },
+            )
+            .unwrap();
+    }
+
+    for (lp_token, user, amount, reward_debt) in [
+        (LP_1, "user1", 100, 5),
+        (LP_1, "user2", 300, 10),
+        (LP_2, "user1", 50, 0),
+    ] {
+        USER_INFO
+            .save(
+                deps.as_mut().storage,
+                (lp_token.as_bytes(), &Addr::unchecked(user)),
+                &UserInfo {
+                    amount: Uint128::new(amount),
+                    reward_debt: Uint128::new(reward_debt),
+                    ..UserInfo::default()
+                },
+            )
+            .unwrap();
+    }
+
+    deps.querier.with_token_balances(&[
+        (LP_1, &[(MOCK_CONTRACT_ADDR, 400)]),
+        (LP_2, &[(MOCK_CONTRACT_ADDR, 50)]),
+    ]);
+}
+
+fn pending_token(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    lp_token: &str,
+    user: &str,
+) -> Uint128 {
+    let msg = QueryMsg::PendingToken {
+        lp_token: token(lp_token),
+        user: Addr::unchecked(user),
+    };
+    let res: PendingTokenResponse = from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+    res.pending
+}
+
+fn migrate_users(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    limit: u32,
+) -> Result<(String, String), ContractError> {
+    let msg = ExecuteMsg::MigrateUsers { limit: Some(limit) };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg)?;
+    Ok((
+        res.attributes[1].value.clone(),
+        res.attributes[2].value.clone(),
+    ))
+}
+
+#[test]
+fn migrate_from_block_based_emissions() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    instantiate_v100(&mut deps, &env);
+
+    let msg = MigrateMsg {
+        emission_schedule: vec![EmissionPeriod {
+            start_time: Uint64::new(env.block.time.seconds()),
+            tokens_per_second: Uint128::new(1),
+        }],
+        end_time: Some(Uint64::new(env.block.time.seconds() + 100)),
+    };
+
+    // 100 ASTRO of the emission and up to 140 ASTRO accrued to the deposits
+    let mut failed_deps = mock_dependencies(&[]);
+    instantiate_v100(&mut failed_deps, &env);
+    failed_deps.querier.with_vesting(VESTING, 239);
+    let err = migrate(failed_deps.as_mut(), env.clone(), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::EmissionExceedsVesting {}));
+
+    deps.querier.with_vesting(VESTING, 240);
+    migrate(deps.as_mut(), env.clone(), msg).unwrap();
+
+    // The contract is paused until all users are migrated
+    let msg = ExecuteMsg::MassUpdatePools {};
+    let err = execute(deps.as_mut(), env.clone(), mock_info("user1", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::MigrationInProgress {}));
+
+    let msg = QueryMsg::PendingToken {
+        lp_token: token(LP_1),
+        user: Addr::unchecked("user1"),
+    };
+    query(deps.as_ref(), env.clone(), msg).unwrap_err();
+
+    assert_eq!(
+        migrate_users(&mut deps, 2).unwrap(),
+        ("2".to_string(), "false".to_string())
+    );
+    assert_eq!(
+        migrate_users(&mut deps, 2).unwrap(),
+        ("1".to_string(), "true".to_string())
+    );
+    let err = migrate_users(&mut deps, 2).unwrap_err();
+    assert!(matches!(err, ContractError::UsersMigrated {}));
+
+    // Pending ASTRO of the users is kept
+    assert_eq!(pending_token(&deps, env.clone(), LP_1, "user1").u128(), 20);
+    assert_eq!(pending_token(&deps, env.clone(), LP_1, "user2").u128(), 65);
+    assert_eq!(pending_token(&deps, env.clone(), LP_2, "user1").u128(), 40);
+
+    // Working supplies are summed from the users
+    let pool = POOL_INFO
+        .load(deps.as_ref().storage, LP_1.as_bytes())
+        .unwrap();
+    assert_eq!(pool.working_supply.u128(), 400);
+    assert_eq!(pool.deposited.u128(), 400);
+    assert_eq!(pool.reward_debt_supply.u128(), 15);
+    let user = USER_INFO
+        .load(
+            deps.as_ref().storage,
+            (LP_2.as_bytes(), &Addr::unchecked("user1")),
+        )
+        .unwrap();
+    assert_eq!(user.working_amount.u128(), 50);
+
+    // The emission is distributed by the working supplies
+    let mut env = env;
+    env.block.time = env.block.time.plus_seconds(10);
+    assert_eq!(pending_token(&deps, env.clone(), LP_1, "user2").u128(), 69);
+    assert_eq!(pending_token(&deps, env.clone(), LP_2, "user1").u128(), 44);
+
+    let msg = ExecuteMsg::MassUpdatePools {};
+    execute(deps.as_mut(), env, mock_info("user1", &[]), msg).unwrap();
+}
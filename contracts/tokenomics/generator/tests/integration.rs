@@ -1,9 +1,13 @@
+use astroport::asset::{Asset, AssetInfo};
 use astroport::generator::{ExecuteMsg, QueryMsg};
 use astroport::{
+    gauge_controller::{Cw20HookMsg as GaugeHookMsg, InstantiateMsg as GaugeInstantiateMsg},
     generator::{
-        ConfigResponse, Cw20HookMsg as GeneratorHookMsg, ExecuteMsg as GeneratorExecuteMsg,
-        InstantiateMsg as GeneratorInstantiateMsg, PendingTokenResponse,
-        QueryMsg as GeneratorQueryMsg,
+        ActivePoolsResponse, ConfigResponse, Cw20HookMsg as GeneratorHookMsg, EmissionPeriod,
+        ExecuteMsg as GeneratorExecuteMsg, InstantiateMsg as GeneratorInstantiateMsg, LockDuration,
+        LockedPosition, LockedPositionsResponse, PendingTokenResponse, PoolInfoResponse,
+        PoolLengthResponse, PoolsResponse, QueryMsg as GeneratorQueryMsg, UserPositionResponse,
+        UserPositionsResponse,
     },
     generator_proxy::InstantiateMsg as ProxyInstantiateMsg,
     token::InstantiateMsg as TokenInstantiateMsg,
@@ -14,7 +18,7 @@ use astroport::{
 };
 use cosmwasm_std::{
     testing::{mock_env, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
-    to_binary, Addr, StdResult, Uint128, Uint64,
+    to_binary, Addr, Coin, StdResult, Uint128, Uint64,
 };
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use mirror_protocol::staking::{
@@ -28,29 +32,101 @@ const USER1: &str = "user1";
 const USER2: &str = "user2";
 
 #[test]
-fn set_tokens_per_block() {
+fn set_emission_schedule() {
     let mut app = mock_app();
 
     let token_code_id = store_token_code(&mut app);
     let astro_token_instance =
         instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
 
     let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
 
+    let now = app.block_info().time.seconds();
+
     let msg = QueryMsg::Config {};
     let res: ConfigResponse = app
         .wrap()
         .query_wasm_smart(&generator_instance, &msg)
         .unwrap();
 
-    assert_eq!(res.tokens_per_block, Uint128::new(10_000000));
+    assert_eq!(
+        res.emission_schedule,
+        vec![EmissionPeriod {
+            start_time: Uint64::from(now),
+            tokens_per_second: Uint128::new(2_000000),
+        }]
+    );
+    assert_eq!(res.end_time, Some(Uint64::from(now + 31536000)));
+
+    register_lp_tokens_in_generator(&mut app, &generator_instance, None, &[&lp_cny_eur_instance]);
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &Addr::unchecked(USER1), 10);
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    // Emission is halved after 7 seconds and ends after 12 seconds
+    let emission_schedule = vec![
+        EmissionPeriod {
+            start_time: Uint64::from(now),
+            tokens_per_second: Uint128::new(2_000000),
+        },
+        EmissionPeriod {
+            start_time: Uint64::from(now + 7),
+            tokens_per_second: Uint128::new(1_000000),
+        },
+    ];
+
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: emission_schedule.iter().rev().cloned().collect(),
+        end_time: None,
+    };
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            generator_instance.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid emission schedule!");
 
-    // setting new value of tokens per block
-    let tokens_per_block = Uint128::new(100);
+    // 63072000 ASTRO is left in the vesting
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: emission_schedule.clone(),
+        end_time: Some(Uint64::from(now + 63072000)),
+    };
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            generator_instance.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Emission schedule exceeds ASTRO left in the vesting!"
+    );
 
-    let msg = GeneratorExecuteMsg::SetTokensPerBlock {
-        amount: tokens_per_block,
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: emission_schedule.clone(),
+        end_time: Some(Uint64::from(now + 12)),
     };
+    let err = app
+        .execute_contract(
+            Addr::unchecked(USER1),
+            generator_instance.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
     app.execute_contract(
         Addr::unchecked(OWNER),
         generator_instance.clone(),
@@ -64,7 +140,113 @@ fn set_tokens_per_block() {
         .wrap()
         .query_wasm_smart(&generator_instance, &msg)
         .unwrap();
-    assert_eq!(res.tokens_per_block, tokens_per_block);
+    assert_eq!(res.emission_schedule, emission_schedule);
+    assert_eq!(res.end_time, Some(Uint64::from(now + 12)));
+
+    // 5 seconds of the first period
+    app.update_block(|bi| next_block(bi));
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (10_000000, None),
+    );
+
+    // 7 seconds of the first period and 3 seconds of the second one
+    app.update_block(|bi| next_block(bi));
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (17_000000, None),
+    );
+
+    // Nothing is emitted after the end
+    app.update_block(|bi| next_block(bi));
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (19_000000, None),
+    );
+
+    app.update_block(|bi| next_block(bi));
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (19_000000, None),
+    );
+
+    let now = app.block_info().time.seconds();
+
+    // The last period emitting ASTRO must end
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: vec![EmissionPeriod {
+            start_time: Uint64::from(now),
+            tokens_per_second: Uint128::new(1_000000),
+        }],
+        end_time: None,
+    };
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            generator_instance.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Emission schedule must end if the last period emits ASTRO!"
+    );
+
+    // 19 ASTRO are distributed but not claimed yet, so 63071981 ASTRO can be emitted
+    let emission_schedule = |emission_seconds: u64| {
+        vec![
+            EmissionPeriod {
+                start_time: Uint64::from(now),
+                tokens_per_second: Uint128::new(1_000000),
+            },
+            EmissionPeriod {
+                start_time: Uint64::from(now + emission_seconds),
+                tokens_per_second: Uint128::zero(),
+            },
+        ]
+    };
+
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: emission_schedule(63071982),
+        end_time: None,
+    };
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            generator_instance.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Emission schedule exceeds ASTRO left in the vesting!"
+    );
+
+    let msg = GeneratorExecuteMsg::SetEmissionSchedule {
+        emission_schedule: emission_schedule(63071981),
+        end_time: None,
+    };
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        generator_instance.clone(),
+        &msg,
+        &[],
+    )
+    .unwrap();
 }
 
 #[test]
@@ -91,6 +273,8 @@ fn update_config() {
 
     let msg = ExecuteMsg::UpdateConfig {
         vesting_contract: Some(new_vesting.to_string()),
+        gauge_controller: None,
+        generator_controller: None,
     };
 
     // Assert cannot update with improper owner
@@ -322,7 +506,7 @@ fn generator_without_reward_proxies() {
 
     // User can't withdraw if didn't deposit
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
         amount: Uint128::new(1_000000),
     };
     assert_eq!(
@@ -334,7 +518,7 @@ fn generator_without_reward_proxies() {
 
     // User can't emergency withdraw if didn't deposit
     let msg = GeneratorExecuteMsg::EmergencyWithdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     assert_eq!(
         app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
@@ -345,7 +529,7 @@ fn generator_without_reward_proxies() {
 
     app.update_block(|bi| next_block(bi));
 
-    // 10 per block by 5 for two pools having the same alloc points
+    // 2 per second over a 5 second block by 5 for two pools having the same alloc points
     check_pending_rewards(
         &mut app,
         &generator_instance,
@@ -411,13 +595,13 @@ fn generator_without_reward_proxies() {
     // change pool alloc points
     let msg = GeneratorExecuteMsg::Set {
         alloc_point: Uint64::new(60),
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
     let msg = GeneratorExecuteMsg::Set {
         alloc_point: Uint64::new(40),
-        lp_token: lp_eur_usd_instance.clone(),
+        lp_token: token_info(&lp_eur_usd_instance),
     };
     app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
@@ -458,7 +642,7 @@ fn generator_without_reward_proxies() {
     // User1 emergency withdraws and loses already fixed rewards (5).
     // Pending tokens (3) will be redistributed to other staking users.
     let msg = GeneratorExecuteMsg::EmergencyWithdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
@@ -498,7 +682,7 @@ fn generator_without_reward_proxies() {
 
     // User1 can't withdraw after emergency withdraw
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
         amount: Uint128::new(1_000000),
     };
     assert_eq!(
@@ -514,59 +698,1501 @@ fn generator_without_reward_proxies() {
 
     // User2 withdraw and get rewards
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_cny_eur_instance, &generator_instance, 0);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user1, 10);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user2, 10);
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 0);
+    check_token_balance(&mut app, &astro_token_instance, &user2, 6_000000);
+    // Distributed Astro are 7 + 2 (for other pool) (5 left on emergency withdraw, 6 transfered to User2)
+
+    // User1 withdraw and get rewards
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_eur_usd_instance),
+        amount: Uint128::new(5),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 15);
+    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 5);
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
+
+    // User1 withdraw and get rewards
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_eur_usd_instance),
+        amount: Uint128::new(5),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 10);
+    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 10);
+    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
+
+    // User2 withdraw and get rewards
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_eur_usd_instance),
         amount: Uint128::new(10),
     };
     app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
 
-    check_token_balance(&mut app, &lp_cny_eur_instance, &generator_instance, 0);
-    check_token_balance(&mut app, &lp_cny_eur_instance, &user1, 10);
-    check_token_balance(&mut app, &lp_cny_eur_instance, &user2, 10);
-
-    check_token_balance(&mut app, &astro_token_instance, &user1, 0);
-    check_token_balance(&mut app, &astro_token_instance, &user2, 6_000000);
-    // Distributed Astro are 7 + 2 (for other pool) (5 left on emergency withdraw, 6 transfered to User2)
+    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 0);
+    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 10);
+    check_token_balance(&mut app, &lp_eur_usd_instance, &user2, 10);
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
+    check_token_balance(&mut app, &astro_token_instance, &user2, 6_000000 + 2_000000);
+}
+
+#[test]
+fn claim_rewards() {
+    let mut app = mock_app();
+
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let lp_eur_usd_instance = instantiate_token(&mut app, token_code_id, "EUR-USD", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(
+        &mut app,
+        &generator_instance,
+        None,
+        &[&lp_cny_eur_instance, &lp_eur_usd_instance],
+    );
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_eur_usd_instance, &user1, 10);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10), (&lp_eur_usd_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // Pools can't be claimed twice in one message
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![
+            token_info(&lp_cny_eur_instance),
+            token_info(&lp_cny_eur_instance),
+        ],
+        recipient: None,
+    };
+    assert_eq!(
+        app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Duplicate of pool".to_string()
+    );
+
+    // User can't claim from a pool without deposit
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![token_info(&lp_cny_eur_instance)],
+        recipient: None,
+    };
+    assert_eq!(
+        app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "astroport_generator::state::UserInfo not found".to_string()
+    );
+
+    // Rewards from both pools are sent to the recipient
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![
+            token_info(&lp_cny_eur_instance),
+            token_info(&lp_eur_usd_instance),
+        ],
+        recipient: Some(USER2.to_string()),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 0);
+    check_token_balance(&mut app, &astro_token_instance, &user2, 10_000000);
+
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (0, None),
+    );
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_eur_usd_instance,
+        USER1,
+        (0, None),
+    );
+
+    // Deposits stay in the generator
+    check_token_balance(&mut app, &lp_cny_eur_instance, &generator_instance, 10);
+    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 10);
+
+    app.update_block(|bi| next_block(bi));
+
+    // Rewards are sent to the sender if the recipient is not set
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![token_info(&lp_eur_usd_instance)],
+        recipient: None,
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 5_000000);
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (5_000000, None),
+    );
+}
+
+#[test]
+fn generator_with_external_rewards() {
+    let mut app = mock_app();
+
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let partner_token_instance = instantiate_token(&mut app, token_code_id, "PARTNER", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(&mut app, &generator_instance, None, &[&lp_cny_eur_instance]);
+
+    let partner = Addr::unchecked("partner");
+    mint_tokens(&mut app, &partner_token_instance, &partner, 100_000000);
+
+    let now = app.block_info().time.seconds();
+    let reward = Asset {
+        info: AssetInfo::Token {
+            contract_addr: partner_token_instance.clone(),
+        },
+        amount: Uint128::new(100_000000),
+    };
+
+    // Only the owner allows external rewards
+    let msg = GeneratorExecuteMsg::AddRewardSchedule {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward: reward.clone(),
+        start_time: Uint64::from(now),
+        end_time: Uint64::from(now + 100),
+    };
+    assert_eq!(
+        app.execute_contract(partner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "The token isn't allowed as an external reward!".to_string()
+    );
+
+    let msg = GeneratorExecuteMsg::SetAllowedExternalRewards {
+        rewards: vec![reward.info.clone()],
+    };
+    assert_eq!(
+        app.execute_contract(partner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Unauthorized".to_string()
+    );
+    allow_external_rewards(&mut app, &generator_instance, &[reward.info.clone()]);
+
+    // Schedule can't end before it starts
+    let msg = GeneratorExecuteMsg::AddRewardSchedule {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward: reward.clone(),
+        start_time: Uint64::from(now),
+        end_time: Uint64::from(now),
+    };
+    assert_eq!(
+        app.execute_contract(partner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Invalid reward schedule!".to_string()
+    );
+
+    // LP token can't be rewarded
+    let msg = GeneratorExecuteMsg::AddRewardSchedule {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward: Asset {
+            info: AssetInfo::Token {
+                contract_addr: lp_cny_eur_instance.clone(),
+            },
+            amount: Uint128::new(100_000000),
+        },
+        start_time: Uint64::from(now),
+        end_time: Uint64::from(now + 100),
+    };
+    assert_eq!(
+        app.execute_contract(partner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "LP token can't be an external reward!".to_string()
+    );
+
+    // The partner funds 1 PARTNER per second for 100 seconds
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::AddRewardSchedule {
+            lp_token: token_info(&lp_cny_eur_instance),
+            start_time: Uint64::from(now),
+            end_time: Uint64::from(now + 100),
+        })
+        .unwrap(),
+        amount: reward.amount,
+    };
+    app.execute_contract(partner.clone(), partner_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &partner_token_instance, &partner, 0);
+    check_token_balance(
+        &mut app,
+        &partner_token_instance,
+        &generator_instance,
+        100_000000,
+    );
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user2, 10);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = GeneratorQueryMsg::PendingToken {
+        lp_token: token_info(&lp_cny_eur_instance),
+        user: user1.clone(),
+    };
+    let res: PendingTokenResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(
+        res.pending_external,
+        vec![Asset {
+            info: AssetInfo::Token {
+                contract_addr: partner_token_instance.clone(),
+            },
+            amount: Uint128::new(5_000000),
+        }]
+    );
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER2,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // Rewards of the second block are shared by both depositors
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![token_info(&lp_cny_eur_instance)],
+        recipient: None,
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &partner_token_instance, &user1, 7_500000);
+    check_token_balance(&mut app, &partner_token_instance, &user2, 2_500000);
+
+    // Nothing is distributed after the schedule ends
+    app.update_block(|bi| {
+        bi.height += 100;
+        bi.time = bi.time.plus_seconds(1000);
+    });
+
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_cny_eur_instance),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &partner_token_instance, &user1, 52_500000);
+    check_token_balance(&mut app, &partner_token_instance, &user2, 47_500000);
+    check_token_balance(&mut app, &partner_token_instance, &generator_instance, 0);
+}
+
+#[test]
+fn cancel_reward_schedules() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let partner = Addr::unchecked("partner");
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let partner_token_instance = instantiate_token(&mut app, token_code_id, "PARTNER", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(&mut app, &generator_instance, None, &[&lp_cny_eur_instance]);
+
+    mint_tokens(&mut app, &partner_token_instance, &partner, 100_000000);
+    allow_external_rewards(
+        &mut app,
+        &generator_instance,
+        &[token_info(&partner_token_instance)],
+    );
+
+    // 1 PARTNER per second for 100 seconds
+    let now = app.block_info().time.seconds();
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::AddRewardSchedule {
+            lp_token: token_info(&lp_cny_eur_instance),
+            start_time: Uint64::from(now),
+            end_time: Uint64::from(now + 100),
+        })
+        .unwrap(),
+        amount: Uint128::new(100_000000),
+    };
+    app.execute_contract(partner.clone(), partner_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = GeneratorExecuteMsg::CancelRewardSchedules {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward_info: token_info(&partner_token_instance),
+        recipient: Some(partner.to_string()),
+        remove_reward: false,
+    };
+    assert_eq!(
+        app.execute_contract(partner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Unauthorized".to_string()
+    );
+
+    let msg = GeneratorExecuteMsg::CancelRewardSchedules {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward_info: token_info(&astro_token_instance),
+        recipient: Some(partner.to_string()),
+        remove_reward: false,
+    };
+    assert_eq!(
+        app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "The pool doesn't have the external reward!".to_string()
+    );
+
+    // 5 PARTNER are distributed in the first block, the rest is sent back
+    let msg = GeneratorExecuteMsg::CancelRewardSchedules {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward_info: token_info(&partner_token_instance),
+        recipient: Some(partner.to_string()),
+        remove_reward: false,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &partner_token_instance, &partner, 95_000000);
+    check_token_balance(
+        &mut app,
+        &partner_token_instance,
+        &generator_instance,
+        5_000000,
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // Distributed rewards stay claimable
+    let msg = GeneratorExecuteMsg::ClaimRewards {
+        lp_tokens: vec![token_info(&lp_cny_eur_instance)],
+        recipient: None,
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &partner_token_instance, &user1, 5_000000);
+    check_token_balance(&mut app, &partner_token_instance, &generator_instance, 0);
+
+    // The reward is removed without a refund, it isn't returned by the pending query anymore
+    let msg = GeneratorExecuteMsg::CancelRewardSchedules {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward_info: token_info(&partner_token_instance),
+        recipient: None,
+        remove_reward: true,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::PendingToken {
+        lp_token: token_info(&lp_cny_eur_instance),
+        user: user1.clone(),
+    };
+    let res: PendingTokenResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.pending_external, vec![]);
+
+    // The token is added again as a new reward, debts of the removed one are ignored
+    let now = app.block_info().time.seconds();
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::AddRewardSchedule {
+            lp_token: token_info(&lp_cny_eur_instance),
+            start_time: Uint64::from(now),
+            end_time: Uint64::from(now + 100),
+        })
+        .unwrap(),
+        amount: Uint128::new(10_000000),
+    };
+    app.execute_contract(partner.clone(), partner_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_cny_eur_instance),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user1, 10);
+    check_token_balance(&mut app, &partner_token_instance, &user1, 5_500000);
+    check_token_balance(
+        &mut app,
+        &partner_token_instance,
+        &generator_instance,
+        9_500000,
+    );
+}
+
+#[test]
+fn generator_with_boost() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let xastro_token_instance = instantiate_token(&mut app, token_code_id, "XASTRO", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+    let gauge_instance =
+        instantiate_gauge_controller(&mut app, &generator_instance, &xastro_token_instance);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        vesting_contract: None,
+        gauge_controller: Some(gauge_instance.to_string()),
+        generator_controller: None,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    register_lp_tokens_in_generator(&mut app, &generator_instance, None, &[&lp_cny_eur_instance]);
+
+    mint_tokens(&mut app, &xastro_token_instance, &user1, 200_000000);
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user2, 10);
+
+    // User 1 locks a half of xASTRO and transfers the other half to user 2
+    lock_xastro(
+        &mut app,
+        &xastro_token_instance,
+        &gauge_instance,
+        &user1,
+        100_000000,
+    );
+
+    let msg = Cw20ExecuteMsg::Transfer {
+        recipient: USER2.to_string(),
+        amount: Uint128::new(100_000000),
+    };
+    app.execute_contract(user1.clone(), xastro_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    // User 1 locked all voting power and gets the full amount, user 2 gets 40% of the deposit
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER2,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // 10 ASTRO shared by working amounts 10 and 4
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (7_142857, None),
+    );
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER2,
+        (2_857142, None),
+    );
+
+    // Transferred xASTRO doesn't boost rewards of user 2 until it is locked
+    let msg = GeneratorExecuteMsg::CheckpointUserBoost {
+        lp_tokens: vec![token_info(&lp_cny_eur_instance)],
+        user: None,
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    // Pending rewards are sent to the user on checkpoint
+    check_token_balance(&mut app, &astro_token_instance, &user2, 2_857142);
+
+    app.update_block(|bi| next_block(bi));
+
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER2,
+        (2_857142, None),
+    );
+
+    lock_xastro(
+        &mut app,
+        &xastro_token_instance,
+        &gauge_instance,
+        &user2,
+        100_000000,
+    );
+
+    // Boost isn't changed until checkpoint
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER2,
+        (2_857142, None),
+    );
+
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    // Anyone can checkpoint the user
+    let msg = GeneratorExecuteMsg::CheckpointUserBoost {
+        lp_tokens: vec![token_info(&lp_cny_eur_instance)],
+        user: Some(USER1.to_string()),
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &astro_token_instance, &user1, 14_285714);
+    check_token_balance(&mut app, &astro_token_instance, &user2, 5_714284);
+
+    app.update_block(|bi| next_block(bi));
+
+    // Both users locked a half of xASTRO: 4 + 0.6 * 20 * 0.5 = 10
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (5_000000, None),
+    );
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER2,
+        (5_000000, None),
+    );
+}
+
+#[test]
+fn generator_with_lockups() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(&mut app, &generator_instance, None, &[&lp_cny_eur_instance]);
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 20);
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user2, 10);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    // User 2 locks LP tokens for a month and gets the 1.1x multiplier
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::DepositLocked {
+            lock_duration: LockDuration::OneMonth,
+        })
+        .unwrap(),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user2.clone(), lp_cny_eur_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let unlock_time = app.block_info().time.seconds() + 30 * 86400;
+
+    let msg = GeneratorQueryMsg::LockedPositions {
+        lp_token: token_info(&lp_cny_eur_instance),
+        user: user2.clone(),
+    };
+    let res: LockedPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(
+        res.positions,
+        vec![LockedPosition {
+            amount: Uint128::new(10),
+            lock_duration: LockDuration::OneMonth,
+            unlock_time: Uint64::from(unlock_time),
+        }]
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // 10 ASTRO shared by working amounts 10 and 11
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (4_761904, None),
+    );
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER2,
+        (5_238095, None),
+    );
+
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_cny_eur_instance),
+        amount: Uint128::new(10),
+    };
+    let err = app
+        .execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Locked LP tokens can't be withdrawn before the unlock time!"
+    );
+
+    // The position is released after the unlock time
+    app.update_block(|bi| {
+        bi.height += 1;
+        bi.time = bi.time.plus_seconds(30 * 86400);
+    });
+
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user2, 10);
+
+    let msg = GeneratorQueryMsg::LockedPositions {
+        lp_token: token_info(&lp_cny_eur_instance),
+        user: user2.clone(),
+    };
+    let res: LockedPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.positions, vec![]);
+
+    // Emergency withdrawal charges the penalty from locked LP tokens only
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::DepositLocked {
+            lock_duration: LockDuration::TwelveMonths,
+        })
+        .unwrap(),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user1.clone(), lp_cny_eur_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorExecuteMsg::EmergencyWithdraw {
+        lp_token: token_info(&lp_cny_eur_instance),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user1, 19);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &owner, 1);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &generator_instance, 0);
+}
+
+#[test]
+fn remove_pool() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let lp_eur_usd_instance = instantiate_token(&mut app, token_code_id, "EUR-USD", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(
+        &mut app,
+        &generator_instance,
+        None,
+        &[&lp_cny_eur_instance, &lp_eur_usd_instance],
+    );
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_eur_usd_instance, &user2, 20);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER2,
+        &[(&lp_eur_usd_instance, 10)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = GeneratorExecuteMsg::RemovePool {
+        lp_token: token_info(&lp_eur_usd_instance),
+    };
+    let err = app
+        .execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let err = app
+        .execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Pool is removed!");
+
+    let msg = GeneratorQueryMsg::PoolLength {};
+    let res: PoolLengthResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.length, 1);
+
+    let msg = GeneratorQueryMsg::Config {};
+    let res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.total_alloc_point, Uint64::from(100u64));
+
+    // Alloc point of the removed pool can't be set
+    let msg = GeneratorExecuteMsg::Set {
+        lp_token: token_info(&lp_eur_usd_instance),
+        alloc_point: Uint64::from(100u64),
+    };
+    let err = app
+        .execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Pool is removed!");
+
+    // New deposits to the removed pool aren't accepted
+    let msg = Cw20ExecuteMsg::Send {
+        contract: generator_instance.to_string(),
+        msg: to_binary(&GeneratorHookMsg::Deposit {}).unwrap(),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user2.clone(), lp_eur_usd_instance.clone(), &msg, &[])
+        .unwrap_err();
+
+    app.update_block(|bi| next_block(bi));
+
+    // The remaining pool gets all ASTRO, rewards of the removed pool are frozen
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (15_000000, None),
+    );
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_eur_usd_instance,
+        USER2,
+        (5_000000, None),
+    );
+
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_eur_usd_instance),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_eur_usd_instance, &user2, 20);
+    check_token_balance(&mut app, &astro_token_instance, &user2, 5_000000);
+}
+
+#[test]
+fn generator_controller() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let controller = Addr::unchecked("controller");
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let lp_eur_usd_instance = instantiate_token(&mut app, token_code_id, "EUR-USD", None);
+    let lp_usd_luna_instance = instantiate_token(&mut app, token_code_id, "USD-LUNA", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(
+        &mut app,
+        &generator_instance,
+        None,
+        &[
+            &lp_cny_eur_instance,
+            &lp_eur_usd_instance,
+            &lp_usd_luna_instance,
+        ],
+    );
+
+    let msg = ExecuteMsg::UpdateConfig {
+        vesting_contract: None,
+        gauge_controller: None,
+        generator_controller: Some(controller.to_string()),
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::Config {};
+    let res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.generator_controller, Some(controller.clone()));
+
+    // The controller can only set alloc points
+    let msg = GeneratorExecuteMsg::RemovePool {
+        lp_token: token_info(&lp_usd_luna_instance),
+    };
+    let err = app
+        .execute_contract(controller.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::ActivePools {};
+    let res: ActivePoolsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(
+        res.pools,
+        vec![
+            token_info(&lp_cny_eur_instance),
+            token_info(&lp_eur_usd_instance)
+        ]
+    );
+
+    let msg = GeneratorExecuteMsg::SetAllocPoints {
+        pools: vec![
+            (token_info(&lp_cny_eur_instance), Uint64::from(300u64)),
+            (token_info(&lp_usd_luna_instance), Uint64::from(100u64)),
+        ],
+    };
+    let err = app
+        .execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let err = app
+        .execute_contract(controller.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Pool is removed!");
+
+    let msg = GeneratorExecuteMsg::SetAllocPoints {
+        pools: vec![
+            (token_info(&lp_cny_eur_instance), Uint64::from(300u64)),
+            (token_info(&lp_eur_usd_instance), Uint64::from(100u64)),
+        ],
+    };
+    app.execute_contract(controller.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::Config {};
+    let res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.total_alloc_point, Uint64::from(400u64));
+
+    let msg = GeneratorExecuteMsg::Set {
+        lp_token: token_info(&lp_eur_usd_instance),
+        alloc_point: Uint64::zero(),
+    };
+    app.execute_contract(controller.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::Config {};
+    let res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.total_alloc_point, Uint64::from(300u64));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        vesting_contract: None,
+        gauge_controller: None,
+        generator_controller: Some(user1.to_string()),
+    };
+    let err = app
+        .execute_contract(controller.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+}
+
+#[test]
+fn user_positions_and_pools() {
+    let mut app = mock_app();
+
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let lp_eur_usd_instance = instantiate_token(&mut app, token_code_id, "EUR-USD", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    register_lp_tokens_in_generator(
+        &mut app,
+        &generator_instance,
+        None,
+        &[&lp_cny_eur_instance, &lp_eur_usd_instance],
+    );
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_eur_usd_instance, &user1, 10);
+    mint_tokens(&mut app, &lp_eur_usd_instance, &user2, 30);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10), (&lp_eur_usd_instance, 10)],
+    );
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER2,
+        &[(&lp_eur_usd_instance, 30)],
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    // Every pool gets 5 ASTRO per block, user1 has 1/4 of the EUR-USD deposits
+    let mut expected = vec![
+        UserPositionResponse {
+            lp_token: token_info(&lp_cny_eur_instance),
+            amount: Uint128::new(10),
+            pending: Uint128::new(5_000000),
+            pending_on_proxy: None,
+            pending_external: vec![],
+        },
+        UserPositionResponse {
+            lp_token: token_info(&lp_eur_usd_instance),
+            amount: Uint128::new(10),
+            pending: Uint128::new(1_250000),
+            pending_on_proxy: None,
+            pending_external: vec![],
+        },
+    ];
+    // Positions are returned in ascending order of LP tokens
+    expected.sort_by(|a, b| a.lp_token.as_bytes().cmp(b.lp_token.as_bytes()));
+
+    let msg = GeneratorQueryMsg::UserPositions {
+        user: user1.clone(),
+        start_after: None,
+        limit: None,
+    };
+    let res: UserPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.positions, expected);
+
+    let msg = GeneratorQueryMsg::UserPositions {
+        user: user1.clone(),
+        start_after: None,
+        limit: Some(1),
+    };
+    let res: UserPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.positions, expected[..1]);
+
+    let msg = GeneratorQueryMsg::UserPositions {
+        user: user1.clone(),
+        start_after: Some(expected[0].lp_token.clone()),
+        limit: None,
+    };
+    let res: UserPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.positions, expected[1..]);
+
+    // Pools without deposits of the user are skipped
+    let msg = GeneratorQueryMsg::UserPositions {
+        user: user2.clone(),
+        start_after: None,
+        limit: Some(1),
+    };
+    let res: UserPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(
+        res.positions,
+        vec![UserPositionResponse {
+            lp_token: token_info(&lp_eur_usd_instance),
+            amount: Uint128::new(30),
+            pending: Uint128::new(3_750000),
+            pending_on_proxy: None,
+            pending_external: vec![],
+        }]
+    );
+
+    let mut expected = vec![
+        PoolInfoResponse {
+            lp_token: token_info(&lp_cny_eur_instance),
+            alloc_point: Uint64::from(100u64),
+            astro_per_second: Uint128::new(1_000000),
+            lp_supply: Uint128::new(10),
+            working_supply: Uint128::new(10),
+            reward_proxy: None,
+            active: true,
+        },
+        PoolInfoResponse {
+            lp_token: token_info(&lp_eur_usd_instance),
+            alloc_point: Uint64::from(100u64),
+            astro_per_second: Uint128::new(1_000000),
+            lp_supply: Uint128::new(40),
+            working_supply: Uint128::new(40),
+            reward_proxy: None,
+            active: true,
+        },
+    ];
+    expected.sort_by(|a, b| a.lp_token.as_bytes().cmp(b.lp_token.as_bytes()));
+
+    let msg = GeneratorQueryMsg::Pools {
+        start_after: None,
+        limit: None,
+    };
+    let res: PoolsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.pools, expected);
+
+    let msg = GeneratorQueryMsg::Pools {
+        start_after: Some(expected[0].lp_token.clone()),
+        limit: Some(1),
+    };
+    let res: PoolsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.pools, expected[1..]);
+
+    // Withdrawn positions are removed
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_eur_usd_instance),
+        amount: Uint128::new(30),
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorQueryMsg::UserPositions {
+        user: user2.clone(),
+        start_after: None,
+        limit: None,
+    };
+    let res: UserPositionsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    assert_eq!(res.positions, vec![]);
+}
+
+#[test]
+fn generator_with_native_lp_tokens() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+    let user2 = Addr::unchecked(USER2);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    let lp_token = AssetInfo::NativeToken {
+        denom: "ulp".to_string(),
+    };
+
+    let msg = GeneratorExecuteMsg::Add {
+        lp_token: lp_token.clone(),
+        alloc_point: Uint64::from(100u64),
+        reward_proxy: Some("proxy".to_string()),
+    };
+    let err = app
+        .execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Reward proxies are supported by CW20 LP tokens only!"
+    );
+
+    let msg = GeneratorExecuteMsg::Add {
+        lp_token: lp_token.clone(),
+        alloc_point: Uint64::from(100u64),
+        reward_proxy: None,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    app.init_bank_balance(&user1, vec![Coin::new(100, "ulp"), Coin::new(100, "uusd")])
+        .unwrap();
+
+    let msg = GeneratorExecuteMsg::Deposit {
+        beneficiary: None,
+        lock_duration: None,
+    };
+    let err = app
+        .execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Exactly one coin of a native LP token must be sent!"
+    );
+
+    let err = app
+        .execute_contract(
+            user1.clone(),
+            generator_instance.clone(),
+            &msg,
+            &[Coin::new(10, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    app.execute_contract(
+        user1.clone(),
+        generator_instance.clone(),
+        &msg,
+        &[Coin::new(10, "ulp")],
+    )
+    .unwrap();
+
+    // Deposit on behalf of user2
+    let msg = GeneratorExecuteMsg::Deposit {
+        beneficiary: Some(USER2.to_string()),
+        lock_duration: None,
+    };
+    app.execute_contract(
+        user1.clone(),
+        generator_instance.clone(),
+        &msg,
+        &[Coin::new(30, "ulp")],
+    )
+    .unwrap();
+
+    // Native LP tokens which are rewards of another pool aren't counted as deposits
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+    let msg = GeneratorExecuteMsg::Add {
+        lp_token: token_info(&lp_cny_eur_instance),
+        alloc_point: Uint64::zero(),
+        reward_proxy: None,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+    allow_external_rewards(&mut app, &generator_instance, &[lp_token.clone()]);
+
+    let start_time = app.block_info().time.seconds();
+    let msg = GeneratorExecuteMsg::AddRewardSchedule {
+        lp_token: token_info(&lp_cny_eur_instance),
+        reward: Asset {
+            info: lp_token.clone(),
+            amount: Uint128::new(50),
+        },
+        start_time: Uint64::from(start_time),
+        end_time: Uint64::from(start_time + 100),
+    };
+    app.execute_contract(
+        user1.clone(),
+        generator_instance.clone(),
+        &msg,
+        &[Coin::new(50, "ulp")],
+    )
+    .unwrap();
+
+    app.update_block(|bi| next_block(bi));
+
+    for (user, pending) in [(USER1, 2_500000), (USER2, 7_500000)] {
+        let msg = GeneratorQueryMsg::PendingToken {
+            lp_token: lp_token.clone(),
+            user: Addr::unchecked(user),
+        };
+        let res: PendingTokenResponse = app
+            .wrap()
+            .query_wasm_smart(&generator_instance, &msg)
+            .unwrap();
+        assert_eq!(res.pending, Uint128::new(pending));
+    }
+
+    let msg = GeneratorQueryMsg::Pools {
+        start_after: None,
+        limit: None,
+    };
+    let res: PoolsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
+        .unwrap();
+    let pool = res.pools.iter().find(|v| v.lp_token == lp_token).unwrap();
+    assert_eq!(pool.lp_supply, Uint128::new(40));
+
+    // Native LP tokens are withdrawn by bank sends
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: lp_token.clone(),
+        amount: Uint128::new(30),
+    };
+    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res = app.wrap().query_balance(user2.to_string(), "ulp").unwrap();
+    assert_eq!(res.amount, Uint128::new(30));
+    check_token_balance(&mut app, &astro_token_instance, &user2, 7_500000);
+
+    let msg = GeneratorExecuteMsg::EmergencyWithdraw {
+        lp_token: lp_token.clone(),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res = app.wrap().query_balance(user1.to_string(), "ulp").unwrap();
+    assert_eq!(res.amount, Uint128::new(20));
+
+    // Reward tokens of the other pool are left
+    let res = app
+        .wrap()
+        .query_balance(generator_instance.to_string(), "ulp")
+        .unwrap();
+    assert_eq!(res.amount, Uint128::new(50));
+}
+
+#[test]
+fn native_lp_tokens_round_trip() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    let lp_token = AssetInfo::NativeToken {
+        denom: "ulp".to_string(),
+    };
+
+    let msg = GeneratorExecuteMsg::Add {
+        lp_token: lp_token.clone(),
+        alloc_point: Uint64::from(100u64),
+        reward_proxy: None,
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    app.init_bank_balance(&user1, vec![Coin::new(1_000_000000, "ulp")])
+        .unwrap();
 
-    // User1 withdraw and get rewards
-    let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
-        amount: Uint128::new(5),
+    let msg = GeneratorExecuteMsg::Deposit {
+        beneficiary: None,
+        lock_duration: None,
     };
-    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
-        .unwrap();
+    app.execute_contract(
+        user1.clone(),
+        generator_instance.clone(),
+        &msg,
+        &[Coin::new(600_000000, "ulp")],
+    )
+    .unwrap();
 
-    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 15);
-    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 5);
+    let msg = GeneratorExecuteMsg::Deposit {
+        beneficiary: None,
+        lock_duration: Some(LockDuration::ThreeMonths),
+    };
+    app.execute_contract(
+        user1.clone(),
+        generator_instance.clone(),
+        &msg,
+        &[Coin::new(400_000000, "ulp")],
+    )
+    .unwrap();
 
-    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
+    app.update_block(|bi| next_block(bi));
 
-    // User1 withdraw and get rewards
+    // The exact amount is sent back, no tax is deducted from LP tokens
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
-        amount: Uint128::new(5),
+        lp_token: lp_token.clone(),
+        amount: Uint128::new(600_000000),
     };
     app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
 
-    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 10);
-    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 10);
-    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
+    let res = app.wrap().query_balance(user1.to_string(), "ulp").unwrap();
+    assert_eq!(res.amount, Uint128::new(600_000000));
+    let res = app
+        .wrap()
+        .query_balance(generator_instance.to_string(), "ulp")
+        .unwrap();
+    assert_eq!(res.amount, Uint128::new(400_000000));
 
-    // User2 withdraw and get rewards
-    let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
-        amount: Uint128::new(10),
+    let msg = GeneratorQueryMsg::Pools {
+        start_after: None,
+        limit: None,
     };
-    app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
+    let res: PoolsResponse = app
+        .wrap()
+        .query_wasm_smart(&generator_instance, &msg)
         .unwrap();
+    assert_eq!(res.pools[0].lp_supply, Uint128::new(400_000000));
 
-    check_token_balance(&mut app, &lp_eur_usd_instance, &generator_instance, 0);
-    check_token_balance(&mut app, &lp_eur_usd_instance, &user1, 10);
-    check_token_balance(&mut app, &lp_eur_usd_instance, &user2, 10);
+    // 10% of the locked LP tokens are sent to the owner as a penalty
+    let msg = GeneratorExecuteMsg::EmergencyWithdraw {
+        lp_token: lp_token.clone(),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
 
-    check_token_balance(&mut app, &astro_token_instance, &user1, 7_000000);
-    check_token_balance(&mut app, &astro_token_instance, &user2, 6_000000 + 2_000000);
+    let res = app.wrap().query_balance(user1.to_string(), "ulp").unwrap();
+    assert_eq!(res.amount, Uint128::new(960_000000));
+    let res = app.wrap().query_balance(owner.to_string(), "ulp").unwrap();
+    assert_eq!(res.amount, Uint128::new(40_000000));
+    let res = app
+        .wrap()
+        .query_balance(generator_instance.to_string(), "ulp")
+        .unwrap();
+    assert_eq!(res.amount, Uint128::zero());
 }
 
 #[test]
@@ -612,7 +2238,7 @@ fn generator_with_mirror_reward_proxy() {
     let msg = GeneratorExecuteMsg::Add {
         alloc_point: Uint64::from(100u64),
         reward_proxy: Some(proxy_to_mirror_instance.to_string()),
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     assert_eq!(
         app.execute_contract(
@@ -702,7 +2328,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User can't withdraw if didn't deposit
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
         amount: Uint128::new(1_000000),
     };
     assert_eq!(
@@ -714,7 +2340,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User can't emergency withdraw if didn't deposit
     let msg = GeneratorExecuteMsg::EmergencyWithdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     assert_eq!(
         app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
@@ -738,7 +2364,7 @@ fn generator_with_mirror_reward_proxy() {
     app.execute_contract(owner.clone(), mirror_token_instance.clone(), &msg, &[])
         .unwrap();
 
-    // 10 per block by 5 for two pools having the same alloc points
+    // 2 per second over a 5 second block by 5 for two pools having the same alloc points
     check_pending_rewards(
         &mut app,
         &generator_instance,
@@ -815,13 +2441,13 @@ fn generator_with_mirror_reward_proxy() {
     // change pool alloc points
     let msg = GeneratorExecuteMsg::Set {
         alloc_point: Uint64::new(60),
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
     let msg = GeneratorExecuteMsg::Set {
         alloc_point: Uint64::new(40),
-        lp_token: lp_eur_usd_instance.clone(),
+        lp_token: token_info(&lp_eur_usd_instance),
     };
     app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
@@ -875,7 +2501,7 @@ fn generator_with_mirror_reward_proxy() {
     // User1 emergency withdraws and loses already fixed rewards (5).
     // Pending tokens (3) will be redistributed to other staking users.
     let msg = GeneratorExecuteMsg::EmergencyWithdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
         .unwrap();
@@ -915,7 +2541,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User1 can't withdraw after emergency withdraw
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
         amount: Uint128::new(1_000000),
     };
     assert_eq!(
@@ -939,7 +2565,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // Check if there are orphan proxy rewards
     let msg = GeneratorQueryMsg::OrphanProxyRewards {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
     let orphan_rewards: Uint128 = app
         .wrap()
@@ -950,7 +2576,7 @@ fn generator_with_mirror_reward_proxy() {
     // Owner sends orphan proxy rewards
     let msg = GeneratorExecuteMsg::SendOrphanProxyReward {
         recipient: owner.to_string(),
-        lp_token: lp_cny_eur_instance.to_string(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
 
     app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
@@ -967,7 +2593,7 @@ fn generator_with_mirror_reward_proxy() {
     // Owner can't send proxy rewards for distribution to users
     let msg = GeneratorExecuteMsg::SendOrphanProxyReward {
         recipient: owner.to_string(),
-        lp_token: lp_cny_eur_instance.to_string(),
+        lp_token: token_info(&lp_cny_eur_instance),
     };
 
     assert_eq!(
@@ -979,7 +2605,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User2 withdraw and get rewards
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_cny_eur_instance.clone(),
+        lp_token: token_info(&lp_cny_eur_instance),
         amount: Uint128::new(10),
     };
     app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
@@ -1004,7 +2630,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User1 withdraw and get rewards
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
+        lp_token: token_info(&lp_eur_usd_instance),
         amount: Uint128::new(5),
     };
     app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
@@ -1018,7 +2644,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User1 withdraw and get rewards
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
+        lp_token: token_info(&lp_eur_usd_instance),
         amount: Uint128::new(5),
     };
     app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
@@ -1031,7 +2657,7 @@ fn generator_with_mirror_reward_proxy() {
 
     // User2 withdraw and get rewards
     let msg = GeneratorExecuteMsg::Withdraw {
-        lp_token: lp_eur_usd_instance.clone(),
+        lp_token: token_info(&lp_eur_usd_instance),
         amount: Uint128::new(10),
     };
     app.execute_contract(user2.clone(), generator_instance.clone(), &msg, &[])
@@ -1053,6 +2679,224 @@ fn generator_with_mirror_reward_proxy() {
     );
 }
 
+#[test]
+fn migrate_proxy() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked(OWNER);
+    let user1 = Addr::unchecked(USER1);
+
+    let token_code_id = store_token_code(&mut app);
+
+    let pair_cny_eur_instance = Addr::unchecked("cny-eur pair");
+
+    let lp_cny_eur_instance = instantiate_token(&mut app, token_code_id, "CNY-EUR", None);
+
+    let astro_token_instance =
+        instantiate_token(&mut app, token_code_id, "ASTRO", Some(1_000_000_000_000000));
+
+    let generator_instance = instantiate_generator(&mut app, &astro_token_instance);
+
+    let (mirror_token_instance, mirror_staking_instance) = instantiate_mirror_protocol(
+        &mut app,
+        token_code_id,
+        &pair_cny_eur_instance,
+        &lp_cny_eur_instance,
+    );
+
+    let proxy_code_id = store_proxy_code(&mut app);
+
+    let old_proxy_instance = instantiate_proxy(
+        &mut app,
+        proxy_code_id,
+        &generator_instance,
+        &pair_cny_eur_instance,
+        &lp_cny_eur_instance,
+        &mirror_staking_instance,
+        &mirror_token_instance,
+    );
+    let new_proxy_instance = instantiate_proxy(
+        &mut app,
+        proxy_code_id,
+        &generator_instance,
+        &pair_cny_eur_instance,
+        &lp_cny_eur_instance,
+        &mirror_staking_instance,
+        &mirror_token_instance,
+    );
+    let lp_eur_usd_instance = instantiate_token(&mut app, token_code_id, "EUR-USD", None);
+    let eur_usd_proxy_instance = instantiate_proxy(
+        &mut app,
+        proxy_code_id,
+        &generator_instance,
+        &pair_cny_eur_instance,
+        &lp_eur_usd_instance,
+        &mirror_staking_instance,
+        &mirror_token_instance,
+    );
+
+    let msg = GeneratorExecuteMsg::SetAllowedRewardProxies {
+        proxies: vec![old_proxy_instance.to_string()],
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    register_lp_tokens_in_generator(
+        &mut app,
+        &generator_instance,
+        Some(&old_proxy_instance),
+        &[&lp_cny_eur_instance],
+    );
+
+    mint_tokens(&mut app, &lp_cny_eur_instance, &user1, 10);
+
+    deposit_lp_tokens_to_generator(
+        &mut app,
+        &generator_instance,
+        USER1,
+        &[(&lp_cny_eur_instance, 10)],
+    );
+
+    let msg = GeneratorExecuteMsg::MigrateProxy {
+        lp_token: token_info(&lp_cny_eur_instance),
+        new_proxy: new_proxy_instance.to_string(),
+    };
+    assert_eq!(
+        app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Unauthorized"
+    );
+
+    // can't migrate if the new proxy isn't allowed
+    assert_eq!(
+        app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "Reward proxy not allowed!"
+    );
+
+    let msg = GeneratorExecuteMsg::SetAllowedRewardProxies {
+        proxies: vec![
+            old_proxy_instance.to_string(),
+            new_proxy_instance.to_string(),
+            eur_usd_proxy_instance.to_string(),
+        ],
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = GeneratorExecuteMsg::MigrateProxy {
+        lp_token: token_info(&lp_cny_eur_instance),
+        new_proxy: old_proxy_instance.to_string(),
+    };
+    assert_eq!(
+        app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "The pool already uses the reward proxy!"
+    );
+
+    // can't migrate to the proxy of another LP token
+    let msg = GeneratorExecuteMsg::MigrateProxy {
+        lp_token: token_info(&lp_cny_eur_instance),
+        new_proxy: eur_usd_proxy_instance.to_string(),
+    };
+    assert_eq!(
+        app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+            .unwrap_err()
+            .to_string(),
+        "The reward proxy stakes another LP token!"
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = Cw20ExecuteMsg::Send {
+        contract: mirror_staking_instance.to_string(),
+        msg: to_binary(&MirrorStakingHookMsg::DepositReward {
+            rewards: vec![(pair_cny_eur_instance.to_string(), Uint128::new(50_000000))],
+        })
+        .unwrap(),
+        amount: Uint128::new(50_000000),
+    };
+
+    mint_tokens(&mut app, &mirror_token_instance, &owner, 50_000000);
+    app.execute_contract(owner.clone(), mirror_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (10_000000, Some(50_000000)),
+    );
+
+    let msg = GeneratorExecuteMsg::MigrateProxy {
+        lp_token: token_info(&lp_cny_eur_instance),
+        new_proxy: new_proxy_instance.to_string(),
+    };
+    app.execute_contract(owner.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    // LP tokens are staked by the new proxy, claimed proxy rewards are moved to it
+    check_token_balance(&mut app, &lp_cny_eur_instance, &generator_instance, 0);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &old_proxy_instance, 0);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &mirror_staking_instance, 10);
+    check_token_balance(&mut app, &mirror_token_instance, &old_proxy_instance, 0);
+    check_token_balance(
+        &mut app,
+        &mirror_token_instance,
+        &new_proxy_instance,
+        50_000000,
+    );
+
+    // pending rewards are kept
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (10_000000, Some(50_000000)),
+    );
+
+    app.update_block(|bi| next_block(bi));
+
+    let msg = Cw20ExecuteMsg::Send {
+        contract: mirror_staking_instance.to_string(),
+        msg: to_binary(&MirrorStakingHookMsg::DepositReward {
+            rewards: vec![(pair_cny_eur_instance.to_string(), Uint128::new(20_000000))],
+        })
+        .unwrap(),
+        amount: Uint128::new(20_000000),
+    };
+
+    mint_tokens(&mut app, &mirror_token_instance, &owner, 20_000000);
+    app.execute_contract(owner.clone(), mirror_token_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_pending_rewards(
+        &mut app,
+        &generator_instance,
+        &lp_cny_eur_instance,
+        USER1,
+        (20_000000, Some(70_000000)),
+    );
+
+    let msg = GeneratorExecuteMsg::Withdraw {
+        lp_token: token_info(&lp_cny_eur_instance),
+        amount: Uint128::new(10),
+    };
+    app.execute_contract(user1.clone(), generator_instance.clone(), &msg, &[])
+        .unwrap();
+
+    check_token_balance(&mut app, &lp_cny_eur_instance, &mirror_staking_instance, 0);
+    check_token_balance(&mut app, &lp_cny_eur_instance, &user1, 10);
+    check_token_balance(&mut app, &astro_token_instance, &user1, 20_000000);
+    check_token_balance(&mut app, &mirror_token_instance, &user1, 70_000000);
+    check_token_balance(&mut app, &mirror_token_instance, &new_proxy_instance, 0);
+}
+
 fn mock_app() -> App {
     let api = MockApi::default();
     let env = mock_env();
@@ -1138,10 +2982,16 @@ fn instantiate_generator(mut app: &mut App, astro_token_instance: &Addr) -> Addr
     let init_msg = GeneratorInstantiateMsg {
         owner: owner.to_string(),
         allowed_reward_proxies: vec![],
-        start_block: Uint64::from(app.block_info().height),
         astro_token: astro_token_instance.to_string(),
-        tokens_per_block: Uint128::new(10_000000),
+        emission_schedule: vec![EmissionPeriod {
+            start_time: Uint64::from(app.block_info().time.seconds()),
+            tokens_per_second: Uint128::new(2_000000),
+        }],
+        // The vesting covers a year of the emission
+        end_time: Some(Uint64::from(app.block_info().time.seconds() + 31536000)),
         vesting_contract: vesting_instance.to_string(),
+        gauge_controller: None,
+        generator_controller: None,
     };
 
     let generator_instance = app
@@ -1185,6 +3035,44 @@ fn instantiate_generator(mut app: &mut App, astro_token_instance: &Addr) -> Addr
     generator_instance
 }
 
+fn instantiate_gauge_controller(app: &mut App, generator: &Addr, xastro_token: &Addr) -> Addr {
+    let gauge_contract = Box::new(ContractWrapper::new(
+        astroport_gauge_controller::contract::execute,
+        astroport_gauge_controller::contract::instantiate,
+        astroport_gauge_controller::contract::query,
+    ));
+
+    let gauge_code_id = app.store_code(gauge_contract);
+
+    let init_msg = GaugeInstantiateMsg {
+        owner: OWNER.to_string(),
+        generator: generator.to_string(),
+        xastro_token: xastro_token.to_string(),
+        epoch_length: None,
+        total_alloc_point: Uint64::new(1000),
+    };
+
+    app.instantiate_contract(
+        gauge_code_id,
+        Addr::unchecked(OWNER),
+        &init_msg,
+        &[],
+        "Gauge controller",
+        None,
+    )
+    .unwrap()
+}
+
+fn lock_xastro(app: &mut App, xastro_token: &Addr, gauge: &Addr, user: &Addr, amount: u128) {
+    let msg = Cw20ExecuteMsg::Send {
+        contract: gauge.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&GaugeHookMsg::Lock {}).unwrap(),
+    };
+    app.execute_contract(user.clone(), xastro_token.clone(), &msg, &[])
+        .unwrap();
+}
+
 fn instantiate_mirror_protocol(
     app: &mut App,
     token_code_id: u64,
@@ -1288,7 +3176,7 @@ fn register_lp_tokens_in_generator(
         let msg = GeneratorExecuteMsg::Add {
             alloc_point: Uint64::from(100u64),
             reward_proxy: reward_proxy.map(|v| v.to_string()),
-            lp_token: (*lp).clone(),
+            lp_token: token_info(lp),
         };
         app.execute_contract(
             Addr::unchecked(OWNER),
@@ -1300,6 +3188,25 @@ fn register_lp_tokens_in_generator(
     }
 }
 
+fn allow_external_rewards(app: &mut App, generator_instance: &Addr, rewards: &[AssetInfo]) {
+    let msg = GeneratorExecuteMsg::SetAllowedExternalRewards {
+        rewards: rewards.to_vec(),
+    };
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        generator_instance.clone(),
+        &msg,
+        &[],
+    )
+    .unwrap();
+}
+
+fn token_info(contract_addr: &Addr) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: contract_addr.clone(),
+    }
+}
+
 fn mint_tokens(app: &mut App, token: &Addr, recipient: &Addr, amount: u128) {
     let msg = Cw20ExecuteMsg::Mint {
         recipient: recipient.to_string(),
@@ -1344,7 +3251,7 @@ fn check_pending_rewards(
     expected: (u128, Option<u128>),
 ) {
     let msg = GeneratorQueryMsg::PendingToken {
-        lp_token: token.to_owned(),
+        lp_token: token_info(token),
         user: Addr::unchecked(depositor),
     };
 
@@ -8,7 +8,7 @@ use crate::error::ContractError;
 use crate::state::{Config, CONFIG};
 use astroport::asset::addr_validate_to_lower;
 use astroport::generator_proxy::{
-    CallbackMsg, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    CallbackMsg, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
 use cw2::set_contract_version;
 use mirror_protocol::staking::{
@@ -280,6 +280,13 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             let config = CONFIG.load(deps.storage)?;
             to_binary(&config.reward_token_addr)
         }
+        QueryMsg::Config {} => to_binary(&ConfigResponse {
+            generator_contract_addr: cfg.generator_contract_addr,
+            pair_addr: cfg.pair_addr,
+            lp_token_addr: cfg.lp_token_addr,
+            reward_contract_addr: cfg.reward_contract_addr,
+            reward_token_addr: cfg.reward_token_addr,
+        }),
     }
 }
 
@@ -0,0 +1,130 @@
+use crate::asset::AssetInfo;
+use cosmwasm_std::{Addr, Uint128, Uint64};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Sum of vote weights of a user in basis points
+pub const VOTE_WEIGHT_BPS: u16 = 10000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub owner: String,
+    /// Generator contract which alloc points are tuned, the gauge controller must be set as its
+    /// generator controller
+    pub generator: String,
+    /// xASTRO token of the staking contract, votes are weighted by xASTRO locked by voters
+    pub xastro_token: String,
+    /// Time in seconds between tunes, a week by default, must be positive. Locked xASTRO can be
+    /// unlocked an epoch after the last lock
+    pub epoch_length: Option<u64>,
+    /// Alloc points which are split between pools on tune
+    pub total_alloc_point: Uint64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Replaces votes of the sender by weights of LP tokens in basis points, votes are weighted
+    /// by xASTRO locked by the sender
+    Vote {
+        votes: Vec<(AssetInfo, u16)>,
+    },
+    /// Sets alloc points of the whitelisted pools which are not removed in the generator by
+    /// the current votes
+    Tune {},
+    /// Locks xASTRO sent with `Cw20HookMsg::Lock`
+    Receive(Cw20ReceiveMsg),
+    /// Unlocks xASTRO of the sender, votes lose the unlocked voting power. The voter is removed
+    /// if all xASTRO is unlocked
+    Unlock {
+        amount: Uint128,
+    },
+    /// Epoch length must be positive
+    UpdateConfig {
+        epoch_length: Option<u64>,
+        total_alloc_point: Option<Uint64>,
+    },
+    UpdateWhitelist {
+        add: Option<Vec<AssetInfo>>,
+        remove: Option<Vec<AssetInfo>>,
+    },
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    DropOwnershipProposal {},
+    ClaimOwnership {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Adds sent xASTRO to the voting power of the sender, it can be unlocked an epoch later
+    Lock {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    UserVotes {
+        user: String,
+    },
+    TuneInfo {},
+    /// Returns weights of the whitelisted pools by the current votes
+    PoolWeights {},
+    /// Returns xASTRO locked by the user and by all voters, the generator boosts ASTRO rewards
+    /// by them
+    VotingPower {
+        user: String,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub generator: Addr,
+    pub xastro_token: Addr,
+    pub epoch_length: u64,
+    pub total_alloc_point: Uint64,
+    pub whitelist: Vec<AssetInfo>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserVotesResponse {
+    /// xASTRO locked by the user
+    pub voting_power: Uint128,
+    /// Time when locked xASTRO can be unlocked
+    pub unlock_time: u64,
+    pub votes: Vec<(AssetInfo, u16)>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TuneInfoResponse {
+    /// Time of the last tune
+    pub tune_time: u64,
+    /// Alloc points which are set to the generator on the last tune
+    pub alloc_points: Vec<(AssetInfo, Uint64)>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolWeightsResponse {
+    pub weights: Vec<(AssetInfo, Uint128)>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotingPowerResponse {
+    /// xASTRO locked by the user
+    pub voting_power: Uint128,
+    /// xASTRO locked by all voters
+    pub total_voting_power: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
@@ -1,3 +1,4 @@
+use crate::asset::{Asset, AssetInfo};
 use cosmwasm_std::{Addr, Uint128, Uint64};
 use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
@@ -7,10 +8,26 @@ use serde::{Deserialize, Serialize};
 pub struct InstantiateMsg {
     pub owner: String,
     pub astro_token: String,
-    pub tokens_per_block: Uint128,
-    pub start_block: Uint64,
+    /// ASTRO emission periods sorted by start time
+    pub emission_schedule: Vec<EmissionPeriod>,
+    /// Time in seconds when ASTRO emission ends, the last period lasts forever if it is not set
+    /// and must not emit ASTRO then
+    pub end_time: Option<Uint64>,
     pub allowed_reward_proxies: Vec<String>,
     pub vesting_contract: String,
+    /// Gauge controller contract, ASTRO rewards are boosted by the share of xASTRO locked by the
+    /// user in it if it is set
+    pub gauge_controller: Option<String>,
+    /// Contract which may set alloc points of the pools besides the owner, e.g. the gauge
+    /// controller
+    pub generator_controller: Option<String>,
+}
+
+/// ASTRO tokens emitted per second from the start time until the start of the next period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionPeriod {
+    pub start_time: Uint64,
+    pub tokens_per_second: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -18,37 +35,114 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     UpdateConfig {
         vesting_contract: Option<String>,
+        gauge_controller: Option<String>,
+        generator_controller: Option<String>,
     },
+    /// Adds a pool of the CW20 or native LP token, reward proxies are supported by CW20 LP
+    /// tokens only
     Add {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         alloc_point: Uint64,
         reward_proxy: Option<String>,
     },
+    /// Sets the alloc point of the active pool, the owner or the generator controller can
+    /// execute it
     Set {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         alloc_point: Uint64,
     },
+    /// Sets alloc points of several active pools, pools are updated once. The owner or the
+    /// generator controller can execute it
+    SetAllocPoints {
+        pools: Vec<(AssetInfo, Uint64)>,
+    },
+    /// Stops ASTRO emission of the pool and excludes it from the pool list, users can still
+    /// withdraw LP tokens and claim rewards accrued before the removal
+    RemovePool {
+        lp_token: AssetInfo,
+    },
     MassUpdatePools {},
     UpdatePool {
-        lp_token: Addr,
+        lp_token: AssetInfo,
     },
     Withdraw {
-        lp_token: Addr,
+        lp_token: AssetInfo,
         amount: Uint128,
     },
+    /// Withdraws all LP tokens of the sender without rewards, a penalty is charged from LP
+    /// tokens which are locked yet and sent to the owner
     EmergencyWithdraw {
-        lp_token: Addr,
+        lp_token: AssetInfo,
+    },
+    /// Claims pending ASTRO and proxy rewards of the sender in the given pools, rewards are
+    /// sent to the recipient if it is set, otherwise to the sender
+    ClaimRewards {
+        lp_tokens: Vec<AssetInfo>,
+        recipient: Option<String>,
+    },
+    /// Adds a schedule of external rewards to the pool, the reward is distributed linearly
+    /// from the start to the end time. Anyone can fund a schedule of the allowed external
+    /// reward, tokens are transferred from
+    /// the sender by allowance (or sent with the AddRewardSchedule hook), native coins must be
+    /// sent with the message
+    AddRewardSchedule {
+        lp_token: AssetInfo,
+        reward: Asset,
+        start_time: Uint64,
+        end_time: Uint64,
+    },
+    /// Cancels the schedules of the external reward in the pool and sends the not distributed
+    /// amount to the recipient, it is left in the generator if the recipient isn't set, e.g.
+    /// when the token can't be transferred. Distributed rewards stay claimable unless the reward
+    /// is removed from the pool. Only owner can execute it
+    CancelRewardSchedules {
+        lp_token: AssetInfo,
+        reward_info: AssetInfo,
+        recipient: Option<String>,
+        remove_reward: bool,
+    },
+    /// Recalculates the boost of the user in the given pools by the current locked xASTRO share,
+    /// pending rewards are sent to the user
+    CheckpointUserBoost {
+        lp_tokens: Vec<AssetInfo>,
+        user: Option<String>,
     },
     SetAllowedRewardProxies {
         proxies: Vec<String>,
     },
+    /// Sets tokens which can be external rewards of the pools, rewards which are already added
+    /// aren't affected. Only owner can execute it
+    SetAllowedExternalRewards {
+        rewards: Vec<AssetInfo>,
+    },
     SendOrphanProxyReward {
         recipient: String,
-        lp_token: String,
+        lp_token: AssetInfo,
+    },
+    /// Moves the pool's LP tokens and proxy rewards from the current reward proxy to the new
+    /// allowed one, the new proxy must give the same reward token
+    MigrateProxy {
+        lp_token: AssetInfo,
+        new_proxy: String,
     },
     Receive(Cw20ReceiveMsg),
-    SetTokensPerBlock {
-        amount: Uint128,
+    /// Migrates the next batch of users after the migration from block based emissions, the
+    /// contract accepts nothing else until all users are migrated. Anyone can execute it
+    MigrateUsers {
+        limit: Option<u32>,
+    },
+    /// Deposits native LP tokens sent with the message, they are deposited for the beneficiary
+    /// if it is set, otherwise for the sender. Locked deposits get the multiplier of ASTRO
+    /// rewards as `Cw20HookMsg::DepositLocked`
+    Deposit {
+        beneficiary: Option<String>,
+        lock_duration: Option<LockDuration>,
+    },
+    /// Replaces the emission schedule, ASTRO emitted from now to the end and ASTRO distributed
+    /// but not claimed yet must not exceed ASTRO left in the vesting account of the generator
+    SetEmissionSchedule {
+        emission_schedule: Vec<EmissionPeriod>,
+        end_time: Option<Uint64>,
     },
     ProposeNewOwner {
         owner: String,
@@ -62,11 +156,38 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     PoolLength {},
-    Deposit { lp_token: Addr, user: Addr },
-    PendingToken { lp_token: Addr, user: Addr },
+    Deposit {
+        lp_token: AssetInfo,
+        user: Addr,
+    },
+    PendingToken {
+        lp_token: AssetInfo,
+        user: Addr,
+    },
     Config {},
-    RewardInfo { lp_token: Addr },
-    OrphanProxyRewards { lp_token: Addr },
+    RewardInfo {
+        lp_token: AssetInfo,
+    },
+    OrphanProxyRewards {
+        lp_token: AssetInfo,
+    },
+    LockedPositions {
+        lp_token: AssetInfo,
+        user: Addr,
+    },
+    /// Returns positions of the user in all pools in ascending order of LP tokens
+    UserPositions {
+        user: Addr,
+        start_after: Option<AssetInfo>,
+        limit: Option<u32>,
+    },
+    /// Returns pools in ascending order of LP tokens
+    Pools {
+        start_after: Option<AssetInfo>,
+        limit: Option<u32>,
+    },
+    /// Returns LP tokens of the pools which are not removed
+    ActivePools {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -74,35 +195,146 @@ pub struct PoolLengthResponse {
     pub length: usize,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivePoolsResponse {
+    pub pools: Vec<AssetInfo>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PendingTokenResponse {
     pub pending: Uint128,
     pub pending_on_proxy: Option<Uint128>,
+    /// Pending external rewards of the pool
+    pub pending_external: Vec<Asset>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct RewardInfoResponse {
     pub base_reward_token: Addr,
     pub proxy_reward_token: Option<Addr>,
+    pub external_reward_tokens: Vec<AssetInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserPositionResponse {
+    pub lp_token: AssetInfo,
+    pub amount: Uint128,
+    pub pending: Uint128,
+    pub pending_on_proxy: Option<Uint128>,
+    pub pending_external: Vec<Asset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserPositionsResponse {
+    pub positions: Vec<UserPositionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolInfoResponse {
+    pub lp_token: AssetInfo,
+    pub alloc_point: Uint64,
+    /// ASTRO emitted to the pool per second at the moment
+    pub astro_per_second: Uint128,
+    /// LP tokens deposited to the pool
+    pub lp_supply: Uint128,
+    /// Sum of working amounts of the pool users, ASTRO is distributed pro rata to it
+    pub working_supply: Uint128,
+    pub reward_proxy: Option<Addr>,
+    /// Removed pools don't get ASTRO and new deposits
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolsResponse {
+    pub pools: Vec<PoolInfoResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedPositionsResponse {
+    pub positions: Vec<LockedPosition>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: Addr,
     pub astro_token: Addr,
-    pub tokens_per_block: Uint128,
+    pub emission_schedule: Vec<EmissionPeriod>,
+    pub end_time: Option<Uint64>,
     pub total_alloc_point: Uint64,
-    pub start_block: Uint64,
     pub allowed_reward_proxies: Vec<Addr>,
     pub vesting_contract: Addr,
+    pub gauge_controller: Option<Addr>,
+    pub generator_controller: Option<Addr>,
+    pub allowed_external_rewards: Vec<AssetInfo>,
 }
 
+/// Migration from block based emissions, pools are rewarded per block up to the migration
+/// block and per second from then on
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// ASTRO emission periods sorted by start time
+    pub emission_schedule: Vec<EmissionPeriod>,
+    /// Time in seconds when ASTRO emission ends
+    pub end_time: Option<Uint64>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     Deposit {},
     DepositFor(Addr),
+    /// Deposits LP tokens which can't be withdrawn for the lock duration
+    DepositLocked {
+        lock_duration: LockDuration,
+    },
+    DepositLockedFor {
+        beneficiary: Addr,
+        lock_duration: LockDuration,
+    },
+    /// Adds a schedule of the sent tokens to the external rewards of the pool
+    AddRewardSchedule {
+        lp_token: AssetInfo,
+        start_time: Uint64,
+        end_time: Uint64,
+    },
+}
+
+/// Lock duration of deposits, the longer lock gives the higher multiplier of ASTRO rewards
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LockDuration {
+    OneMonth,
+    ThreeMonths,
+    SixMonths,
+    TwelveMonths,
+}
+
+impl LockDuration {
+    /// Lock duration in seconds
+    pub fn seconds(&self) -> u64 {
+        match self {
+            LockDuration::OneMonth => 30 * 86400,
+            LockDuration::ThreeMonths => 91 * 86400,
+            LockDuration::SixMonths => 182 * 86400,
+            LockDuration::TwelveMonths => 365 * 86400,
+        }
+    }
+
+    /// Multiplier of ASTRO rewards in percents
+    pub fn multiplier(&self) -> u64 {
+        match self {
+            LockDuration::OneMonth => 110,
+            LockDuration::ThreeMonths => 125,
+            LockDuration::SixMonths => 150,
+            LockDuration::TwelveMonths => 200,
+        }
+    }
+}
+
+/// LP tokens which can't be withdrawn until the unlock time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockedPosition {
+    pub amount: Uint128,
+    pub lock_duration: LockDuration,
+    pub unlock_time: Uint64,
 }
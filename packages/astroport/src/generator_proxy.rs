@@ -45,6 +45,17 @@ pub enum QueryMsg {
     Reward {},
     PendingToken {},
     RewardInfo {},
+    /// Returns the proxy config, the generator checks the LP token of the proxy by it
+    Config {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub generator_contract_addr: Addr,
+    pub pair_addr: Addr,
+    pub lp_token_addr: Addr,
+    pub reward_contract_addr: Addr,
+    pub reward_token_addr: Addr,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
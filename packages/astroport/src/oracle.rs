@@ -1,27 +1,100 @@
-use crate::asset::AssetInfo;
+use crate::asset::{Asset, AssetInfo};
 use cosmwasm_std::Uint128;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// Default minimal time between price updates in seconds
+pub const DEFAULT_PERIOD: u64 = 86400;
+/// Default number of cumulative price observations kept by the oracle
+pub const DEFAULT_MAX_OBSERVATIONS: u32 = 7;
+/// Maximum number of cumulative price observations kept by the oracle
+pub const MAX_OBSERVATIONS_LIMIT: u32 = 1000;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub factory_contract: String,
-    pub asset_infos: [AssetInfo; 2],
+    /// Pairs registered on instantiate, more can be added with RegisterPair
+    pub pairs: Vec<[AssetInfo; 2]>,
+    /// Minimal time between price updates in seconds
+    pub period: Option<u64>,
+    /// Number of cumulative price observations kept for the Consult window
+    pub max_observations: Option<u32>,
+    /// Maximum time in seconds since the last update of a pair for its price to be consulted,
+    /// defaults to two periods
+    pub max_age: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Update prices
-    Update {},
+    /// Register the factory pair of the given assets, only owner can execute it
+    RegisterPair { asset_infos: [AssetInfo; 2] },
+    /// Update prices of a registered pair
+    Update { asset_infos: [AssetInfo; 2] },
+    /// Update prices of several registered pairs, pairs updated less than a period ago are skipped
+    UpdateMany { pairs: Vec<[AssetInfo; 2]> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     /// Consult
-    Consult { token: AssetInfo, amount: Uint128 },
+    Consult {
+        /// Assets of the registered pair
+        asset_infos: [AssetInfo; 2],
+        token: AssetInfo,
+        amount: Uint128,
+        /// Lookback in seconds, the average price of the last update period is used if not set
+        window: Option<u64>,
+        /// Use the spot price if no TWAP is available yet, disabled by default
+        allow_spot: Option<bool>,
+        /// Use the geometric mean price instead of the arithmetic one, disabled by default
+        geometric: Option<bool>,
+    },
+    /// Price `amount` of the first path asset in the last one through the registered pairs
+    /// of the consecutive path assets
+    ConsultPath {
+        path: Vec<AssetInfo>,
+        amount: Uint128,
+        /// Lookback in seconds of every leg, see Consult
+        window: Option<u64>,
+        /// Maximum time in seconds since the last update of every leg, the config max_age is used if
+        /// not set or if it is lower
+        max_age: Option<u64>,
+        /// Use the geometric mean price of every leg, see Consult
+        geometric: Option<bool>,
+    },
+    /// Time weighted average reserves of the registered pair
+    ConsultLiquidity {
+        asset_infos: [AssetInfo; 2],
+        /// Lookback in seconds, the average reserves of the last update period are used if not set
+        window: Option<u64>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConsultPathResponse {
+    pub amount: Uint128,
+    /// Time in seconds since the last update of the most recently updated leg
+    pub min_observation_age: u64,
+    /// Time in seconds since the last update of the least recently updated leg
+    pub max_observation_age: u64,
 }
 
+// We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct ConsultLiquidityResponse {
+    /// Average reserves in the pair assets order
+    pub assets: [Asset; 2],
+    /// Current LP token supply of the pair
+    pub total_share: Uint128,
+}
+
+/// Limits of the migrated oracle, see InstantiateMsg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    pub period: Option<u64>,
+    pub max_observations: Option<u32>,
+    pub max_age: Option<u64>,
+}
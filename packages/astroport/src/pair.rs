@@ -11,6 +11,36 @@ pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 
 pub const TWAP_PRECISION: u8 = 6;
 
+/// Fixed point one of the log2 price accumulator values
+pub const LOG_PRICE_ONE: u128 = 1_000_000_000_000_000_000;
+
+/// Returns log2(numerator / denominator) multiplied by LOG_PRICE_ONE, both values must be positive
+pub fn log2_ratio(numerator: Uint128, denominator: Uint128) -> i128 {
+    log2(numerator.u128()) - log2(denominator.u128())
+}
+
+fn log2(value: u128) -> i128 {
+    let msb = 127 - value.leading_zeros();
+
+    // mantissa in [1, 2), the low bits of big values don't change the result
+    let shift = msb.saturating_sub(64);
+    let mut mantissa = ((value >> shift) * LOG_PRICE_ONE) >> (msb - shift);
+
+    // every squaring of the mantissa gives the next bit of the fractional part
+    let mut result = msb as i128 * LOG_PRICE_ONE as i128;
+    let mut bit = LOG_PRICE_ONE / 2;
+    while bit > 0 {
+        mantissa = mantissa * mantissa / LOG_PRICE_ONE;
+        if mantissa >= 2 * LOG_PRICE_ONE {
+            mantissa /= 2;
+            result += bit as i128;
+        }
+        bit /= 2;
+    }
+
+    result
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     /// Asset infos
@@ -108,6 +138,16 @@ pub struct CumulativePricesResponse {
     pub total_share: Uint128,
     pub price0_cumulative_last: Uint128,
     pub price1_cumulative_last: Uint128,
+    /// Cumulative log2 of the asset 0 price in asset 1 multiplied by LOG_PRICE_ONE (see `log2_ratio`).
+    /// The accumulated values are signed and wrap around, the difference of two readings
+    /// should be cast to i128. The asset 1 price is the opposite.
+    #[serde(default)]
+    pub log_price_cumulative_last: Uint128,
+    /// Cumulative reserves of both assets, the time weighted sum of the pool amounts
+    #[serde(default)]
+    pub reserve0_cumulative_last: Uint128,
+    #[serde(default)]
+    pub reserve1_cumulative_last: Uint128,
 }
 
 /// We currently take no arguments for migrations
@@ -1,16 +1,37 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
-use crate::asset::AssetInfo;
+use crate::asset::{Asset, AssetInfo};
 
 pub const MAX_SWAP_OPERATIONS: usize = 50;
 
+/// Default maximum number of hops in a route found by FindBestRoute
+pub const DEFAULT_MAX_ROUTE_HOPS: u32 = 3;
+/// Default maximum number of candidate routes simulated by FindBestRoute
+pub const DEFAULT_MAX_ROUTE_CANDIDATES: u32 = 10;
+/// Default maximum number of factory pages read by FindBestRoute
+pub const DEFAULT_MAX_PAIRS_PAGES: u32 = 10;
+
+/// Upper bound of max_route_hops, the route search grows exponentially with hops
+pub const MAX_ROUTE_HOPS: u32 = 4;
+/// Upper bound of max_route_candidates
+pub const MAX_ROUTE_CANDIDATES: u32 = 20;
+/// Upper bound of max_pairs_pages
+pub const MAX_PAIRS_PAGES: u32 = 30;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
+    pub owner: String,
     pub astroport_factory: String,
+    /// Maximum number of hops in a route found by FindBestRoute
+    pub max_route_hops: Option<u32>,
+    /// Maximum number of candidate routes simulated by FindBestRoute
+    pub max_route_candidates: Option<u32>,
+    /// Maximum number of factory pages of pairs read by FindBestRoute
+    pub max_pairs_pages: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -23,20 +44,96 @@ pub enum SwapOperation {
     AstroSwap {
         offer_asset_info: AssetInfo,
         ask_asset_info: AssetInfo,
+        /// Forwarded to the pair Swap message, see `astroport::pair::ExecuteMsg::Swap`
+        belief_price: Option<Decimal>,
+        /// Forwarded to the pair Swap message, the route max_spread is used if not set
+        max_spread: Option<Decimal>,
+    },
+    /// Swap through a whitelisted external pair implementing the `astroport::pair` swap interface
+    ExternalSwap {
+        venue: Addr,
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
     },
 }
 
 impl SwapOperation {
+    /// Use the given max_spread if the operation doesn't set its own
+    pub fn with_default_max_spread(self, default_max_spread: Option<Decimal>) -> Self {
+        match self {
+            SwapOperation::AstroSwap {
+                offer_asset_info,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+            } => SwapOperation::AstroSwap {
+                offer_asset_info,
+                ask_asset_info,
+                belief_price,
+                max_spread: max_spread.or(default_max_spread),
+            },
+            SwapOperation::ExternalSwap {
+                venue,
+                offer_asset_info,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+            } => SwapOperation::ExternalSwap {
+                venue,
+                offer_asset_info,
+                ask_asset_info,
+                belief_price,
+                max_spread: max_spread.or(default_max_spread),
+            },
+            operation => operation,
+        }
+    }
+
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { offer_denom, .. } => AssetInfo::NativeToken {
+                denom: offer_denom.clone(),
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info, ..
+            }
+            | SwapOperation::ExternalSwap {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::NativeSwap { ask_denom, .. } => AssetInfo::NativeToken {
                 denom: ask_denom.clone(),
             },
-            SwapOperation::AstroSwap { ask_asset_info, .. } => ask_asset_info.clone(),
+            SwapOperation::AstroSwap { ask_asset_info, .. }
+            | SwapOperation::ExternalSwap { ask_asset_info, .. } => ask_asset_info.clone(),
         }
     }
 }
 
+/// A single path of a split swap and the share of the offer amount routed through it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub operations: Vec<SwapOperation>,
+    pub offer_share: Decimal,
+    /// Default max_spread for the route operations
+    pub max_spread: Option<Decimal>,
+}
+
+/// External pair allowed in ExternalSwap operations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalVenue {
+    pub venue: String,
+    /// Contract answering the `astroport::pair` simulation queries for the venue,
+    /// the venue itself is queried if not set
+    pub simulation_adapter: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -46,7 +143,44 @@ pub enum ExecuteMsg {
         operations: Vec<SwapOperation>,
         minimum_receive: Option<Uint128>,
         to: Option<Addr>,
+        /// Default max_spread for the operations
+        max_spread: Option<Decimal>,
+        /// Executed on the receiver contract with the swap output
+        callback: Option<Binary>,
+    },
+    /// Split the offer amount across several routes and execute each of them
+    ExecuteSplitSwap {
+        routes: Vec<SwapRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<Addr>,
     },
+    /// Swap only as much of the offer asset as needed to receive ask_amount,
+    /// the rest of max_offer is sent back to the sender
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        max_offer: Uint128,
+        to: Option<Addr>,
+        /// Default max_spread for the operations
+        max_spread: Option<Decimal>,
+    },
+    /// Update the FindBestRoute limits, only owner can execute it
+    UpdateConfig {
+        max_route_hops: Option<u32>,
+        max_route_candidates: Option<u32>,
+        max_pairs_pages: Option<u32>,
+    },
+    /// Whitelist external venues or remove them from the whitelist, only owner can execute it
+    UpdateExternalVenues {
+        add: Vec<ExternalVenue>,
+        remove: Vec<String>,
+    },
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    DropOwnershipProposal {},
+    ClaimOwnership {},
 
     /// Internal use
     /// Swap all offer tokens to ask token
@@ -62,6 +196,15 @@ pub enum ExecuteMsg {
         minimum_receive: Uint128,
         receiver: String,
     },
+    /// Internal use
+    /// Send the swap output to the receiver contract along with the callback message
+    SendWithCallback {
+        asset_info: AssetInfo,
+        prev_balance: Uint128,
+        minimum_receive: Option<Uint128>,
+        receiver: String,
+        callback: Binary,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -71,6 +214,20 @@ pub enum Cw20HookMsg {
         operations: Vec<SwapOperation>,
         minimum_receive: Option<Uint128>,
         to: Option<String>,
+        max_spread: Option<Decimal>,
+        callback: Option<Binary>,
+    },
+    ExecuteSplitSwap {
+        routes: Vec<SwapRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+    },
+    /// The sent amount is used as max_offer
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        to: Option<String>,
+        max_spread: Option<Decimal>,
     },
 }
 
@@ -82,12 +239,39 @@ pub enum QueryMsg {
         offer_amount: Uint128,
         operations: Vec<SwapOperation>,
     },
+    /// Same as SimulateSwapOperations with the tax, commission and spread of every operation
+    SimulateSwapOperationsDetailed {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+    SimulateSplitSwap {
+        offer_amount: Uint128,
+        routes: Vec<SwapRoute>,
+    },
+    SimulateReverseSwapOperations {
+        ask_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+    /// Find the route through Astroport pairs with the biggest output
+    FindBestRoute {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+    },
+    ExternalVenues {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    pub owner: String,
     pub astroport_factory: String,
+    pub max_route_hops: u32,
+    pub max_route_candidates: u32,
+    pub max_pairs_pages: u32,
 }
 
 // We define a custom struct for each query response
@@ -96,6 +280,68 @@ pub struct SimulateSwapOperationsResponse {
     pub amount: Uint128,
 }
 
-/// We currently take no arguments for migrations
+/// Simulation of a single swap operation, tax amounts are in the offer and ask assets respectively
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct SwapOperationSimulation {
+    /// Amount swapped, the offer tax is already deducted
+    pub offer_amount: Uint128,
+    /// Tax deducted when sending the offer asset to the pair
+    pub offer_tax_amount: Uint128,
+    /// Amount received, the return tax is already deducted
+    pub return_amount: Uint128,
+    /// Tax deducted when the pair sends the ask asset
+    pub return_tax_amount: Uint128,
+    pub commission_amount: Uint128,
+    pub spread_amount: Uint128,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateSwapOperationsDetailedResponse {
+    /// Simulated output of the last operation
+    pub amount: Uint128,
+    /// Simulation of every operation, in the order they were given
+    pub operations: Vec<SwapOperationSimulation>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateSplitSwapResponse {
+    /// Simulated output of every route, in the order they were given
+    pub route_amounts: Vec<Uint128>,
+    /// Total simulated output of all routes
+    pub amount: Uint128,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateReverseSwapOperationsResponse {
+    /// Offer amount required to receive the ask amount
+    pub offer_amount: Uint128,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FindBestRouteResponse {
+    pub operations: Vec<SwapOperation>,
+    /// Simulated output of the route
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalVenueInfo {
+    pub venue: Addr,
+    pub simulation_adapter: Addr,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExternalVenuesResponse {
+    pub venues: Vec<ExternalVenueInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Owner of the router, v1.0.0 has no owner
+    pub owner: String,
+}
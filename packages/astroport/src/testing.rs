@@ -5,6 +5,7 @@ use crate::querier::{
 };
 
 use crate::factory::PairType;
+use crate::pair::{log2_ratio, LOG_PRICE_ONE};
 use crate::DecimalCheckedOps;
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
 use cosmwasm_std::{to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Uint128, WasmMsg};
@@ -352,3 +353,28 @@ fn test_decimal_checked_ops() {
             .is_err()
     );
 }
+
+#[test]
+fn log2_ratio_values() {
+    let one = LOG_PRICE_ONE as i128;
+
+    assert_eq!(log2_ratio(Uint128::new(8), Uint128::new(1)), 3 * one);
+    assert_eq!(log2_ratio(Uint128::new(1), Uint128::new(8)), -3 * one);
+    assert_eq!(log2_ratio(Uint128::new(123), Uint128::new(123)), 0);
+    assert_eq!(
+        log2_ratio(Uint128::new(1 << 100), Uint128::new(1)),
+        100 * one
+    );
+
+    // log2(3) = 1.584962500721156181...
+    let res = log2_ratio(Uint128::new(3_000000), Uint128::new(1_000000));
+    assert!((res - 1_584962500721156181).abs() < 1_000000);
+
+    // the directions are reciprocal
+    let res = log2_ratio(Uint128::new(1_000000), Uint128::new(3_000000));
+    assert!((res + 1_584962500721156181).abs() < 1_000000);
+    assert_eq!(
+        log2_ratio(Uint128::new(5_000000), Uint128::new(7_000000)),
+        -log2_ratio(Uint128::new(7_000000), Uint128::new(5_000000))
+    );
+}
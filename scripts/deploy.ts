@@ -126,6 +126,7 @@ async function main() {
             wallet,
             join(ARTIFACTS_PATH, 'astroport_router.wasm'),
             {
+                owner: wallet.key.accAddress,
                 astroport_factory: network.factoryAddress,
             },
         )
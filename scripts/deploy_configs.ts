@@ -10,8 +10,14 @@ export const configDefault: Config = {
             owner: '',
             allowed_reward_proxies: [],
             astro_token: '',
-            start_block: '1',
-            tokens_per_block: String(10000000),
+            emission_schedule: [
+                {
+                    start_time: '1',
+                    tokens_per_second: String(2000000),
+                }
+            ],
+            // a year of the emission is vested to the generator
+            end_time: String(1 + 31536000),
             vesting_contract: '',
         }
     },
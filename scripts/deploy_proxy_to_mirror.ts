@@ -95,7 +95,11 @@ async function main() {
             add: {
                 alloc_point: String(100),
                 reward_proxy: network.proxy_to_mirror,
-                lp_token: pool_info.liquidity_token
+                lp_token: {
+                    token: {
+                        contract_addr: pool_info.liquidity_token
+                    }
+                }
             }
         })
 
@@ -20,12 +20,19 @@ interface GeneratorInitMsg {
         owner: string
         allowed_reward_proxies: string[],
         astro_token: string,
-        start_block: string,
-        tokens_per_block: string,
+        emission_schedule: EmissionPeriod[],
+        end_time?: string,
         vesting_contract: string,
+        gauge_controller?: string,
+        generator_controller?: string,
     }
 }
 
+interface EmissionPeriod {
+    start_time: string,
+    tokens_per_second: string,
+}
+
 type PairType = {
     xyk: {}
 } | {